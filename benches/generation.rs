@@ -0,0 +1,39 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use vintergatan::model::generator::GeneratorType;
+use vintergatan::model::grid::GridType;
+use vintergatan::model::mask::Mask;
+use vintergatan::model::symmetry::Symmetry;
+use vintergatan::model::topology::Topology;
+
+/// Generates universes of increasing size from a fixed seed with every generator,
+/// so that runs and generators can be compared.
+///
+/// On a single core, climbing takes about 0.04s for 10×10, 0.2s for 20×20 and 0.6s for 30×30,
+/// down from 0.08s, 0.5s and 1.4s before the score was updated incrementally.
+/// Most of it is spent taking steps, and a quarter on the totals of the galaxies they change.
+fn generation(c: &mut Criterion) {
+    for generator in GeneratorType::ALL {
+        let mut group = c.benchmark_group(format!("generation/{generator}"));
+        group.sample_size(10);
+        for size in [10, 20, 30] {
+            group.bench_with_input(BenchmarkId::from_parameter(size), &size, |b, &size| {
+                b.iter(|| {
+                    generator
+                        .generator()
+                        .generate(
+                            Mask::full(size, size),
+                            Symmetry::default(),
+                            Topology::default(),
+                            GridType::default(),
+                            42,
+                        )
+                        .unwrap()
+                })
+            });
+        }
+        group.finish();
+    }
+}
+
+criterion_group!(benches, generation);
+criterion_main!(benches);
@@ -1,29 +1,73 @@
+use crate::model::board_error::Problem;
 use crate::model::border::Border;
-use crate::model::history::HistoryEntry;
+use crate::model::daily::{generate_daily, is_daily_completed, mark_daily_completed};
+use crate::model::date::Date;
+use crate::model::generation::{GenerationRequest, GenerationResponse};
+use crate::model::grid::GridType;
+use crate::model::mask::{Mask, Shape};
+use crate::model::objective::CenterColor;
 use crate::model::position::Position;
+use crate::model::puzzle_link::PuzzleLink;
+use crate::model::saved_game::SavedGame;
 use crate::model::state::State;
-use itertools::Itertools;
+use crate::model::statistics::Statistics;
+use crate::model::storage::Storage;
+use crate::model::symmetry::Symmetry;
+use crate::model::timer::format_duration;
+use crate::model::topology::Topology;
+use crate::model::universe::Universe;
+use crate::svg::{centroid, format_points, Layout};
 use std::cell::RefCell;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::rc::Rc;
 use web_sys::wasm_bindgen::closure::Closure;
 use web_sys::wasm_bindgen::{JsCast, JsValue};
-use web_sys::{window, Document, Element, Event};
+use web_sys::{window, Document, Element, Event, HtmlSelectElement, MessageEvent, Worker};
 
 const VIEW_BOX_SIZE: f64 = 100.0;
-const WALL_CELL_RATIO: f64 = 0.1;
 const SIZE: i32 = 10;
-const CELL_SIZE: f64 = VIEW_BOX_SIZE / (SIZE as f64 + (SIZE as f64 + 1.0) * WALL_CELL_RATIO);
-const WALL_SIZE: f64 = CELL_SIZE * WALL_CELL_RATIO;
 const SVG_NAMESPACE: Option<&str> = Some("http://www.w3.org/2000/svg");
 const WALL_COLOR: &str = "#5a5a5a";
+/// Milliseconds between updates of the timer
+const TIMER_INTERVAL: i32 = 500;
+/// The script that Trunk generates to start the worker binary
+const WORKER_SCRIPT: &str = "./worker_loader.js";
+/// How many seeds are tried before giving up on generating a game on a board
+const GENERATION_ATTEMPTS: usize = 5;
 
 pub struct App {
     state: State,
-    border_elements: HashMap<Border, Element>,
+    layout: Layout,
+    border_elements: HashMap<Border, Vec<Element>>,
     galaxy_center_elements: HashMap<Position, Element>,
     cell_elements: HashMap<Position, Element>,
     new_game_button: Element,
+    /// Explains the errors found by the last check
+    report: Element,
+    /// Lines between centers that share a region, showing where walls are missing
+    merged_links: Element,
+    /// Shows the time spent on the current puzzle
+    timer: Element,
+    pause_button: Element,
+    /// Covers the board while the game is paused, and summarises the game when it's completed
+    overlay: Element,
+    /// The records of every completed game, kept in local storage
+    statistics: Statistics,
+    /// Shows streaks, average times and best times per size
+    stats_panel: Element,
+    stats_visible: bool,
+    /// Generates puzzles in the background, if the browser could start it
+    worker: Option<Worker>,
+    /// The puzzle generated in the background for the next game
+    next_state: Option<State>,
+    /// Whether the worker is generating a puzzle
+    generating: bool,
+    /// Whether the player has asked for a new game that the worker hasn't finished yet
+    awaiting_new_game: bool,
+    /// Whether generating a new game on the board failed, in which case the current game is kept
+    generation_failed: bool,
+    /// Shown while waiting for the worker
+    spinner: Element,
     document: Document,
     svg: Element,
 }
@@ -33,13 +77,44 @@ impl App {
         let document = window().unwrap().document().unwrap();
         let body = document.body().unwrap();
 
+        let state = if let Some(state) = get_linked_state() {
+            state
+        } else if let Some(state) = get_saved_state() {
+            state
+        } else if is_daily_mode() {
+            generate_daily(Date::from_timestamp(js_sys::Date::now()))
+        } else {
+            let (mask, topology, grid) = get_board();
+            generate_or_default(mask, topology, grid)
+        };
+        let layout = Layout::new(
+            state.board.get_surface(),
+            state.board.get_width(),
+            state.board.get_height(),
+            VIEW_BOX_SIZE,
+        );
         let app = Rc::new(RefCell::new(App {
-            state: State::generate(SIZE as usize),
+            state,
+            layout,
             border_elements: HashMap::new(),
             galaxy_center_elements: HashMap::new(),
             cell_elements: HashMap::new(),
             document: window().unwrap().document().unwrap(),
             new_game_button: document.create_element_ns(SVG_NAMESPACE, "svg")?,
+            report: document.create_element("ul")?,
+            merged_links: document.create_element_ns(SVG_NAMESPACE, "g")?,
+            timer: document.create_element("div")?,
+            pause_button: document.create_element("button")?,
+            overlay: document.create_element("div")?,
+            statistics: Statistics::load(&LocalStorage),
+            stats_panel: document.create_element("div")?,
+            stats_visible: false,
+            worker: Worker::new(WORKER_SCRIPT).ok(),
+            next_state: None,
+            generating: false,
+            awaiting_new_game: false,
+            generation_failed: false,
+            spinner: document.create_element("div")?,
             svg: document.create_element_ns(SVG_NAMESPACE, "svg")?,
         }));
 
@@ -49,74 +124,50 @@ impl App {
             svg.set_id("board");
             body.append_child(&svg)?;
 
+            let mask = app.borrow().state.board.get_mask().clone();
+
+            let layout = app.borrow().layout;
+
             {
                 // Add cells
-                for row in 0..SIZE {
-                    for col in 0..SIZE {
-                        let p = Position::new(row, col);
-                        let rect = document.create_element_ns(SVG_NAMESPACE, "rect")?;
-                        svg.append_child(&rect)?;
-                        let x = (WALL_SIZE + CELL_SIZE) * col as f64;
-                        let y = (WALL_SIZE + CELL_SIZE) * row as f64;
-                        rect.set_attribute("x", &x.to_string())?;
-                        rect.set_attribute("y", &y.to_string())?;
-                        rect.set_attribute("width", &(CELL_SIZE + 2.0 * WALL_SIZE).to_string())?;
-                        rect.set_attribute("height", &(CELL_SIZE + 2.0 * WALL_SIZE).to_string())?;
-                        rect.set_attribute("class", "cell")?;
-                        app.borrow_mut().cell_elements.insert(p, rect);
-                    }
+                for p in mask.positions() {
+                    let polygon = document.create_element_ns(SVG_NAMESPACE, "polygon")?;
+                    svg.append_child(&polygon)?;
+                    polygon.set_attribute("points", &format_points(&layout.polygon(&p)))?;
+                    polygon.set_attribute("class", "cell")?;
+                    app.borrow_mut().cell_elements.insert(p, polygon);
                 }
             }
 
-            {
-                // Add border rectangle
-                let rect = document.create_element_ns(SVG_NAMESPACE, "rect")?;
-                rect.set_attribute("x", &(WALL_SIZE / 2.0).to_string())?;
-                rect.set_attribute("y", &(WALL_SIZE / 2.0).to_string())?;
-                rect.set_attribute("width", &(VIEW_BOX_SIZE - WALL_SIZE).to_string())?;
-                rect.set_attribute("height", &(VIEW_BOX_SIZE - WALL_SIZE).to_string())?;
-                rect.set_attribute("stroke", WALL_COLOR)?;
-                rect.set_attribute("stroke-width", &WALL_SIZE.to_string())?;
-                rect.set_attribute("fill", "none")?;
-                svg.append_child(&rect)?;
-            }
+            let surface = *app.borrow().state.board.get_surface();
+            let grid = surface.grid();
 
             {
-                // Add vertical walls
-                for row in 0..SIZE {
-                    for col in 0..SIZE - 1 {
-                        let p1 = Position::new(row, col);
-                        let p2 = p1.right();
-                        let border = Border::new(p1, p2);
-                        let wall_svg = create_wall_svg(&document, border)?;
-                        svg.append_child(&wall_svg)?;
-                        {
-                            let app = Rc::clone(&app);
-                            let closure = Closure::<dyn FnMut(_)>::new(move |event: Event| {
-                                let mut app = app.borrow_mut();
-                                app.on_border_click(border).unwrap();
-                            });
-                            wall_svg.add_event_listener_with_callback(
-                                "click",
-                                closure.as_ref().unchecked_ref(),
-                            )?;
-                            closure.forget();
+                // Add the edge of the board, on every side of a cell that faces outside
+                for p in mask.positions() {
+                    for q in grid.adjacent(&p) {
+                        if !mask.contains(&surface.normalize(&q)) {
+                            let line = create_edge_svg(&document, &layout, &p, &q)?;
+                            svg.append_child(&line)?;
                         }
-                        app.borrow_mut().border_elements.insert(border, wall_svg);
                     }
                 }
+            }
 
-                // Horizontal walls
-                for row in 0..SIZE - 1 {
-                    for col in 0..SIZE {
-                        let p1 = Position::new(row, col);
-                        let p2 = p1.down();
-                        let border = Border::new(p1, p2);
-                        let wall_svg = create_wall_svg(&document, border)?;
+            {
+                // Add walls between adjacent cells, walls across a wrapped edge are added on both edges
+                for p1 in mask.positions() {
+                    for p2 in grid.adjacent(&p1) {
+                        let wrapped_p2 = surface.normalize(&p2);
+                        if !mask.contains(&wrapped_p2) || (p2 == wrapped_p2 && p2 < p1) {
+                            continue;
+                        }
+                        let border = Border::new(p1, wrapped_p2);
+                        let wall_svg = create_wall_svg(&document, &layout, &p1, &p2)?;
                         svg.append_child(&wall_svg)?;
                         {
                             let app = Rc::clone(&app);
-                            let closure = Closure::<dyn FnMut(_)>::new(move |event: Event| {
+                            let closure = Closure::<dyn FnMut(_)>::new(move |_event: Event| {
                                 let mut app = app.borrow_mut();
                                 app.on_border_click(border).unwrap();
                             });
@@ -126,11 +177,21 @@ impl App {
                             )?;
                             closure.forget();
                         }
-                        app.borrow_mut().border_elements.insert(border, wall_svg);
+                        app.borrow_mut()
+                            .border_elements
+                            .entry(border)
+                            .or_default()
+                            .push(wall_svg);
                     }
                 }
             }
 
+            {
+                let merged_links = app.borrow().merged_links.clone();
+                merged_links.set_attribute("class", "merged-links")?;
+                svg.append_child(&merged_links)?;
+            }
+
             app.borrow_mut().init_galaxy_centers()?;
 
             let pre = document.create_element("pre")?;
@@ -144,6 +205,23 @@ impl App {
             div.set_attribute("class", "controls")?;
             body.append_child(&div)?;
 
+            {
+                let timer = app.borrow().timer.clone();
+                timer.set_attribute("class", "timer")?;
+                div.append_child(&timer)?;
+                let app = Rc::clone(&app);
+                let closure = Closure::<dyn FnMut()>::new(move || {
+                    app.borrow().render_timer().unwrap();
+                });
+                window()
+                    .unwrap()
+                    .set_interval_with_callback_and_timeout_and_arguments_0(
+                        closure.as_ref().unchecked_ref(),
+                        TIMER_INTERVAL,
+                    )?;
+                closure.forget();
+            }
+
             {
                 let new_game_button = document.create_element("button")?;
                 div.append_child(&new_game_button)?;
@@ -151,7 +229,7 @@ impl App {
                 new_game_button.set_attribute("class", "hidden")?;
                 {
                     let app = Rc::clone(&app);
-                    let closure = Closure::<dyn FnMut(_)>::new(move |event: Event| {
+                    let closure = Closure::<dyn FnMut(_)>::new(move |_event: Event| {
                         app.borrow_mut().on_new_game_click().unwrap();
                     });
                     new_game_button.add_event_listener_with_callback(
@@ -168,7 +246,7 @@ impl App {
                 div.append_child(&check_button)?;
                 check_button.set_text_content(Some("Check"));
                 let app = Rc::clone(&app);
-                let closure = Closure::<dyn FnMut(_)>::new(move |event: Event| {
+                let closure = Closure::<dyn FnMut(_)>::new(move |_event: Event| {
                     app.borrow_mut().on_check_click().unwrap();
                 });
                 check_button
@@ -178,10 +256,10 @@ impl App {
 
             {
                 let undo_button = &document.create_element("button")?;
-                div.append_child(&undo_button)?;
+                div.append_child(undo_button)?;
                 undo_button.set_text_content(Some("Undo"));
                 let app = Rc::clone(&app);
-                let closure = Closure::<dyn FnMut(_)>::new(move |event: Event| {
+                let closure = Closure::<dyn FnMut(_)>::new(move |_event: Event| {
                     app.borrow_mut().on_undo_click().unwrap();
                 });
                 undo_button
@@ -191,17 +269,170 @@ impl App {
 
             {
                 let redo = &document.create_element("button")?;
-                div.append_child(&redo)?;
+                div.append_child(redo)?;
                 redo.set_text_content(Some("Redo"));
                 let app = Rc::clone(&app);
-                let closure = Closure::<dyn FnMut(_)>::new(move |event: Event| {
+                let closure = Closure::<dyn FnMut(_)>::new(move |_event: Event| {
                     app.borrow_mut().on_redo_click().unwrap();
                 });
                 redo.add_event_listener_with_callback("click", closure.as_ref().unchecked_ref())?;
                 closure.forget();
             }
+
+            {
+                let hint_button = &document.create_element("button")?;
+                div.append_child(hint_button)?;
+                hint_button.set_text_content(Some("Hint"));
+                let app = Rc::clone(&app);
+                let closure = Closure::<dyn FnMut(_)>::new(move |_event: Event| {
+                    app.borrow_mut().on_hint_click().unwrap();
+                });
+                hint_button
+                    .add_event_listener_with_callback("click", closure.as_ref().unchecked_ref())?;
+                closure.forget();
+            }
+
+            {
+                let spinner = app.borrow().spinner.clone();
+                div.append_child(&spinner)?;
+            }
+
+            {
+                let pause_button = app.borrow().pause_button.clone();
+                div.append_child(&pause_button)?;
+                let app = Rc::clone(&app);
+                let closure = Closure::<dyn FnMut(_)>::new(move |_event: Event| {
+                    app.borrow_mut().on_pause_click().unwrap();
+                });
+                pause_button
+                    .add_event_listener_with_callback("click", closure.as_ref().unchecked_ref())?;
+                closure.forget();
+            }
+
+            {
+                let report = document.create_element("ul")?;
+                div.append_child(&report)?;
+                report.set_attribute("class", "report hidden")?;
+                app.borrow_mut().report = report;
+            }
+
+            {
+                // Switching modes reloads the page, since the daily puzzle has its own board
+                let today = Date::from_timestamp(js_sys::Date::now());
+                let mode_select = &document.create_element("select")?;
+                div.append_child(mode_select)?;
+                let daily_text = if is_daily_completed(&LocalStorage, today) {
+                    "Daily (solved)"
+                } else {
+                    "Daily"
+                };
+                for (value, text) in [("random", "Random"), ("daily", daily_text)] {
+                    let option = document.create_element("option")?;
+                    option.set_attribute("value", value)?;
+                    option.set_text_content(Some(text));
+                    mode_select.append_child(&option)?;
+                }
+                let mode_select: HtmlSelectElement = mode_select.clone().dyn_into()?;
+                mode_select.set_value(if is_daily_mode() { "daily" } else { "random" });
+                let closure = Closure::<dyn FnMut(_)>::new(move |event: Event| {
+                    let select: HtmlSelectElement = event.target().unwrap().dyn_into().unwrap();
+                    set_query_parameter("mode", &select.value()).unwrap();
+                });
+                mode_select
+                    .add_event_listener_with_callback("change", closure.as_ref().unchecked_ref())?;
+                closure.forget();
+            }
+
+            {
+                let share_button = &document.create_element("button")?;
+                div.append_child(share_button)?;
+                share_button.set_text_content(Some("Share"));
+                let app = Rc::clone(&app);
+                let closure = Closure::<dyn FnMut(_)>::new(move |_event: Event| {
+                    app.borrow().on_share_click().unwrap();
+                });
+                share_button
+                    .add_event_listener_with_callback("click", closure.as_ref().unchecked_ref())?;
+                closure.forget();
+            }
+
+            {
+                let stats_button = &document.create_element("button")?;
+                div.append_child(stats_button)?;
+                stats_button.set_text_content(Some("Stats"));
+                let app = Rc::clone(&app);
+                let closure = Closure::<dyn FnMut(_)>::new(move |_event: Event| {
+                    app.borrow_mut().on_stats_click().unwrap();
+                });
+                stats_button
+                    .add_event_listener_with_callback("click", closure.as_ref().unchecked_ref())?;
+                closure.forget();
+            }
+
+            {
+                let stats_panel = app.borrow().stats_panel.clone();
+                div.append_child(&stats_panel)?;
+            }
+        }
+
+        {
+            let overlay = app.borrow().overlay.clone();
+            body.append_child(&overlay)?;
+            let app = Rc::clone(&app);
+            let closure = Closure::<dyn FnMut(_)>::new(move |event: Event| {
+                let target = event.target().and_then(|t| t.dyn_into::<Element>().ok());
+                let action = target.and_then(|t| t.get_attribute("data-action"));
+                let mut app = app.borrow_mut();
+                match action.as_deref() {
+                    Some("resume") => app.on_pause_click().unwrap(),
+                    Some("new-game") => app.on_new_game_click().unwrap(),
+                    _ => {}
+                }
+            });
+            overlay.add_event_listener_with_callback("click", closure.as_ref().unchecked_ref())?;
+            closure.forget();
+        }
+
+        {
+            let worker = app.borrow().worker.clone();
+            if let Some(worker) = worker {
+                let app_on_message = Rc::clone(&app);
+                let closure = Closure::<dyn FnMut(_)>::new(move |event: MessageEvent| {
+                    app_on_message
+                        .borrow_mut()
+                        .on_puzzle_generated(event)
+                        .unwrap();
+                });
+                worker.set_onmessage(Some(closure.as_ref().unchecked_ref()));
+                closure.forget();
+
+                // A worker that fails to load or to send its answer is replaced by the main thread
+                let app = Rc::clone(&app);
+                let closure = Closure::<dyn FnMut(_)>::new(move |_event: Event| {
+                    app.borrow_mut().on_worker_error().unwrap();
+                });
+                worker.set_onerror(Some(closure.as_ref().unchecked_ref()));
+                worker.set_onmessageerror(Some(closure.as_ref().unchecked_ref()));
+                closure.forget();
+            }
+        }
+
+        {
+            // The time spent since the last move is saved when the player leaves the page
+            let app = Rc::clone(&app);
+            let closure = Closure::<dyn FnMut(_)>::new(move |_event: Event| {
+                app.borrow().save_game();
+            });
+            window()
+                .unwrap()
+                .add_event_listener_with_callback("pagehide", closure.as_ref().unchecked_ref())?;
+            closure.forget();
         }
 
+        app.borrow_mut().state.resume(js_sys::Date::now());
+        app.borrow().render()?;
+        app.borrow_mut().request_next_puzzle()?;
+
         Ok(app)
     }
 
@@ -212,24 +443,14 @@ impl App {
         self.galaxy_center_elements.clear();
 
         // Centers
-        for center in self
-            .state
-            .objective
-            .centers
-            .iter()
-            .cloned()
-            .collect::<Vec<_>>()
-        {
+        for center in &self.state.objective.centers {
             let g = self.document.create_element_ns(SVG_NAMESPACE, "g")?;
             g.set_attribute("class", "galaxy-center")?;
             self.svg.append_child(&g)?;
-            let cx = WALL_SIZE / 2.0
-                + (WALL_SIZE + CELL_SIZE) / 2.0 * (center.position.column + 1) as f64;
-            let cy =
-                WALL_SIZE / 2.0 + (WALL_SIZE + CELL_SIZE) / 2.0 * (center.position.row + 1) as f64;
+            let (cx, cy) = self.layout.center_point(&center.position);
 
             {
-                let r = CELL_SIZE / 2.5 - WALL_SIZE;
+                let r = self.layout.center_radius();
                 let circle = self.document.create_element_ns(SVG_NAMESPACE, "circle")?;
                 circle.set_attribute("cx", &cx.to_string())?;
                 circle.set_attribute("cy", &cy.to_string())?;
@@ -247,6 +468,11 @@ impl App {
                 }
                 g.append_child(&text)?;
             }
+            {
+                // Tooltip explaining the errors of the center's galaxy
+                let title = self.document.create_element_ns(SVG_NAMESPACE, "title")?;
+                g.append_child(&title)?;
+            }
             self.galaxy_center_elements.insert(center.position, g);
         }
 
@@ -256,69 +482,260 @@ impl App {
     }
 
     fn on_border_click(&mut self, border: Border) -> Result<(), JsValue> {
-        let p1 = border.p1();
-        let p2 = border.p2();
-        self.state.board.toggle_wall(p1, p2);
-        self.state.error = None;
-        self.state.history.push(HistoryEntry::ToggleBorder(border));
+        if self.is_paused() || !self.state.toggle_wall(border) {
+            return Ok(());
+        }
         self.render()
     }
 
     fn on_check_click(&mut self) -> Result<(), JsValue> {
-        self.state.error = Some(self.state.board.compute_error(&self.state.objective));
+        if self.is_paused() {
+            return Ok(());
+        }
+        let was_completed = self.state.is_completed();
+        let now = js_sys::Date::now();
+        self.state.check(now);
+        if let (false, Some(record)) = (was_completed, self.state.record(Date::from_timestamp(now)))
+        {
+            self.statistics.add(record);
+            self.statistics.save(&mut LocalStorage);
+            if let Some(date) = self.state.daily {
+                mark_daily_completed(&mut LocalStorage, date);
+            }
+        }
         self.render()
     }
 
+    /// Writes the puzzle and the walls drawn so far to the URL, so that the link can be shared
+    fn on_share_click(&self) -> Result<(), JsValue> {
+        let link = PuzzleLink::from_state(&self.state, true);
+        window().unwrap().location().set_hash(&link.to_string())
+    }
+
+    fn on_stats_click(&mut self) -> Result<(), JsValue> {
+        self.stats_visible = !self.stats_visible;
+        self.render_stats()
+    }
+
     fn on_new_game_click(&mut self) -> Result<(), JsValue> {
-        self.state = State::generate(SIZE as usize);
+        if self.state.daily.is_some() {
+            // There's one daily puzzle per day, so the next game is a random one
+            return set_query_parameter("mode", "random");
+        }
+        if let Some(state) = self.next_state.take() {
+            return self.start_game(state);
+        }
+        if self.worker.is_some() {
+            // The game starts when the worker is done
+            self.awaiting_new_game = true;
+            self.request_next_puzzle()?;
+            return self.render_spinner();
+        }
+        self.start_generated_game()
+    }
+
+    /// Generates a game on the board of the current one on the main thread, and starts it.
+    /// The elements of the board are only created once, so if generation fails the current game
+    /// is kept and the failure is reported, instead of starting a game on another board.
+    fn start_generated_game(&mut self) -> Result<(), JsValue> {
+        let surface = self.state.board.get_surface();
+        let state = generate_on_board(
+            self.state.board.get_mask().clone(),
+            surface.topology(),
+            surface.grid_type(),
+        );
+        match state {
+            Some(state) => self.start_game(state),
+            None => {
+                self.generation_failed = true;
+                self.render()
+            }
+        }
+    }
+
+    fn on_puzzle_generated(&mut self, event: MessageEvent) -> Result<(), JsValue> {
+        self.generating = false;
+        let message = event.data().as_string().unwrap_or_default();
+        let state = message
+            .parse::<GenerationResponse>()
+            .and_then(|response| response.to_state());
+        let Ok(state) = state else {
+            // The next game is requested again when the player asks for it
+            return self.generate_awaited_game();
+        };
+        if self.awaiting_new_game {
+            self.awaiting_new_game = false;
+            self.start_game(state)
+        } else {
+            self.next_state = Some(state);
+            Ok(())
+        }
+    }
+
+    /// Stops using the worker once it has failed, the following games are generated on the main thread
+    fn on_worker_error(&mut self) -> Result<(), JsValue> {
+        if let Some(worker) = self.worker.take() {
+            worker.terminate();
+        }
+        self.generating = false;
+        self.generate_awaited_game()
+    }
+
+    /// Generates the game that the player is waiting for on the main thread, if there is one
+    fn generate_awaited_game(&mut self) -> Result<(), JsValue> {
+        if !self.awaiting_new_game {
+            return Ok(());
+        }
+        self.awaiting_new_game = false;
+        self.start_generated_game()
+    }
+
+    /// Replaces the game with [state], and starts generating the game after it
+    fn start_game(&mut self, state: State) -> Result<(), JsValue> {
+        // The link in the URL is of the previous puzzle
+        window().unwrap().location().set_hash("")?;
+        self.state = state;
+        self.generation_failed = false;
+        self.state.resume(js_sys::Date::now());
         self.init_galaxy_centers()?;
         self.render()?;
+        self.request_next_puzzle()
+    }
+
+    /// Asks the worker for a puzzle on the same board as the current one, unless it's
+    /// already generating one or there's one waiting. Daily puzzles are followed by a page reload,
+    /// so nothing is generated after them.
+    fn request_next_puzzle(&mut self) -> Result<(), JsValue> {
+        let Some(worker) = &self.worker else {
+            return Ok(());
+        };
+        if self.generating || self.next_state.is_some() || self.state.daily.is_some() {
+            return Ok(());
+        }
+        let surface = self.state.board.get_surface();
+        let request = GenerationRequest {
+            mask: self.state.board.get_mask().clone(),
+            topology: surface.topology(),
+            grid: surface.grid_type(),
+            seed: rand::random(),
+        };
+        worker.post_message(&JsValue::from_str(&request.to_string()))?;
+        self.generating = true;
         Ok(())
     }
 
     fn on_undo_click(&mut self) -> Result<(), JsValue> {
-        if let Some(entry) = self.state.history.undo() {
-            match entry {
-                HistoryEntry::ToggleBorder(border) => {
-                    self.state.board.toggle_wall(border.p1(), border.p2());
-                }
-            }
-            self.state.error = None;
+        if !self.is_paused() && self.state.undo() {
             self.render()?;
         }
         Ok(())
     }
 
     fn on_redo_click(&mut self) -> Result<(), JsValue> {
-        if let Some(entry) = self.state.history.redo() {
-            match entry {
-                HistoryEntry::ToggleBorder(border) => {
-                    self.state.board.toggle_wall(border.p1(), border.p2());
-                }
-            }
-            self.state.error = None;
+        if !self.is_paused() && self.state.redo() {
             self.render()?;
         }
         Ok(())
     }
 
+    fn on_hint_click(&mut self) -> Result<(), JsValue> {
+        if !self.is_paused() && self.state.hint().is_some() {
+            self.render()?;
+        }
+        Ok(())
+    }
+
+    fn on_pause_click(&mut self) -> Result<(), JsValue> {
+        if self.state.is_completed() {
+            return Ok(());
+        }
+        let now = js_sys::Date::now();
+        if self.state.timer.is_running() {
+            self.state.pause(now);
+        } else {
+            self.state.resume(now);
+        }
+        self.render()
+    }
+
+    /// Returns whether the player has paused the game, a completed game isn't paused
+    fn is_paused(&self) -> bool {
+        !self.state.timer.is_running() && !self.state.is_completed()
+    }
+
+    /// Keeps the game in local storage, so that it's continued when the page is reloaded.
+    /// A completed game is forgotten instead.
+    fn save_game(&self) {
+        if self.state.is_completed() {
+            SavedGame::clear(&mut LocalStorage);
+        } else {
+            SavedGame::from_state(&self.state, js_sys::Date::now()).save(&mut LocalStorage);
+        }
+    }
+
     fn render(&self) -> Result<(), JsValue> {
+        // Every change to the game is rendered, so this is also where it's saved
+        self.save_game();
         self.render_cells()?;
         self.render_borders()?;
         self.render_centers()?;
+        self.render_merged_links()?;
+        self.render_report()?;
         self.render_controls()?;
+        self.render_timer()?;
+        self.render_overlay()?;
+        self.render_stats()?;
+        self.render_spinner()?;
 
         Ok(())
     }
 
     fn render_cells(&self) -> Result<(), JsValue> {
+        let shaded_cells: HashSet<Position> = self
+            .state
+            .board
+            .get_completed_galaxies(&self.state.objective)
+            .iter()
+            .filter(|(gc, _)| gc.color == CenterColor::Black)
+            .flat_map(|(_, galaxy)| galaxy.get_positions().copied())
+            .collect();
+        let mut asymmetric_cells: HashSet<Position> = HashSet::new();
+        let mut missing_mirrors: HashSet<Position> = HashSet::new();
+        let mut merged_cells: HashSet<Position> = HashSet::new();
+        if let Some(error) = &self.state.error {
+            for merged in &error.merged_centers {
+                merged_cells.extend(merged.region.get_positions().copied());
+            }
+            for problem in error
+                .galaxy_reports
+                .iter()
+                .flat_map(|report| &report.problems)
+            {
+                if let Problem::Asymmetric { cell, mirror } = problem {
+                    asymmetric_cells.insert(*cell);
+                    missing_mirrors.insert(*mirror);
+                }
+            }
+        }
         for (p, element) in &self.cell_elements {
             let mut classes = vec!["cell"];
+            if shaded_cells.contains(p) {
+                classes.push("shaded");
+            }
             if let Some(error) = &self.state.error {
-                if error.centerless_cells.contains(&p) {
+                if error.centerless_cells.contains(p) {
                     classes.push("centerless");
                 }
             }
+            if asymmetric_cells.contains(p) {
+                classes.push("asymmetric");
+            }
+            if missing_mirrors.contains(p) {
+                classes.push("missing-mirror");
+            }
+            if merged_cells.contains(p) {
+                classes.push("merged");
+            }
             element.set_attribute("class", &classes.join(" "))?;
         }
 
@@ -326,17 +743,29 @@ impl App {
     }
 
     fn render_borders(&self) -> Result<(), JsValue> {
-        for (border, element) in &self.border_elements {
+        for (border, elements) in &self.border_elements {
             let mut classes = vec!["wall-group"];
+            let surface = self.state.board.get_surface();
+            if surface.crosses_wrapped_edge(&border.p1(), &border.p2()) {
+                classes.push("wrapped");
+            }
             if let Some(error) = &self.state.error {
-                if error.dangling_borders.contains(&border) {
+                if error.dangling_borders.contains(border) {
                     classes.push("dangling");
                 }
+                if error.missing_walls.contains(border) {
+                    classes.push("missing");
+                }
             }
             if self.state.board.is_wall(border.p1(), border.p2()) {
                 classes.push("active");
             }
-            element.set_attribute("class", &classes.join(" "))?;
+            if self.state.objective.is_given_wall(border) {
+                classes.push("given");
+            }
+            for element in elements {
+                element.set_attribute("class", &classes.join(" "))?;
+            }
         }
 
         Ok(())
@@ -346,6 +775,9 @@ impl App {
         for gc in &self.state.objective.centers {
             if let Some(element) = self.galaxy_center_elements.get(&gc.position) {
                 let mut classes = vec!["galaxy-center"];
+                if gc.color == CenterColor::Black {
+                    classes.push("black");
+                }
                 if let Some(error) = &self.state.error {
                     if error.cut_centers.contains(&gc.position) {
                         classes.push("cut");
@@ -356,14 +788,74 @@ impl App {
                     if error.incorrect_galaxy_sizes.contains(&gc.position) {
                         classes.push("incorrect-size");
                     }
+                    if error.is_merged_center(&gc.position) {
+                        classes.push("merged");
+                    }
                 }
                 element.set_attribute("class", &classes.join(" "))?;
+
+                let messages = self
+                    .state
+                    .error
+                    .as_ref()
+                    .and_then(|error| error.report_for_center(&gc.position))
+                    .map(|report| report.messages().join("\n"))
+                    .unwrap_or_default();
+                if let Some(title) = element.query_selector("title")? {
+                    title.set_text_content(Some(&messages));
+                }
             }
         }
 
         Ok(())
     }
 
+    fn render_merged_links(&self) -> Result<(), JsValue> {
+        self.merged_links.set_inner_html("");
+        let Some(error) = &self.state.error else {
+            return Ok(());
+        };
+        for merged in &error.merged_centers {
+            for pair in merged.centers.windows(2) {
+                let (x1, y1) = self.layout.center_point(&pair[0]);
+                let (x2, y2) = self.layout.center_point(&pair[1]);
+                let line = self.document.create_element_ns(SVG_NAMESPACE, "line")?;
+                line.set_attribute("x1", &x1.to_string())?;
+                line.set_attribute("y1", &y1.to_string())?;
+                line.set_attribute("x2", &x2.to_string())?;
+                line.set_attribute("y2", &y2.to_string())?;
+                line.set_attribute("stroke-width", &self.layout.wall_size.to_string())?;
+                line.set_attribute("class", "merged-link")?;
+                self.merged_links.append_child(&line)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn render_report(&self) -> Result<(), JsValue> {
+        self.report.set_inner_html("");
+        let mut messages = self
+            .state
+            .error
+            .as_ref()
+            .map(|error| error.messages())
+            .unwrap_or_default();
+        if self.generation_failed {
+            messages.push("Could not generate a new puzzle on this board".to_string());
+        }
+        for message in &messages {
+            let item = self.document.create_element("li")?;
+            item.set_text_content(Some(message));
+            self.report.append_child(&item)?;
+        }
+        let class = if messages.is_empty() {
+            "report hidden"
+        } else {
+            "report"
+        };
+        self.report.set_attribute("class", class)
+    }
+
     fn render_controls(&self) -> Result<(), JsValue> {
         let error_free = self
             .state
@@ -376,32 +868,284 @@ impl App {
         } else {
             self.new_game_button.set_attribute("class", "hidden")?;
         }
+        let pause_class = if self.state.is_completed() {
+            "hidden"
+        } else {
+            ""
+        };
+        self.pause_button.set_attribute("class", pause_class)?;
+        let pause_text = if self.is_paused() { "Resume" } else { "Pause" };
+        self.pause_button.set_text_content(Some(pause_text));
+        Ok(())
+    }
+
+    fn render_spinner(&self) -> Result<(), JsValue> {
+        let class = if self.awaiting_new_game {
+            "spinner"
+        } else {
+            "spinner hidden"
+        };
+        self.spinner.set_attribute("class", class)
+    }
+
+    fn render_timer(&self) -> Result<(), JsValue> {
+        let elapsed = self.state.timer.elapsed(js_sys::Date::now());
+        self.timer.set_text_content(Some(&format_duration(elapsed)));
         Ok(())
     }
+
+    fn render_overlay(&self) -> Result<(), JsValue> {
+        self.overlay.set_inner_html("");
+        if let Some(completion) = &self.state.completion {
+            let heading = self.document.create_element("h2")?;
+            heading.set_text_content(Some("Solved!"));
+            self.overlay.append_child(&heading)?;
+            let size = self.state.board.get_width();
+            let difficulty = self.state.difficulty;
+            let mut lines: Vec<String> = completion.to_string().lines().map(String::from).collect();
+            lines.push(format!("Difficulty: {difficulty}"));
+            if let Some(best) = self.statistics.best_time(size, difficulty) {
+                lines.push(format!("Best time: {}", format_duration(best)));
+            }
+            let list = self.document.create_element("ul")?;
+            for line in lines {
+                let item = self.document.create_element("li")?;
+                item.set_text_content(Some(&line));
+                list.append_child(&item)?;
+            }
+            self.overlay.append_child(&list)?;
+            self.append_overlay_button("New game", "new-game")?;
+            self.overlay.set_attribute("class", "overlay")
+        } else if self.is_paused() {
+            let heading = self.document.create_element("h2")?;
+            heading.set_text_content(Some("Paused"));
+            self.overlay.append_child(&heading)?;
+            self.append_overlay_button("Resume", "resume")?;
+            self.overlay.set_attribute("class", "overlay")
+        } else {
+            self.overlay.set_attribute("class", "overlay hidden")
+        }
+    }
+
+    fn render_stats(&self) -> Result<(), JsValue> {
+        self.stats_panel.set_inner_html("");
+        if !self.stats_visible {
+            return self.stats_panel.set_attribute("class", "stats hidden");
+        }
+        let today = Date::from_timestamp(js_sys::Date::now());
+        let streaks = self.document.create_element("p")?;
+        streaks.set_text_content(Some(&format!(
+            "Streak: {} days, longest: {} days",
+            self.statistics.current_streak(today),
+            self.statistics.longest_streak()
+        )));
+        self.stats_panel.append_child(&streaks)?;
+
+        let table = self.document.create_element("table")?;
+        let header = self.document.create_element("tr")?;
+        for heading in ["Size", "Difficulty", "Games", "Average", "Best"] {
+            let th = self.document.create_element("th")?;
+            th.set_text_content(Some(heading));
+            header.append_child(&th)?;
+        }
+        table.append_child(&header)?;
+        for summary in self.statistics.summaries() {
+            let row = self.document.create_element("tr")?;
+            for value in [
+                summary.size.to_string(),
+                summary.difficulty.to_string(),
+                summary.games.to_string(),
+                format_duration(summary.average_time),
+                format_duration(summary.best_time),
+            ] {
+                let td = self.document.create_element("td")?;
+                td.set_text_content(Some(&value));
+                row.append_child(&td)?;
+            }
+            table.append_child(&row)?;
+        }
+        self.stats_panel.append_child(&table)?;
+        self.stats_panel.set_attribute("class", "stats")
+    }
+
+    /// Adds a button to the overlay, the overlay's click listener dispatches on [action]
+    fn append_overlay_button(&self, text: &str, action: &str) -> Result<(), JsValue> {
+        let button = self.document.create_element("button")?;
+        button.set_text_content(Some(text));
+        button.set_attribute("data-action", action)?;
+        self.overlay.append_child(&button)?;
+        Ok(())
+    }
+}
+
+/// The browser's `localStorage`, where failing to read or write is treated as an empty storage
+struct LocalStorage;
+
+impl Storage for LocalStorage {
+    fn get(&self, key: &str) -> Option<String> {
+        let storage = window()?.local_storage().ok()??;
+        storage.get_item(key).ok()?
+    }
+
+    fn set(&mut self, key: &str, value: &str) {
+        if let Some(Ok(Some(storage))) = window().map(|window| window.local_storage()) {
+            let _ = storage.set_item(key, value);
+        }
+    }
 }
 
-fn create_wall_svg(document: &Document, border: Border) -> Result<Element, JsValue> {
+/// Returns the value of the query parameter with the given name, e.g. `heart` for `?shape=heart`
+fn get_query_parameter(name: &str) -> Option<String> {
+    let search = window()?.location().search().ok()?;
+    search
+        .trim_start_matches('?')
+        .split('&')
+        .filter_map(|parameter| parameter.split_once('='))
+        .find(|(key, _)| *key == name)
+        .map(|(_, value)| value.to_string())
+}
+
+/// Replaces the value of the query parameter with the given name, which reloads the page
+fn set_query_parameter(name: &str, value: &str) -> Result<(), JsValue> {
+    let location = window().unwrap().location();
+    let search = location.search()?;
+    let mut parameters: Vec<String> = search
+        .trim_start_matches('?')
+        .split('&')
+        .filter(|parameter| !parameter.is_empty())
+        .filter(|parameter| parameter.split_once('=').map(|(key, _)| key) != Some(name))
+        .map(String::from)
+        .collect();
+    parameters.push(format!("{name}={value}"));
+    location.set_search(&format!("?{}", parameters.join("&")))
+}
+
+/// Returns the game of the puzzle linked in the fragment of the URL, if there is a valid one
+fn get_linked_state() -> Option<State> {
+    let hash = window()?.location().hash().ok()?;
+    let link: PuzzleLink = hash.trim_start_matches('#').parse().ok()?;
+    link.to_state().ok()
+}
+
+/// Returns the game saved before the page was reloaded, if it's today's daily puzzle in daily mode,
+/// or a random puzzle on the board given by the query parameters otherwise
+fn get_saved_state() -> Option<State> {
+    let saved = SavedGame::load(&LocalStorage)?;
+    let daily = is_daily_mode().then(|| Date::from_timestamp(js_sys::Date::now()));
+    if saved.daily != daily {
+        return None;
+    }
+    let state = saved.to_state().ok()?;
+    let (mask, topology, grid) = get_board();
+    let surface = state.board.get_surface();
+    let is_on_board = state.board.get_mask() == &mask
+        && surface.topology() == topology
+        && surface.grid_type() == grid;
+    (daily.is_some() || is_on_board).then_some(state)
+}
+
+/// Returns whether the `mode` query parameter asks for the daily puzzle, i.e. `?mode=daily`
+fn is_daily_mode() -> bool {
+    get_query_parameter("mode").as_deref() == Some("daily")
+}
+
+/// Returns the shape given by the `shape` query parameter, e.g. `?shape=heart`
+fn get_shape() -> Shape {
+    get_query_parameter("shape")
+        .and_then(|shape| shape.parse().ok())
+        .unwrap_or_default()
+}
+
+/// Returns the topology given by the `topology` query parameter, e.g. `?topology=torus`
+fn get_topology() -> Topology {
+    get_query_parameter("topology")
+        .and_then(|topology| topology.parse().ok())
+        .unwrap_or_default()
+}
+
+/// Returns the grid given by the `grid` query parameter, e.g. `?grid=hexagonal`
+fn get_grid() -> GridType {
+    get_query_parameter("grid")
+        .and_then(|grid| grid.parse().ok())
+        .unwrap_or_default()
+}
+
+/// Returns the board given by the `shape`, `topology` and `grid` query parameters,
+/// or the default board if puzzles can't be generated on it, e.g. a triangular heart
+fn get_board() -> (Mask, Topology, GridType) {
+    let (mask, topology, grid) = (get_shape().mask(SIZE as usize), get_topology(), get_grid());
+    if Universe::check_generation(&mask, Symmetry::default(), topology, grid).is_ok() {
+        (mask, topology, grid)
+    } else {
+        (
+            Shape::default().mask(SIZE as usize),
+            Topology::default(),
+            GridType::default(),
+        )
+    }
+}
+
+/// Generates a game on the board, trying a few seeds since generation can fail on boards
+/// that are hard to divide. Returns None if every seed fails.
+fn generate_on_board(mask: Mask, topology: Topology, grid: GridType) -> Option<State> {
+    (0..GENERATION_ATTEMPTS)
+        .find_map(|_| State::generate_with_mask(mask.clone(), topology, grid).ok())
+}
+
+/// Generates a game on the board, or on the default board if every seed fails.
+/// Only used before the elements of the board are created, since they're created for the game.
+fn generate_or_default(mask: Mask, topology: Topology, grid: GridType) -> State {
+    generate_on_board(mask, topology, grid).unwrap_or_else(|| State::generate(SIZE as usize))
+}
+
+fn set_line_attributes(
+    line: &Element,
+    (x1, y1): (f64, f64),
+    (x2, y2): (f64, f64),
+) -> Result<(), JsValue> {
+    line.set_attribute("x1", &x1.to_string())?;
+    line.set_attribute("y1", &y1.to_string())?;
+    line.set_attribute("x2", &x2.to_string())?;
+    line.set_attribute("y2", &y2.to_string())
+}
+
+/// Creates a line along the side between [p] and [q], where [q] is outside the board
+fn create_edge_svg(
+    document: &Document,
+    layout: &Layout,
+    p: &Position,
+    q: &Position,
+) -> Result<Element, JsValue> {
+    let (start, end) = layout.shared_side(p, q);
+    let line = document.create_element_ns(SVG_NAMESPACE, "line")?;
+    set_line_attributes(&line, start, end)?;
+    line.set_attribute("stroke", WALL_COLOR)?;
+    line.set_attribute("stroke-width", &layout.wall_size.to_string())?;
+    line.set_attribute("stroke-linecap", layout.linecap(p))?;
+    Ok(line)
+}
+
+/// Creates a wall along the side between [p1] and [p2], with a touch area reaching the middle of both cells
+fn create_wall_svg(
+    document: &Document,
+    layout: &Layout,
+    p1: &Position,
+    p2: &Position,
+) -> Result<Element, JsValue> {
     let group = document.create_element_ns(SVG_NAMESPACE, "g")?;
     group.set_attribute("class", "wall-group")?;
 
-    let p1 = border.p1();
-    let p2 = border.p2();
-    let x_min = WALL_SIZE / 2.0 + (WALL_SIZE + CELL_SIZE) * (p1.column + p2.column) as f64 / 2.0;
-    let x_max = x_min + CELL_SIZE + WALL_SIZE;
-    let x_mid = (x_min + x_max) / 2.0;
-    let y_min = WALL_SIZE / 2.0 + (WALL_SIZE + CELL_SIZE) * (p1.row + p2.row) as f64 / 2.0;
-    let y_max = y_min + CELL_SIZE + WALL_SIZE;
-    let y_mid = (y_min + y_max) / 2.0;
+    let (start, end) = layout.shared_side(p1, p2);
 
     {
         let polygon = document.create_element_ns(SVG_NAMESPACE, "polygon")?;
-        polygon.set_attribute(
-            "points",
-            &format!(
-                "{},{} {},{} {},{} {},{}",
-                x_mid, y_min, x_max, y_mid, x_mid, y_max, x_min, y_mid
-            ),
-        )?;
+        let points = [
+            start,
+            centroid(&layout.polygon(p2)),
+            end,
+            centroid(&layout.polygon(p1)),
+        ];
+        polygon.set_attribute("points", &format_points(&points))?;
         polygon.set_attribute("class", "wall-touch")?;
         group.append_child(&polygon)?;
     }
@@ -409,20 +1153,8 @@ fn create_wall_svg(document: &Document, border: Border) -> Result<Element, JsVal
     {
         let line = document.create_element_ns(SVG_NAMESPACE, "line")?;
         line.set_attribute("class", "wall-line")?;
-        if p1.row == p2.row {
-            // Vertical
-            line.set_attribute("x1", &x_mid.to_string())?;
-            line.set_attribute("y1", &y_min.to_string())?;
-            line.set_attribute("x2", &x_mid.to_string())?;
-            line.set_attribute("y2", &y_max.to_string())?;
-        } else {
-            // Horizontal
-            line.set_attribute("x1", &x_min.to_string())?;
-            line.set_attribute("y1", &y_mid.to_string())?;
-            line.set_attribute("x2", &x_max.to_string())?;
-            line.set_attribute("y2", &y_mid.to_string())?;
-        }
-        line.set_attribute("stroke-width", &WALL_SIZE.to_string())?;
+        set_line_attributes(&line, start, end)?;
+        line.set_attribute("stroke-width", &layout.wall_size.to_string())?;
         group.append_child(&line)?;
     }
 
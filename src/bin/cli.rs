@@ -0,0 +1,334 @@
+use std::collections::{BTreeSet, HashMap};
+use std::env;
+use std::fs;
+use std::process::ExitCode;
+#[cfg(feature = "parallel")]
+use std::sync::{atomic::AtomicBool, atomic::Ordering, Arc};
+#[cfg(feature = "parallel")]
+use std::{io::Write, thread, time::Duration};
+use vintergatan::booklet::{booklet_pages, to_pdf, BookletOptions};
+#[cfg(feature = "parallel")]
+use vintergatan::model::batch::{generate_batch, BatchOptions, Progress};
+use vintergatan::model::diagram::Diagram;
+use vintergatan::model::grid::GridType;
+use vintergatan::model::mask::{Mask, Shape};
+use vintergatan::model::objective::{CenterColor, Objective};
+use vintergatan::model::picture::Picture;
+use vintergatan::model::position::Position;
+#[cfg(feature = "parallel")]
+use vintergatan::model::puzzle_link::PuzzleLink;
+use vintergatan::model::solver::Solver;
+use vintergatan::model::state::State;
+use vintergatan::model::symmetry::Symmetry;
+use vintergatan::model::topology::Topology;
+use vintergatan::model::universe::Universe;
+use vintergatan::svg::{objective_svg, universe_svg, SvgOptions};
+
+const DEFAULT_SIZE: usize = 10;
+
+/// How many solutions are shown by default
+const DEFAULT_SOLUTION_LIMIT: usize = 10;
+
+/// How many puzzles are added to a library by default
+#[cfg(feature = "parallel")]
+const DEFAULT_LIBRARY_COUNT: usize = 100;
+
+const USAGE: &str = "Usage: cli <command> [arguments]
+
+Commands:
+  generate [size] [symmetry] [shape] [topology] [grid]
+                           Generates a puzzle and prints its solution, the symmetry is one of
+                           rotational (default), horizontal, vertical or four-fold, the shape
+                           is one of square (default), circle, heart, or a plain PBM or PGM file
+                           whose black pixels are the cells of the board, the topology is one
+                           of plane (default), horizontal-wrap, vertical-wrap or torus, and the
+                           grid is one of square (default), hexagonal or triangular, where
+                           only square grids support symmetries other than rotational and wrapping
+  picture <file> [size]    Generates a picture puzzle from a plain PBM or PGM file
+  svg <file> [size] [colored]
+                           Generates a puzzle and writes it to an SVG file, and its solution to
+                           the same name ending in -solution.svg, with coloured galaxies if the
+                           word colored is given
+  booklet <file> [count] [size]
+                           Generates count puzzles (6 by default) and lays them out on pages,
+                           followed by their answers, as a PDF if the file ends in .pdf, and
+                           otherwise as one SVG file per page, numbered after the file name
+  solutions <file> [limit] Prints up to limit (10 by default) solutions of the puzzle drawn in
+                           the file as an ASCII diagram, and the walls where each solution
+                           differs from the first
+  check <file>             Checks the walls drawn in the file as an ASCII diagram against its
+                           centers, and explains every error that is found
+  library <file> [count] [size] [seconds]
+                           Generates count puzzles (100 by default) on all cores and appends
+                           them to the file as puzzle links, one per line, stopping after the
+                           given number of seconds if there is one. Requires the parallel feature";
+
+fn main() -> ExitCode {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let result = match args.first().map(String::as_str) {
+        Some("generate") => generate(&args[1..]),
+        Some("picture") => picture(&args[1..]),
+        Some("svg") => svg(&args[1..]),
+        Some("booklet") => booklet(&args[1..]),
+        Some("solutions") => solutions(&args[1..]),
+        Some("check") => check(&args[1..]),
+        #[cfg(feature = "parallel")]
+        Some("library") => library(&args[1..]),
+        _ => Err(USAGE.to_string()),
+    };
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(message) => {
+            eprintln!("{message}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn parse_size(arg: Option<&String>) -> Result<usize, String> {
+    match arg {
+        None => Ok(DEFAULT_SIZE),
+        Some(arg) => arg
+            .parse()
+            .ok()
+            .filter(|&size| size > 0)
+            .ok_or_else(|| format!("Invalid size: {arg}")),
+    }
+}
+
+fn read_diagram(path: &str) -> Result<Diagram, String> {
+    let content = fs::read_to_string(path).map_err(|e| format!("Could not read {path}: {e}"))?;
+    content
+        .parse()
+        .map_err(|e| format!("Could not parse {path}: {e}"))
+}
+
+fn read_picture(path: &str) -> Result<Picture, String> {
+    let content = fs::read_to_string(path).map_err(|e| format!("Could not read {path}: {e}"))?;
+    content
+        .parse()
+        .map_err(|e| format!("Could not parse {path}: {e}"))
+}
+
+fn generate(args: &[String]) -> Result<(), String> {
+    let size = parse_size(args.first())?;
+    let symmetry: Symmetry = match args.get(1) {
+        None => Symmetry::default(),
+        Some(arg) => arg.parse()?,
+    };
+    let mask = match args.get(2) {
+        None => Shape::default().mask(size),
+        Some(arg) => match arg.parse::<Shape>() {
+            Ok(shape) => shape.mask(size),
+            Err(_) => Mask::from_picture(&read_picture(arg)?.scaled(size, size)),
+        },
+    };
+    let topology: Topology = match args.get(3) {
+        None => Topology::default(),
+        Some(arg) => arg.parse()?,
+    };
+    let grid: GridType = match args.get(4) {
+        None => GridType::default(),
+        Some(arg) => arg.parse()?,
+    };
+    Universe::check_generation(&mask, symmetry, topology, grid).map_err(|e| e.to_string())?;
+    let universe = Universe::generate_with_mask(mask, symmetry, topology, grid)
+        .map_err(|e| format!("Could not generate a universe: {e}"))?;
+    println!("{universe}");
+    println!("Score: {}", universe.get_score());
+    Ok(())
+}
+
+fn picture(args: &[String]) -> Result<(), String> {
+    let path = args.first().ok_or(USAGE)?;
+    let size = parse_size(args.get(1))?;
+    let picture = read_picture(path)?;
+
+    let universe = Universe::generate_with_picture(size, size, &picture);
+    let objective = Objective::generate_with_picture(&universe, &picture);
+    println!("{universe}");
+    println!();
+
+    let color_by_center: HashMap<Position, CenterColor> = objective
+        .centers
+        .iter()
+        .map(|gc| (gc.position, gc.color))
+        .collect();
+    for row in 0..universe.height() {
+        let line: String = (0..universe.width())
+            .map(|column| {
+                let center = universe.get_galaxy(&Position::from((row, column))).center();
+                match color_by_center.get(&center) {
+                    Some(CenterColor::Black) => "██",
+                    _ => "··",
+                }
+            })
+            .collect();
+        println!("{line}");
+    }
+    println!(
+        "Mismatch: {}",
+        universe.get_picture_mismatch(&picture.scaled(size, size))
+    );
+    Ok(())
+}
+
+fn svg(args: &[String]) -> Result<(), String> {
+    let path = args.first().ok_or(USAGE)?;
+    let size = parse_size(args.get(1))?;
+    let colored_galaxies = match args.get(2).map(String::as_str) {
+        None => false,
+        Some("colored") => true,
+        Some(arg) => return Err(format!("Unknown option: {arg}")),
+    };
+    let options = SvgOptions {
+        colored_galaxies,
+        ..SvgOptions::default()
+    };
+
+    let universe = Universe::generate(size, size);
+    let objective = Objective::generate(&universe);
+    let solution_path = match path.strip_suffix(".svg") {
+        Some(stem) => format!("{stem}-solution.svg"),
+        None => format!("{path}-solution.svg"),
+    };
+    let write = |path: &str, svg: String| {
+        fs::write(path, svg).map_err(|e| format!("Could not write {path}: {e}"))
+    };
+    write(path, objective_svg(universe.mask(), &objective, &options))?;
+    write(&solution_path, universe_svg(&universe, &options))?;
+    Ok(())
+}
+
+fn booklet(args: &[String]) -> Result<(), String> {
+    let path = args.first().ok_or(USAGE)?;
+    let count: usize = match args.get(1) {
+        None => BookletOptions::default().puzzles_per_page(),
+        Some(arg) => arg.parse().map_err(|_| format!("Invalid count: {arg}"))?,
+    };
+    let size = parse_size(args.get(2))?;
+
+    let puzzles: Vec<State> = (0..count).map(|_| State::generate(size)).collect();
+    let pages = booklet_pages(&puzzles, &BookletOptions::default());
+    let write = |path: &str, content: &[u8]| {
+        fs::write(path, content).map_err(|e| format!("Could not write {path}: {e}"))
+    };
+    if path.ends_with(".pdf") {
+        write(path, &to_pdf(&pages))
+    } else {
+        let stem = path.strip_suffix(".svg").unwrap_or(path);
+        for (index, page) in pages.iter().enumerate() {
+            write(
+                &format!("{stem}-{}.svg", index + 1),
+                page.to_svg().as_bytes(),
+            )?;
+        }
+        Ok(())
+    }
+}
+
+fn solutions(args: &[String]) -> Result<(), String> {
+    let path = args.first().ok_or(USAGE)?;
+    let limit: usize = match args.get(1) {
+        None => DEFAULT_SOLUTION_LIMIT,
+        Some(arg) => arg.parse().map_err(|_| format!("Invalid limit: {arg}"))?,
+    };
+    let diagram = read_diagram(path)?;
+    let objective = diagram.to_objective();
+    objective
+        .validate(&diagram.mask)
+        .map_err(|e| e.to_string())?;
+
+    let solver = Solver::new(&diagram.mask, &objective);
+    let solutions: Vec<Universe> = solver.solutions().take(limit).collect();
+    let Some(first) = solutions.first() else {
+        println!("No solutions");
+        return Ok(());
+    };
+    for (index, solution) in solutions.iter().enumerate() {
+        println!("Solution {}:", index + 1);
+        println!("{solution}");
+        if index > 0 {
+            let differences: Vec<String> = first
+                .wall_differences(solution)
+                .iter()
+                .map(|border| format!("{}-{}", border.p1(), border.p2()))
+                .collect();
+            println!("Differs from solution 1 at: {}", differences.join(", "));
+        }
+        println!();
+    }
+    // The search stops at the limit, so there may be more solutions
+    let at_least = if solutions.len() == limit {
+        "at least "
+    } else {
+        ""
+    };
+    println!("Solutions: {at_least}{}", solutions.len());
+    Ok(())
+}
+
+fn check(args: &[String]) -> Result<(), String> {
+    let path = args.first().ok_or(USAGE)?;
+    let diagram = read_diagram(path)?;
+    // The walls are the player's, so none of them are given
+    let objective = Diagram {
+        walls: BTreeSet::new(),
+        ..diagram.clone()
+    }
+    .to_objective();
+    objective
+        .validate(&diagram.mask)
+        .map_err(|e| e.to_string())?;
+
+    let error = diagram.to_board().compute_error(&objective);
+    if error.is_error_free() {
+        println!("No errors, the puzzle is solved");
+        return Ok(());
+    }
+    println!("{error}");
+    Err(format!("Errors: {}", error.messages().len()))
+}
+
+#[cfg(feature = "parallel")]
+fn library(args: &[String]) -> Result<(), String> {
+    let path = args.first().ok_or(USAGE)?;
+    let count: usize = match args.get(1) {
+        None => DEFAULT_LIBRARY_COUNT,
+        Some(arg) => arg.parse().map_err(|_| format!("Invalid count: {arg}"))?,
+    };
+    let size = parse_size(args.get(2))?;
+    let cancel = Arc::new(AtomicBool::new(false));
+    if let Some(arg) = args.get(3) {
+        let seconds: u64 = arg.parse().map_err(|_| format!("Invalid seconds: {arg}"))?;
+        let cancel = Arc::clone(&cancel);
+        thread::spawn(move || {
+            thread::sleep(Duration::from_secs(seconds));
+            cancel.store(true, Ordering::Relaxed);
+        });
+    }
+
+    let options = BatchOptions {
+        count,
+        size,
+        first_seed: rand::random(),
+        threads: 0,
+    };
+    let on_progress = |progress: Progress| {
+        eprint!("\rGenerated {}/{}", progress.generated, progress.total);
+    };
+    let states = generate_batch(&options, on_progress, &cancel)?;
+    eprintln!();
+
+    let mut file = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|e| format!("Could not open {path}: {e}"))?;
+    for state in &states {
+        writeln!(file, "{}", PuzzleLink::from_state(state, false))
+            .map_err(|e| format!("Could not write {path}: {e}"))?;
+    }
+    eprintln!("Added {} puzzles to {path}", states.len());
+    Ok(())
+}
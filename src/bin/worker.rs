@@ -0,0 +1,26 @@
+use vintergatan::model::generation::{GenerationRequest, GenerationResponse};
+use web_sys::wasm_bindgen::closure::Closure;
+use web_sys::wasm_bindgen::{JsCast, JsValue};
+use web_sys::{DedicatedWorkerGlobalScope, MessageEvent};
+
+/// Generates puzzles in a Web Worker, so that the page stays responsive while it's busy.
+/// It receives [GenerationRequest]s and answers with [GenerationResponse]s, both as strings.
+fn main() {
+    console_error_panic_hook::set_once();
+    let scope: DedicatedWorkerGlobalScope = js_sys::global().unchecked_into();
+    let reply_scope = scope.clone();
+    let closure = Closure::<dyn FnMut(_)>::new(move |event: MessageEvent| {
+        let Some(request) = event.data().as_string() else {
+            return;
+        };
+        let response = match request.parse::<GenerationRequest>() {
+            Ok(request) => request.respond(),
+            Err(e) => GenerationResponse::Error(format!("Invalid generation request: {e}")),
+        };
+        reply_scope
+            .post_message(&JsValue::from_str(&response.to_string()))
+            .expect("Failed to send the generated puzzle");
+    });
+    scope.set_onmessage(Some(closure.as_ref().unchecked_ref()));
+    closure.forget();
+}
@@ -0,0 +1,334 @@
+use crate::model::state::State;
+use crate::svg::{objective_drawing, universe_drawing, Drawing, Shape, SvgOptions};
+use pdf_writer::types::LineCapStyle;
+use pdf_writer::{Content, Finish, Name, Pdf, Rect, Ref, Str};
+
+/// The width of an A4 page in points
+const A4_WIDTH: f64 = 595.0;
+
+/// The height of an A4 page in points
+const A4_HEIGHT: f64 = 842.0;
+
+/// The average width of a character of Helvetica compared to its size,
+/// used to center text in a PDF without measuring every glyph
+const HELVETICA_CHARACTER_WIDTH: f64 = 0.55;
+
+/// How a booklet of puzzles is laid out
+#[derive(Debug, Clone)]
+pub struct BookletOptions {
+    pub page_width: f64,
+    pub page_height: f64,
+    /// The empty space around the puzzles of a page
+    pub margin: f64,
+    /// How many puzzles are placed side by side
+    pub columns: usize,
+    /// How many rows of puzzles there are on a page
+    pub rows: usize,
+    pub title_size: f64,
+    pub svg: SvgOptions,
+}
+
+impl Default for BookletOptions {
+    fn default() -> Self {
+        BookletOptions {
+            page_width: A4_WIDTH,
+            page_height: A4_HEIGHT,
+            margin: 36.0,
+            columns: 2,
+            rows: 3,
+            title_size: 14.0,
+            svg: SvgOptions::default(),
+        }
+    }
+}
+
+impl BookletOptions {
+    pub fn puzzles_per_page(&self) -> usize {
+        self.columns * self.rows
+    }
+}
+
+/// Lays out [puzzles] on pages, followed by pages with their solutions in the same order.
+/// Every puzzle is titled with its number, difficulty and seed.
+pub fn booklet_pages(puzzles: &[State], options: &BookletOptions) -> Vec<Drawing> {
+    let titled: Vec<(String, String, Drawing)> = puzzles
+        .iter()
+        .enumerate()
+        .map(|(index, state)| {
+            let drawing = objective_drawing(state.board.get_mask(), &state.objective, &options.svg);
+            (format!("Puzzle {}", index + 1), caption(state), drawing)
+        })
+        .collect();
+    let answers: Vec<(String, String, Drawing)> = puzzles
+        .iter()
+        .enumerate()
+        .map(|(index, state)| {
+            let drawing = universe_drawing(&state.universe, &options.svg);
+            (format!("Answer {}", index + 1), caption(state), drawing)
+        })
+        .collect();
+    let mut pages = layout_pages(&titled, options);
+    pages.extend(layout_pages(&answers, options));
+    pages
+}
+
+fn caption(state: &State) -> String {
+    match state.seed {
+        Some(seed) => format!("{} - seed {seed}", state.difficulty),
+        None => state.difficulty.to_string(),
+    }
+}
+
+/// Places the drawings in a grid of slots, each below its title and caption
+fn layout_pages(drawings: &[(String, String, Drawing)], options: &BookletOptions) -> Vec<Drawing> {
+    let slot_width = (options.page_width - 2.0 * options.margin) / options.columns as f64;
+    let slot_height = (options.page_height - 2.0 * options.margin) / options.rows as f64;
+    let title_size = options.title_size;
+    let caption_size = title_size * 0.75;
+    // Room for the title, the caption and some space below them
+    let header_height = 1.5 * title_size + 1.5 * caption_size;
+    drawings
+        .chunks(options.puzzles_per_page().max(1))
+        .map(|chunk| {
+            let mut page = Drawing::new(options.page_width, options.page_height);
+            for (index, (title, caption, drawing)) in chunk.iter().enumerate() {
+                let x = options.margin + (index % options.columns) as f64 * slot_width;
+                let y = options.margin + (index / options.columns) as f64 * slot_height;
+                let middle = x + slot_width / 2.0;
+                page.push(text(title, (middle, y + title_size / 2.0), title_size));
+                page.push(text(
+                    caption,
+                    (middle, y + 1.5 * title_size + caption_size / 2.0),
+                    caption_size,
+                ));
+                // Leave a gap of one title between the slots
+                let available_width = slot_width - title_size;
+                let available_height = slot_height - header_height - title_size;
+                let scale =
+                    (available_width / drawing.width).min(available_height / drawing.height);
+                let offset = (middle - drawing.width * scale / 2.0, y + header_height);
+                page.place(drawing, offset, scale);
+            }
+            page
+        })
+        .collect()
+}
+
+fn text(text: &str, position: (f64, f64), size: f64) -> Shape {
+    Shape::Text {
+        position,
+        text: text.to_string(),
+        size,
+        fill: "#000000".to_string(),
+    }
+}
+
+/// Writes the pages as a PDF document, with a point as the unit of the drawings.
+/// Text is written in Helvetica, which every PDF reader provides.
+pub fn to_pdf(pages: &[Drawing]) -> Vec<u8> {
+    let catalog_id = Ref::new(1);
+    let page_tree_id = Ref::new(2);
+    let font_id = Ref::new(3);
+    let font_name = Name(b"F1");
+    // Every page is followed by its content stream
+    let page_ids: Vec<Ref> = (0..pages.len() as i32)
+        .map(|index| Ref::new(4 + 2 * index))
+        .collect();
+
+    let mut pdf = Pdf::new();
+    pdf.catalog(catalog_id).pages(page_tree_id);
+    pdf.pages(page_tree_id)
+        .kids(page_ids.iter().copied())
+        .count(pages.len() as i32);
+    pdf.type1_font(font_id).base_font(Name(b"Helvetica"));
+
+    for (drawing, &page_id) in pages.iter().zip(&page_ids) {
+        let content_id = Ref::new(page_id.get() + 1);
+        let mut page = pdf.page(page_id);
+        page.media_box(Rect::new(
+            0.0,
+            0.0,
+            drawing.width as f32,
+            drawing.height as f32,
+        ));
+        page.parent(page_tree_id);
+        page.contents(content_id);
+        page.resources().fonts().pair(font_name, font_id);
+        page.finish();
+        pdf.stream(content_id, &page_content(drawing, font_name).finish());
+    }
+    pdf.finish()
+}
+
+fn page_content(drawing: &Drawing, font_name: Name) -> Content {
+    let mut content = Content::new();
+    // The drawings have their origin at the top left corner, and PDF pages at the bottom left
+    content.transform([1.0, 0.0, 0.0, -1.0, 0.0, drawing.height as f32]);
+    for shape in &drawing.shapes {
+        match shape {
+            Shape::Polygon { points, fill } => {
+                let (r, g, b) = rgb(fill);
+                content.set_fill_rgb(r, g, b);
+                if let Some(((x, y), rest)) = points.split_first() {
+                    content.move_to(*x as f32, *y as f32);
+                    for (x, y) in rest {
+                        content.line_to(*x as f32, *y as f32);
+                    }
+                    content.close_path();
+                    content.fill_nonzero();
+                }
+            }
+            Shape::Line {
+                start,
+                end,
+                stroke,
+                width,
+                linecap,
+            } => {
+                let (r, g, b) = rgb(stroke);
+                content.set_stroke_rgb(r, g, b);
+                content.set_line_width(*width as f32);
+                content.set_line_cap(match *linecap {
+                    "round" => LineCapStyle::RoundCap,
+                    "square" => LineCapStyle::ProjectingSquareCap,
+                    _ => LineCapStyle::ButtCap,
+                });
+                content.move_to(start.0 as f32, start.1 as f32);
+                content.line_to(end.0 as f32, end.1 as f32);
+                content.stroke();
+            }
+            Shape::Circle {
+                center,
+                radius,
+                fill,
+                stroke,
+                width,
+            } => {
+                let (r, g, b) = rgb(fill);
+                content.set_fill_rgb(r, g, b);
+                let (r, g, b) = rgb(stroke);
+                content.set_stroke_rgb(r, g, b);
+                content.set_line_width(*width as f32);
+                circle(&mut content, *center, *radius);
+                content.fill_nonzero_and_stroke();
+            }
+            Shape::Text {
+                position,
+                text,
+                size,
+                fill,
+            } => {
+                let (r, g, b) = rgb(fill);
+                content.set_fill_rgb(r, g, b);
+                let width = text.chars().count() as f64 * size * HELVETICA_CHARACTER_WIDTH;
+                // Flip the text back upright, with its middle at the position
+                let x = position.0 - width / 2.0;
+                let y = position.1 + size * 0.35;
+                content.begin_text();
+                content.set_font(font_name, *size as f32);
+                content.set_text_matrix([1.0, 0.0, 0.0, -1.0, x as f32, y as f32]);
+                content.show(Str(text.as_bytes()));
+                content.end_text();
+            }
+        }
+    }
+    content
+}
+
+/// Adds a circle to the path, as four Bézier curves
+fn circle(content: &mut Content, (cx, cy): (f64, f64), radius: f64) {
+    // The distance of the control points that makes a quarter of a circle
+    let k = 0.552_284_75 * radius;
+    let point = |x: f64, y: f64| ((cx + x) as f32, (cy + y) as f32);
+    let (x, y) = point(radius, 0.0);
+    content.move_to(x, y);
+    let quarters = [
+        ((radius, k), (k, radius), (0.0, radius)),
+        ((-k, radius), (-radius, k), (-radius, 0.0)),
+        ((-radius, -k), (-k, -radius), (0.0, -radius)),
+        ((k, -radius), (radius, -k), (radius, 0.0)),
+    ];
+    for ((x1, y1), (x2, y2), (x3, y3)) in quarters {
+        let (x1, y1) = point(x1, y1);
+        let (x2, y2) = point(x2, y2);
+        let (x3, y3) = point(x3, y3);
+        content.cubic_to(x1, y1, x2, y2, x3, y3);
+    }
+    content.close_path();
+}
+
+/// Parses a colour written as `#rrggbb`, anything else is black
+fn rgb(color: &str) -> (f32, f32, f32) {
+    let channel = |index: usize| {
+        color
+            .get(index..index + 2)
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+            .map_or(0.0, |value| value as f32 / 255.0)
+    };
+    if color.len() == 7 && color.starts_with('#') {
+        (channel(1), channel(3), channel(5))
+    } else {
+        (0.0, 0.0, 0.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::booklet::{booklet_pages, rgb, to_pdf, BookletOptions};
+    use crate::model::state::State;
+    use crate::svg::Shape;
+
+    #[test]
+    fn puzzles_should_be_followed_by_their_answers() {
+        let options = BookletOptions {
+            columns: 2,
+            rows: 1,
+            ..BookletOptions::default()
+        };
+        let puzzles: Vec<State> = (0..3).map(|_| State::generate(3)).collect();
+        let pages = booklet_pages(&puzzles, &options);
+        assert_eq!(pages.len(), 4);
+        let titles: Vec<String> = pages
+            .iter()
+            .flat_map(|page| &page.shapes)
+            .filter_map(|shape| match shape {
+                Shape::Text { text, .. } if !text.contains("seed") => Some(text.clone()),
+                _ => None,
+            })
+            .filter(|text| text.starts_with("Puzzle") || text.starts_with("Answer"))
+            .collect();
+        assert_eq!(
+            titles,
+            ["Puzzle 1", "Puzzle 2", "Puzzle 3", "Answer 1", "Answer 2", "Answer 3"]
+        );
+    }
+
+    #[test]
+    fn puzzles_should_fit_on_the_page() {
+        let options = BookletOptions::default();
+        let pages = booklet_pages(&[State::generate(4)], &options);
+        for shape in &pages[0].shapes {
+            if let Shape::Polygon { points, .. } = shape {
+                for &(x, y) in points {
+                    assert!((options.margin..options.page_width - options.margin).contains(&x));
+                    assert!((options.margin..options.page_height - options.margin).contains(&y));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn pdf_should_have_a_page_per_drawing() {
+        let pages = booklet_pages(&[State::generate(3)], &BookletOptions::default());
+        let pdf = to_pdf(&pages);
+        let text = String::from_utf8_lossy(&pdf);
+        assert!(text.starts_with("%PDF"));
+        assert!(text.contains("/Count 2"));
+    }
+
+    #[test]
+    fn should_parse_colors() {
+        assert_eq!(rgb("#ff0000"), (1.0, 0.0, 0.0));
+        assert_eq!(rgb("red"), (0.0, 0.0, 0.0));
+    }
+}
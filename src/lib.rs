@@ -0,0 +1,4 @@
+pub mod app;
+pub mod booklet;
+pub mod model;
+pub mod svg;
@@ -1,10 +1,7 @@
 use std::thread::sleep;
 use std::time::Duration;
-use crate::app::App;
-use crate::model::universe::Universe;
-
-mod app;
-mod model;
+use vintergatan::app::App;
+use vintergatan::model::universe::Universe;
 
 const CONSOLE: bool = false;
 
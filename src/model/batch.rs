@@ -0,0 +1,118 @@
+use crate::model::generation_error::GenerationError;
+use crate::model::grid::GridType;
+use crate::model::mask::Mask;
+use crate::model::state::State;
+use crate::model::topology::Topology;
+use rayon::prelude::*;
+use rayon::ThreadPoolBuilder;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+/// Which puzzles a batch consists of, and how many threads generate them
+#[derive(Debug, Clone)]
+pub struct BatchOptions {
+    pub count: usize,
+    pub size: usize,
+    /// The seed of the first puzzle, the following puzzles get the following seeds
+    pub first_seed: u64,
+    /// How many threads generate puzzles, 0 for one per core
+    pub threads: usize,
+}
+
+/// How many of the puzzles of a batch have been generated
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Progress {
+    pub generated: usize,
+    pub total: usize,
+}
+
+/// Generates the puzzles of [options] on several threads, and returns them in the order
+/// of their seeds, so the same options always give the same puzzles.
+/// [on_progress] is called from the generating threads whenever a puzzle is done.
+/// Once [cancel] is set no more puzzles are started, and only the finished ones are returned.
+pub fn generate_batch(
+    options: &BatchOptions,
+    on_progress: impl Fn(Progress) + Sync,
+    cancel: &AtomicBool,
+) -> Result<Vec<State>, String> {
+    let pool = ThreadPoolBuilder::new()
+        .num_threads(options.threads)
+        .build()
+        .map_err(|e| format!("Could not start the threads: {e}"))?;
+    let generated = AtomicUsize::new(0);
+    let states = pool.install(|| {
+        (0..options.count as u64)
+            .into_par_iter()
+            .map(|index| {
+                if cancel.load(Ordering::Relaxed) {
+                    return None;
+                }
+                let state = State::generate_with_seed(
+                    Mask::full(options.size, options.size),
+                    Topology::default(),
+                    GridType::default(),
+                    options.first_seed.wrapping_add(index),
+                );
+                on_progress(Progress {
+                    generated: generated.fetch_add(1, Ordering::Relaxed) + 1,
+                    total: options.count,
+                });
+                Some(state)
+            })
+            .collect::<Vec<Option<Result<State, GenerationError>>>>()
+    });
+    states
+        .into_iter()
+        .flatten()
+        .collect::<Result<Vec<State>, GenerationError>>()
+        .map_err(|e| format!("Could not generate a puzzle: {e}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::batch::{generate_batch, BatchOptions, Progress};
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Mutex;
+
+    fn options(count: usize) -> BatchOptions {
+        BatchOptions {
+            count,
+            size: 4,
+            first_seed: 42,
+            threads: 2,
+        }
+    }
+
+    #[test]
+    fn batch_should_be_ordered_by_seed_and_report_progress() {
+        let progress = Mutex::new(Vec::new());
+        let states = generate_batch(
+            &options(4),
+            |p| progress.lock().unwrap().push(p),
+            &AtomicBool::new(false),
+        )
+        .unwrap();
+        let seeds: Vec<Option<u64>> = states.iter().map(|state| state.seed).collect();
+        assert_eq!(seeds, [Some(42), Some(43), Some(44), Some(45)]);
+        let mut generated: Vec<usize> = progress
+            .into_inner()
+            .unwrap()
+            .iter()
+            .map(|p: &Progress| p.generated)
+            .collect();
+        generated.sort();
+        assert_eq!(generated, [1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn cancelled_batch_should_stop_early() {
+        let cancel = AtomicBool::new(false);
+        let states = generate_batch(
+            &options(50),
+            |_| cancel.store(true, Ordering::Relaxed),
+            &cancel,
+        )
+        .unwrap();
+        assert!(!states.is_empty());
+        assert!(states.len() < 50);
+    }
+}
@@ -1,50 +1,64 @@
-use crate::model::board_error::BoardError;
+use crate::model::board_error::{BoardError, GalaxyReport, MergedCenters, Problem};
 use crate::model::border::Border;
 use crate::model::galaxy::Galaxy;
-use crate::model::objective::Objective;
-use crate::model::position::{CenterPlacement, Position};
-use itertools::Itertools;
+use crate::model::grid::GridType;
+use crate::model::mask::Mask;
+use crate::model::objective::{GalaxyCenter, Objective};
+use crate::model::position::Position;
+use crate::model::solution_error::SolutionError;
+use crate::model::symmetry::Symmetry;
+use crate::model::topology::{Surface, Topology};
+use crate::model::universe::Universe;
 use petgraph::graphmap::UnGraphMap;
-use petgraph::visit::{FilterEdge, Visitable};
 use std::collections::{BTreeSet, HashMap, HashSet};
 
 #[derive(Clone, Debug)]
 pub struct Board {
-    width: usize,
-    height: usize,
+    mask: Mask,
+    surface: Surface,
     graph: UnGraphMap<Position, ()>,
 }
 
 impl Board {
     pub fn new(width: usize, height: usize) -> Self {
+        Self::new_with_mask(
+            Mask::full(width, height),
+            Topology::default(),
+            GridType::default(),
+        )
+    }
+
+    pub fn new_with_mask(mask: Mask, topology: Topology, grid: GridType) -> Self {
+        let surface = Surface::new(grid, topology, mask.width(), mask.height());
         Board {
-            width,
-            height,
+            mask,
+            surface,
             graph: Default::default(),
         }
     }
 
     pub fn get_width(&self) -> usize {
-        self.width
+        self.mask.width()
     }
 
     pub fn get_height(&self) -> usize {
-        self.width
+        self.mask.height()
     }
 
-    fn contains(&self, position: &Position) -> bool {
-        position.row >= 0
-            && position.row < self.height as i32
-            && position.column >= 0
-            && position.column < self.width as i32
+    pub fn get_mask(&self) -> &Mask {
+        &self.mask
+    }
+
+    pub fn get_surface(&self) -> &Surface {
+        &self.surface
+    }
+
+    pub fn contains(&self, position: &Position) -> bool {
+        self.mask.contains(position)
     }
 
     fn get_positions(&self) -> impl Iterator<Item = Position> + use<'_> {
-        (0..self.height).into_iter().flat_map(move |row| {
-            (0..self.width)
-                .into_iter()
-                .map(move |col| Position::new(row as i32, col as i32))
-        })
+        self.mask.positions()
     }
 
     pub fn is_active(&self, border: &Border) -> bool {
@@ -53,7 +67,7 @@ impl Board {
 
     /// Adds a wall between [p1] and [p2], returns true if the wall did not previously exist
     pub fn add_wall(&mut self, p1: Position, p2: Position) -> bool {
-        debug_assert!(p1.is_adjacent_to(&p2));
+        debug_assert!(self.surface.are_adjacent(&p1, &p2));
         debug_assert!(self.contains(&p1));
         debug_assert!(self.contains(&p2));
         let result = self.graph.add_edge(p1, p2, ());
@@ -62,7 +76,7 @@ impl Board {
 
     /// Removes the wall between [p1] and [p2], if it exists. Returns true if the wall existed
     pub fn remove_wall(&mut self, p1: Position, p2: Position) -> bool {
-        debug_assert!(p1.is_adjacent_to(&p2));
+        debug_assert!(self.surface.are_adjacent(&p1, &p2));
         debug_assert!(self.contains(&p1));
         debug_assert!(self.contains(&p2));
         let result = self.graph.remove_edge(p1, p2);
@@ -99,7 +113,7 @@ impl Board {
             while let Some(p) = queue.pop_first() {
                 component.insert(p);
                 remaining_positions.remove(&p);
-                for neighbour in p.adjacent() {
+                for neighbour in self.surface.adjacent(&p) {
                     if component.contains(&neighbour) {
                         continue;
                     }
@@ -121,30 +135,159 @@ impl Board {
         galaxies
     }
 
+    /// Returns the universe whose galaxies are the regions of the board, if the walls divide
+    /// the board into galaxies with [symmetry] and every wall separates two of them.
+    /// A completed board is converted into the solution that the player found.
+    pub fn to_universe(&self, symmetry: Symmetry) -> Result<Universe, SolutionError> {
+        let galaxies = self.get_galaxies();
+        let galaxy_index_by_position: HashMap<Position, usize> = galaxies
+            .iter()
+            .enumerate()
+            .flat_map(|(index, galaxy)| galaxy.get_positions().map(move |&p| (p, index)))
+            .collect();
+        let mut borders: Vec<Border> = self.get_borders().collect();
+        borders.sort();
+        if let Some(&border) = borders.iter().find(|border| {
+            galaxy_index_by_position.get(&border.p1()) == galaxy_index_by_position.get(&border.p2())
+        }) {
+            return Err(SolutionError::InnerWall(border));
+        }
+        if let Some(&position) = galaxies
+            .iter()
+            .filter(|galaxy| !self.surface.is_valid(galaxy, symmetry))
+            .find_map(|galaxy| galaxy.get_positions().min())
+        {
+            return Err(SolutionError::InvalidGalaxy(position));
+        }
+
+        let mut universe = Universe::new_with_mask(
+            self.mask.clone(),
+            symmetry,
+            self.surface.topology(),
+            self.surface.grid_type(),
+        );
+        for galaxy in &galaxies {
+            universe.add_galaxy(galaxy);
+        }
+        Ok(universe)
+    }
+
+    /// Returns the galaxies of the board that are finished, i.e. valid galaxies that contain
+    /// exactly one center of the objective and are symmetric around it, together with that center.
+    pub fn get_completed_galaxies(&self, objective: &Objective) -> Vec<(GalaxyCenter, Galaxy)> {
+        self.get_galaxies()
+            .into_iter()
+            .filter(|galaxy| self.surface.is_valid(galaxy, objective.symmetry))
+            .filter_map(
+                |galaxy| match self.centers_in(&galaxy, objective).as_slice() {
+                    [&gc] if Some(gc.position) == self.surface.center(&galaxy) => {
+                        Some((gc, galaxy))
+                    }
+                    _ => None,
+                },
+            )
+            .collect()
+    }
+
+    /// Returns the centers of [objective] that lie in [galaxy], at least partially
+    fn centers_in<'a>(&self, galaxy: &Galaxy, objective: &'a Objective) -> Vec<&'a GalaxyCenter> {
+        objective
+            .centers
+            .iter()
+            .filter(|gc| {
+                self.surface
+                    .center_positions(&gc.position)
+                    .iter()
+                    .any(|p| galaxy.contains_position(p))
+            })
+            .collect()
+    }
+
+    /// Explains what's wrong with the region [galaxy] with respect to [objective]
+    fn report_galaxy(&self, galaxy: &Galaxy, objective: &Objective) -> GalaxyReport {
+        let centers = self.centers_in(galaxy, objective);
+        // Cut centers lie partially in other regions, so they're reported on their own
+        let (cut, whole): (Vec<&GalaxyCenter>, Vec<&GalaxyCenter>) = centers
+            .iter()
+            .partition(|gc| self.is_center_cut(&gc.position));
+        let mut problems: Vec<Problem> = cut
+            .iter()
+            .map(|gc| Problem::CutCenter(gc.position))
+            .collect();
+        match whole.as_slice() {
+            [] if cut.is_empty() => problems.push(Problem::NoCenter),
+            [gc] if cut.is_empty() => {
+                if let Some(expected) = gc.size.filter(|&size| size != galaxy.size()) {
+                    problems.push(Problem::IncorrectSize {
+                        expected,
+                        actual: galaxy.size(),
+                    });
+                }
+                let mut cells: Vec<Position> = galaxy.get_positions().copied().collect();
+                cells.sort();
+                for cell in cells {
+                    for mirror in self.surface.images(objective.symmetry, &gc.position, &cell) {
+                        if !galaxy.contains_position(&mirror) {
+                            problems.push(Problem::Asymmetric { cell, mirror });
+                        }
+                    }
+                }
+            }
+            [] | [_] => {}
+            _ => problems.push(Problem::MultipleCenters(
+                whole.iter().map(|gc| gc.position).collect(),
+            )),
+        }
+        GalaxyReport {
+            galaxy: galaxy.clone(),
+            centers: centers.iter().map(|gc| gc.position).collect(),
+            problems,
+        }
+    }
+
     pub fn compute_error(&self, objective: &Objective) -> BoardError {
         let dangling_borders = self.get_dangling_borders().collect();
 
         let galaxies = self.get_galaxies();
+        let galaxy_reports: Vec<GalaxyReport> = galaxies
+            .iter()
+            .map(|galaxy| self.report_galaxy(galaxy, objective))
+            .collect();
+        let merged_centers: Vec<MergedCenters> = galaxy_reports
+            .iter()
+            .flat_map(|report| {
+                report.problems.iter().filter_map(|problem| match problem {
+                    Problem::MultipleCenters(centers) => Some(MergedCenters {
+                        centers: centers.clone(),
+                        region: report.galaxy.clone(),
+                    }),
+                    _ => None,
+                })
+            })
+            .collect();
         let galaxy_by_position: HashMap<Position, &Galaxy> = galaxies
             .iter()
             .flat_map(|galaxy| galaxy.get_positions().copied().map(move |p| (p, galaxy)))
             .collect();
+        // Centers that don't surround cells of the board have no galaxy
         let galaxy_by_objective_center: HashMap<Position, &Galaxy> = objective
             .centers
             .iter()
-            .map(|gc| {
-                let some_position_around_center = match gc.position.get_center_placement() {
-                    CenterPlacement::Center(p) => p,
-                    CenterPlacement::VerticalBorder(b) => b.p1(),
-                    CenterPlacement::HorizontalBorder(b) => b.p1(),
-                    CenterPlacement::Intersection(r) => r.top_left(),
-                };
-                let &galaxy = galaxy_by_position
-                    .get(&some_position_around_center)
-                    .unwrap();
-                (gc.position, galaxy)
+            .filter_map(|gc| {
+                let positions = self.surface.center_positions(&gc.position);
+                if positions.is_empty() || !positions.iter().all(|p| self.contains(p)) {
+                    return None;
+                }
+                let &galaxy = galaxy_by_position.get(&positions[0])?;
+                Some((gc.position, galaxy))
             })
             .collect();
+        let invalid_centers = objective
+            .centers
+            .iter()
+            .map(|gc| gc.position)
+            .filter(|center| !galaxy_by_objective_center.contains_key(center))
+            .collect();
 
         let cut_centers: HashSet<Position> = objective
             .centers
@@ -158,7 +301,7 @@ impl Board {
             .iter()
             .filter_map(|gc| {
                 if let Some(size) = gc.size {
-                    let galaxy = galaxy_by_objective_center.get(&gc.position).unwrap();
+                    let galaxy = galaxy_by_objective_center.get(&gc.position)?;
                     if galaxy.size() != size {
                         Some(gc.position)
                     } else {
@@ -170,12 +313,20 @@ impl Board {
             })
             .collect();
 
+        // Merged centers are reported as such, rather than as asymmetric
+        let merged: HashSet<Position> = merged_centers
+            .iter()
+            .flat_map(|merged| merged.centers.iter().copied())
+            .collect();
         let asymmetric_centers = objective
             .centers
             .iter()
+            .filter(|gc| !merged.contains(&gc.position))
             .filter_map(|gc| {
-                let galaxy = galaxy_by_objective_center.get(&gc.position).unwrap();
-                if galaxy.center() != gc.position || !galaxy.is_valid() {
+                let galaxy = galaxy_by_objective_center.get(&gc.position)?;
+                if self.surface.center(galaxy) != Some(gc.position)
+                    || !self.surface.is_valid(galaxy, objective.symmetry)
+                {
                     Some(gc.position)
                 } else {
                     None
@@ -193,93 +344,62 @@ impl Board {
             .filter(|p| !centerfull_cells.contains(p))
             .collect();
 
+        let missing_walls = objective
+            .walls
+            .iter()
+            .filter(|border| !self.is_active(border))
+            .copied()
+            .collect();
+
         BoardError {
             dangling_borders,
             incorrect_galaxy_sizes,
             centerless_cells,
             cut_centers,
             asymmetric_centers,
+            missing_walls,
+            invalid_centers,
+            merged_centers,
+            galaxy_reports,
         }
     }
 
+    /// Returns whether there's a wall between any of the cells surrounding [center]
     fn is_center_cut(&self, center: &Position) -> bool {
-        match center.get_center_placement() {
-            CenterPlacement::Center(_) => false,
-            CenterPlacement::VerticalBorder(border) => self.is_active(&border),
-            CenterPlacement::HorizontalBorder(border) => self.is_active(&border),
-            CenterPlacement::Intersection(rect) => {
-                let top_left = Position::new(rect.min_row, rect.min_column);
-                let top_right = Position::new(rect.min_row, rect.max_column);
-                let bottom_left = Position::new(rect.max_row, rect.min_column);
-                let bottom_right = Position::new(rect.max_row, rect.max_column);
-                self.is_wall(top_left, top_right)
-                    || self.is_wall(top_right, bottom_right)
-                    || self.is_wall(bottom_right, bottom_left)
-                    || self.is_wall(top_left, bottom_left)
-            }
-        }
+        let positions = self.surface.center_positions(center);
+        positions.iter().any(|p1| {
+            positions
+                .iter()
+                .any(|p2| self.surface.are_adjacent(p1, p2) && self.is_wall(*p1, *p2))
+        })
     }
 
     fn get_dangling_borders(&self) -> impl Iterator<Item = Border> + use<'_> {
         self.get_borders().filter(|border| self.is_dangling(border))
     }
 
+    /// Returns whether there's a wall between [p1] and [p2], or if they're on different sides of the edge of the board
+    fn is_wall_or_edge(&self, p1: Position, p2: Position) -> bool {
+        !self.contains(&p1) || !self.contains(&p2) || self.is_wall(p1, p2)
+    }
+
     fn is_dangling(&self, border: &Border) -> bool {
         let p1 = border.p1();
         let p2 = border.p2();
-        if border.is_vertical() {
-            // Check that the border connects to something above
-            if p1.row != 0 {
-                let p1_up = p1.up();
-                let p2_up = p2.up();
-                if !self.is_wall(p1, p1_up)
-                    && !self.is_wall(p1_up, p2_up)
-                    && !self.is_wall(p2_up, p2)
-                {
-                    return true;
-                }
-            }
-
-            // Check that the border connects to something below
-            if p1.row != (self.height - 1) as i32 {
-                let p1_down = p1.down();
-                let p2_down = p2.down();
-                if !self.is_wall(p1, p1_down)
-                    && !self.is_wall(p1_down, p2_down)
-                    && !self.is_wall(p2_down, p2)
-                {
-                    return true;
-                }
-            }
-        } else {
-            // The border is horizontal
-
-            // Check that the border connects to something to the left
-            if p1.column != 0 {
-                let p1_left = p1.left();
-                let p2_left = p2.left();
-                if !self.is_wall(p1, p1_left)
-                    && !self.is_wall(p1_left, p2_left)
-                    && !self.is_wall(p2_left, p2)
-                {
-                    return true;
-                }
-            }
-
-            // Check that the border connects to something below
-            if p1.column != (self.width - 1) as i32 {
-                let p1_right = p1.right();
-                let p2_right = p2.right();
-                if !self.is_wall(p1, p1_right)
-                    && !self.is_wall(p1_right, p2_right)
-                    && !self.is_wall(p2_right, p2)
-                {
-                    return true;
-                }
-            }
-        }
-
-        false
+        let is_border = |q1: Position, q2: Position| (q1, q2) == (p1, p2) || (q2, q1) == (p1, p2);
+        // The ends of the border are the corners of p1 that p2 meets as well
+        let mut ends = self
+            .surface
+            .corners(&p1)
+            .into_iter()
+            .filter(|corner| corner.contains(&p2));
+        // An end is dangling if it doesn't connect to another wall or the edge of the board
+        ends.any(|corner| {
+            (0..corner.len())
+                .map(|i| (corner[i], corner[(i + 1) % corner.len()]))
+                .filter(|&(q1, q2)| !is_border(q1, q2))
+                .all(|(q1, q2)| !self.is_wall_or_edge(q1, q2))
+        })
     }
 }
 
@@ -297,4 +417,253 @@ mod tests {
             assert_eq!(galaxies[0].size(), 1);
         }
     }
+
+    mod is_dangling {
+        use crate::model::board::Board;
+        use crate::model::border::Border;
+        use crate::model::grid::GridType;
+        use crate::model::mask::Mask;
+        use crate::model::position::Position;
+        use crate::model::topology::Topology;
+
+        #[test]
+        fn wall_ending_in_the_middle_should_be_dangling() {
+            let mut board = Board::new(3, 3);
+            board.add_wall(Position::new(0, 1), Position::new(0, 2));
+            assert!(board.is_dangling(&Border::new(Position::new(0, 1), Position::new(0, 2))));
+        }
+
+        #[test]
+        fn wall_ending_at_hole_should_not_be_dangling() {
+            let mask = Mask::full(3, 3).without(&Position::new(1, 1));
+            let mut board = Board::new_with_mask(mask, Topology::default(), GridType::default());
+            board.add_wall(Position::new(0, 1), Position::new(0, 2));
+            assert!(!board.is_dangling(&Border::new(Position::new(0, 1), Position::new(0, 2))));
+        }
+
+        #[test]
+        fn hexagonal_walls_meeting_at_a_corner_should_not_be_dangling() {
+            let mut board =
+                Board::new_with_mask(Mask::full(3, 3), Topology::default(), GridType::Hexagonal);
+            // (1, 1) is adjacent to both (0, 1) and (0, 2), which are adjacent to each other
+            let border = Border::new(Position::new(1, 1), Position::new(0, 1));
+            board.add_wall(border.p1(), border.p2());
+            assert!(board.is_dangling(&border));
+            board.add_wall(Position::new(1, 1), Position::new(0, 2));
+            board.add_wall(Position::new(0, 1), Position::new(1, 0));
+            assert!(!board.is_dangling(&border));
+        }
+    }
+
+    mod compute_error {
+        use crate::model::board::Board;
+        use crate::model::board_error::Problem;
+        use crate::model::border::Border;
+        use crate::model::grid::GridType;
+        use crate::model::mask::Mask;
+        use crate::model::objective::{CenterColor, GalaxyCenter, Objective};
+        use crate::model::position::Position;
+        use crate::model::symmetry::Symmetry;
+        use crate::model::topology::Topology;
+
+        fn two_singles_with_given_wall() -> Objective {
+            Objective {
+                centers: vec![
+                    GalaxyCenter {
+                        position: Position::new(0, 0),
+                        size: None,
+                        color: CenterColor::White,
+                    },
+                    GalaxyCenter {
+                        position: Position::new(0, 2),
+                        size: None,
+                        color: CenterColor::White,
+                    },
+                ],
+                walls: vec![Border::new(Position::new(0, 0), Position::new(0, 1))],
+                symmetry: Symmetry::default(),
+                topology: Topology::default(),
+                grid: GridType::default(),
+            }
+        }
+
+        #[test]
+        fn removed_given_wall_should_be_missing() {
+            let board = Board::new(2, 1);
+            let error = board.compute_error(&two_singles_with_given_wall());
+            assert!(error
+                .missing_walls
+                .contains(&Border::new(Position::new(0, 0), Position::new(0, 1))));
+            assert!(!error.is_error_free());
+        }
+
+        #[test]
+        fn board_with_given_wall_should_be_error_free() {
+            let mut board = Board::new(2, 1);
+            board.add_wall(Position::new(0, 0), Position::new(0, 1));
+            let error = board.compute_error(&two_singles_with_given_wall());
+            assert!(error.is_error_free());
+        }
+
+        fn single_center(row: i32, column: i32) -> Objective {
+            Objective {
+                centers: vec![GalaxyCenter {
+                    position: Position::new(row, column),
+                    size: None,
+                    color: CenterColor::White,
+                }],
+                walls: vec![],
+                symmetry: Symmetry::default(),
+                topology: Topology::default(),
+                grid: GridType::default(),
+            }
+        }
+
+        #[test]
+        fn region_with_two_centers_should_be_reported() {
+            let mut objective = two_singles_with_given_wall();
+            objective.walls.clear();
+            let error = Board::new(2, 1).compute_error(&objective);
+            assert_eq!(error.galaxy_reports.len(), 1);
+            let centers = vec![Position::new(0, 0), Position::new(0, 2)];
+            assert_eq!(
+                error.galaxy_reports[0].problems,
+                vec![Problem::MultipleCenters(centers.clone())]
+            );
+            assert_eq!(error.merged_centers.len(), 1);
+            assert_eq!(error.merged_centers[0].centers, centers);
+            assert_eq!(error.merged_centers[0].region.size(), 2);
+            assert!(error.is_merged_center(&Position::new(0, 0)));
+            assert!(error.asymmetric_centers.is_empty());
+            assert!(!error.is_error_free());
+            assert_eq!(
+                error
+                    .report_for_center(&Position::new(0, 2))
+                    .unwrap()
+                    .centers,
+                vec![Position::new(0, 0), Position::new(0, 2)]
+            );
+        }
+
+        #[test]
+        fn asymmetric_cell_should_be_reported_with_its_mirror() {
+            let error = Board::new(2, 1).compute_error(&single_center(0, 0));
+            assert_eq!(
+                error.galaxy_reports[0].problems,
+                vec![Problem::Asymmetric {
+                    cell: Position::new(0, 1),
+                    mirror: Position::new(0, -1),
+                }]
+            );
+            assert_eq!(
+                error.messages(),
+                vec!["The galaxy at (0, 0): (0, 1) is part of it, but its mirror image (0, -1) isn't"]
+            );
+        }
+
+        #[test]
+        fn center_outside_board_should_not_panic() {
+            let mut board = Board::new(2, 1);
+            board.add_wall(Position::new(0, 0), Position::new(0, 1));
+            let mut objective = two_singles_with_given_wall();
+            objective.centers[1].position = Position::new(0, 6);
+            let error = board.compute_error(&objective);
+            assert!(error.invalid_centers.contains(&Position::new(0, 6)));
+            assert!(!error.is_error_free());
+        }
+
+        #[test]
+        fn region_without_center_should_be_reported() {
+            let mut board = Board::new(2, 1);
+            board.add_wall(Position::new(0, 0), Position::new(0, 1));
+            let error = board.compute_error(&single_center(0, 0));
+            assert!(error.galaxy_reports[0].problems.is_empty());
+            assert_eq!(error.galaxy_reports[1].problems, vec![Problem::NoCenter]);
+        }
+
+        #[test]
+        fn galaxy_across_wrapped_edge_should_be_error_free() {
+            let center = |row, column| GalaxyCenter {
+                position: Position::new(row, column),
+                size: None,
+                color: CenterColor::White,
+            };
+            let objective = Objective {
+                centers: vec![center(0, 7), center(0, 3)],
+                walls: vec![],
+                symmetry: Symmetry::default(),
+                topology: Topology::HorizontalWrap,
+                grid: GridType::default(),
+            };
+            let mut board = Board::new_with_mask(
+                Mask::full(4, 1),
+                Topology::HorizontalWrap,
+                GridType::default(),
+            );
+            board.add_wall(Position::new(0, 0), Position::new(0, 1));
+            board.add_wall(Position::new(0, 2), Position::new(0, 3));
+            assert!(board.compute_error(&objective).is_error_free());
+            assert_eq!(board.get_completed_galaxies(&objective).len(), 2);
+        }
+
+        #[test]
+        fn triangles_sharing_a_border_should_be_error_free() {
+            // Two triangles pointing up and down, centered on the middle of the slanted border between them
+            let objective = Objective {
+                centers: vec![GalaxyCenter {
+                    position: Position::new(3, 1),
+                    size: None,
+                    color: CenterColor::White,
+                }],
+                walls: vec![],
+                symmetry: Symmetry::default(),
+                topology: Topology::default(),
+                grid: GridType::Triangular,
+            };
+            let board =
+                Board::new_with_mask(Mask::full(2, 1), Topology::default(), GridType::Triangular);
+            assert!(board.compute_error(&objective).is_error_free());
+            assert_eq!(board.get_completed_galaxies(&objective).len(), 1);
+        }
+    }
+
+    mod to_universe {
+        use crate::model::board::Board;
+        use crate::model::border::Border;
+        use crate::model::position::Position;
+        use crate::model::solution_error::SolutionError;
+        use crate::model::symmetry::Symmetry;
+        use crate::model::universe::Universe;
+
+        #[test]
+        fn solved_board_should_convert_back_to_its_universe() {
+            let universe = Universe::generate(5, 5);
+            let board = universe.to_board();
+            let solution = board.to_universe(universe.symmetry()).unwrap();
+            assert_eq!(solution.get_walls(), universe.get_walls());
+            assert_eq!(solution.get_score(), universe.get_score());
+        }
+
+        #[test]
+        fn wall_inside_a_galaxy_should_be_rejected() {
+            let mut board = Board::new(3, 2);
+            board.add_wall(Position::new(0, 1), Position::new(1, 1));
+            assert_eq!(
+                board.to_universe(Symmetry::default()).unwrap_err(),
+                SolutionError::InnerWall(Border::new(Position::new(0, 1), Position::new(1, 1)))
+            );
+        }
+
+        #[test]
+        fn asymmetric_region_should_be_rejected() {
+            // An L shaped region and the single cell it surrounds
+            let mut board = Board::new(2, 2);
+            board.add_wall(Position::new(0, 1), Position::new(1, 1));
+            board.add_wall(Position::new(1, 0), Position::new(1, 1));
+            assert_eq!(
+                board.to_universe(Symmetry::default()).unwrap_err(),
+                SolutionError::InvalidGalaxy(Position::new(0, 0))
+            );
+        }
+    }
 }
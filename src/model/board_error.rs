@@ -1,6 +1,8 @@
 use crate::model::border::Border;
+use crate::model::galaxy::Galaxy;
 use crate::model::position::Position;
 use std::collections::HashSet;
+use std::fmt::{Display, Formatter};
 
 #[derive(Debug, Default)]
 pub struct BoardError {
@@ -9,6 +11,46 @@ pub struct BoardError {
     pub centerless_cells: HashSet<Position>,
     pub cut_centers: HashSet<Position>,
     pub asymmetric_centers: HashSet<Position>,
+    pub missing_walls: HashSet<Border>,
+    /// Centers of the objective that don't surround cells of the board, see [crate::model::objective::Objective::validate]
+    pub invalid_centers: HashSet<Position>,
+    /// Regions that enclose more than one center, ordered by their first cell
+    pub merged_centers: Vec<MergedCenters>,
+    /// One report for each region of the board enclosed by walls, ordered by their first cell
+    pub galaxy_reports: Vec<GalaxyReport>,
+}
+
+/// A region that encloses more than one center, so it's missing walls between them
+#[derive(Debug, Clone)]
+pub struct MergedCenters {
+    pub centers: Vec<Position>,
+    /// The cells of the region
+    pub region: Galaxy,
+}
+
+/// Something that's wrong with a region of the board, i.e. a set of cells enclosed by walls
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Problem {
+    /// The region doesn't contain any center
+    NoCenter,
+    /// The region contains more than one center, so a wall is missing between them
+    MultipleCenters(Vec<Position>),
+    /// A wall runs through the center
+    CutCenter(Position),
+    /// The region has a different number of cells than its center asks for
+    IncorrectSize { expected: usize, actual: usize },
+    /// The cell is part of the region, but its mirror image around the center isn't
+    Asymmetric { cell: Position, mirror: Position },
+}
+
+/// The diagnostics of one region of the board
+#[derive(Debug, Clone)]
+pub struct GalaxyReport {
+    /// The cells of the region
+    pub galaxy: Galaxy,
+    /// The centers of the objective that lie in the region, at least partially
+    pub centers: Vec<Position>,
+    pub problems: Vec<Problem>,
 }
 
 impl BoardError {
@@ -22,5 +64,99 @@ impl BoardError {
             && self.centerless_cells.is_empty()
             && self.asymmetric_centers.is_empty()
             && self.cut_centers.is_empty()
+            && self.missing_walls.is_empty()
+            && self.merged_centers.is_empty()
+            && self.invalid_centers.is_empty()
+    }
+
+    /// Returns whether [center] shares its region with another center
+    pub fn is_merged_center(&self, center: &Position) -> bool {
+        self.merged_centers
+            .iter()
+            .any(|merged| merged.centers.contains(center))
+    }
+
+    /// Returns the report of the region that contains [center], at least partially
+    pub fn report_for_center(&self, center: &Position) -> Option<&GalaxyReport> {
+        self.galaxy_reports
+            .iter()
+            .find(|report| report.centers.contains(center))
+    }
+
+    /// Returns a human-readable explanation of every error, one per line
+    pub fn messages(&self) -> Vec<String> {
+        let mut messages: Vec<String> = self
+            .galaxy_reports
+            .iter()
+            .flat_map(|report| report.messages())
+            .collect();
+        let mut dangling_borders: Vec<&Border> = self.dangling_borders.iter().collect();
+        dangling_borders.sort();
+        for border in dangling_borders {
+            messages.push(format!(
+                "The wall between {} and {} doesn't connect to another wall or the edge",
+                border.p1(),
+                border.p2()
+            ));
+        }
+        let mut invalid_centers: Vec<&Position> = self.invalid_centers.iter().collect();
+        invalid_centers.sort();
+        for center in invalid_centers {
+            messages.push(format!("The center {center} is not on the board"));
+        }
+        let mut missing_walls: Vec<&Border> = self.missing_walls.iter().collect();
+        missing_walls.sort();
+        for border in missing_walls {
+            messages.push(format!(
+                "The given wall between {} and {} has been removed",
+                border.p1(),
+                border.p2()
+            ));
+        }
+        messages
+    }
+}
+
+impl Display for BoardError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.messages().join("\n"))
+    }
+}
+
+impl GalaxyReport {
+    /// Returns the cell of the region that comes first in reading order
+    pub fn first_cell(&self) -> Option<Position> {
+        self.galaxy.get_positions().min().copied()
+    }
+
+    /// Returns a human-readable explanation of each problem
+    pub fn messages(&self) -> Vec<String> {
+        let first_cell = self.first_cell().map(|p| p.to_string()).unwrap_or_default();
+        self.problems
+            .iter()
+            .map(|problem| format!("The galaxy at {first_cell}: {problem}"))
+            .collect()
+    }
+}
+
+impl Display for Problem {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Problem::NoCenter => write!(f, "it doesn't contain a center"),
+            Problem::MultipleCenters(centers) => write!(
+                f,
+                "it contains {} centers, they need to be separated by walls",
+                centers.len()
+            ),
+            Problem::CutCenter(_) => write!(f, "a wall runs through its center"),
+            Problem::IncorrectSize { expected, actual } => write!(
+                f,
+                "it has {actual} cells, but its center asks for {expected}"
+            ),
+            Problem::Asymmetric { cell, mirror } => write!(
+                f,
+                "{cell} is part of it, but its mirror image {mirror} isn't"
+            ),
+        }
     }
 }
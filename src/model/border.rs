@@ -8,8 +8,11 @@ pub struct Border {
 }
 
 impl Border {
+    /// Creates the border between two adjacent positions. What's adjacent depends on the grid,
+    /// and on a wrapping board the positions can also be at opposite ends of the same row or column,
+    /// see [crate::model::topology::Surface].
     pub fn new(p1: Position, p2: Position) -> Self {
-        debug_assert!(p1.is_adjacent_to(&p2));
+        debug_assert!(p1 != p2);
         Self {
             p1: min(p1, p2),
             p2: max(p1, p2),
@@ -0,0 +1,172 @@
+use crate::model::generation_error::{ConstraintViolation, GenerationError};
+use crate::model::grid::GridType;
+use crate::model::universe::Universe;
+use std::ops::RangeInclusive;
+
+/// Limits on the galaxies of a generated universe. The default allows every universe.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GenerationConstraints {
+    /// How many cells each galaxy may have
+    pub galaxy_size: RangeInclusive<usize>,
+    /// How many galaxies the universe may have
+    pub galaxy_count: RangeInclusive<usize>,
+    /// The largest fraction of the galaxies that may be rectangles, only checked on square grids
+    pub max_rectangular_fraction: f64,
+    /// Whether galaxies of a single cell are allowed, which overrides the smallest galaxy size
+    pub single_cells: bool,
+}
+
+impl Default for GenerationConstraints {
+    fn default() -> Self {
+        GenerationConstraints {
+            galaxy_size: 1..=usize::MAX,
+            galaxy_count: 0..=usize::MAX,
+            max_rectangular_fraction: 1.0,
+            single_cells: true,
+        }
+    }
+}
+
+impl GenerationConstraints {
+    /// Returns the fewest cells that a galaxy may have
+    pub fn min_galaxy_size(&self) -> usize {
+        let smallest = if self.single_cells { 1 } else { 2 };
+        (*self.galaxy_size.start()).max(smallest)
+    }
+
+    pub fn max_galaxy_size(&self) -> usize {
+        *self.galaxy_size.end()
+    }
+
+    /// Returns how many of [count] galaxies may be rectangles
+    pub fn max_rectangular(&self, count: usize) -> usize {
+        (self.max_rectangular_fraction * count as f64)
+            .floor()
+            .max(0.0) as usize
+    }
+
+    /// Checks that some division of [cells] cells satisfies the sizes and the count,
+    /// without looking at whether the galaxies can be symmetric
+    pub fn validate(&self, cells: usize) -> Result<(), GenerationError> {
+        let (min_size, max_size) = (self.min_galaxy_size(), self.max_galaxy_size());
+        if min_size > max_size {
+            return Err(GenerationError::NoGalaxySizes);
+        }
+        if self.galaxy_count.is_empty() {
+            return Err(GenerationError::NoGalaxyCounts);
+        }
+        // The galaxies can cover the cells if there are enough of them to reach the largest sizes,
+        // and few enough of them to stay at the smallest sizes
+        let fewest = cells.div_ceil(max_size).max(*self.galaxy_count.start());
+        let most = (cells / min_size).min(*self.galaxy_count.end());
+        if fewest > most {
+            return Err(GenerationError::IndivisibleMask(cells));
+        }
+        Ok(())
+    }
+
+    /// Returns a way in which [universe] breaks the constraints, if any
+    pub fn check(&self, universe: &Universe) -> Result<(), ConstraintViolation> {
+        let galaxies = universe.get_galaxies();
+        for galaxy in &galaxies {
+            if galaxy.size() < self.min_galaxy_size() {
+                return Err(ConstraintViolation::GalaxyTooSmall(galaxy.size()));
+            }
+            if galaxy.size() > self.max_galaxy_size() {
+                return Err(ConstraintViolation::GalaxyTooLarge(galaxy.size()));
+            }
+        }
+        let count = galaxies.len();
+        if count < *self.galaxy_count.start() {
+            return Err(ConstraintViolation::TooFewGalaxies(count));
+        }
+        if count > *self.galaxy_count.end() {
+            return Err(ConstraintViolation::TooManyGalaxies(count));
+        }
+        if universe.grid_type() == GridType::Square {
+            let rectangular = galaxies
+                .iter()
+                .filter(|galaxy| galaxy.rectangles().len() == 1)
+                .count();
+            if rectangular > self.max_rectangular(count) {
+                return Err(ConstraintViolation::TooManyRectangles(rectangular));
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns how far [universe] is from satisfying the constraints, 0 if it does.
+    /// Every cell that a galaxy is too small or large by, every galaxy too many or few,
+    /// and every rectangle too many counts as one.
+    pub fn penalty(&self, universe: &Universe) -> i64 {
+        let (min_size, max_size) = (self.min_galaxy_size(), self.max_galaxy_size());
+        let sizes = universe.galaxy_sizes();
+        let size_penalty: usize = sizes
+            .iter()
+            .map(|&size| min_size.saturating_sub(size) + size.saturating_sub(max_size))
+            .sum();
+        let count = sizes.len();
+        let count_penalty = self.galaxy_count.start().saturating_sub(count)
+            + count.saturating_sub(*self.galaxy_count.end());
+        let rectangle_penalty = universe
+            .count_rectangular_galaxies()
+            .saturating_sub(self.max_rectangular(count));
+        (size_penalty + count_penalty + rectangle_penalty) as i64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::constraints::GenerationConstraints;
+    use crate::model::galaxy::Galaxy;
+    use crate::model::generation_error::{ConstraintViolation, GenerationError};
+    use crate::model::position::Position;
+    use crate::model::universe::Universe;
+
+    #[test]
+    fn contradictory_constraints_should_be_invalid() {
+        let constraints = GenerationConstraints {
+            galaxy_size: 1..=1,
+            single_cells: false,
+            ..GenerationConstraints::default()
+        };
+        assert_eq!(constraints.validate(9), Err(GenerationError::NoGalaxySizes));
+        let constraints = GenerationConstraints {
+            galaxy_size: 2..=3,
+            galaxy_count: 5..=8,
+            ..GenerationConstraints::default()
+        };
+        assert_eq!(
+            constraints.validate(9),
+            Err(GenerationError::IndivisibleMask(9))
+        );
+        assert_eq!(constraints.validate(12), Ok(()));
+    }
+
+    #[test]
+    fn rectangles_and_single_cells_should_be_checked() {
+        let galaxies = [
+            Galaxy::from_positions([Position::new(0, 0)]),
+            Galaxy::from_positions([Position::new(0, 1), Position::new(0, 2)]),
+        ];
+        let universe = Universe::from(galaxies.as_slice());
+        let constraints = GenerationConstraints {
+            max_rectangular_fraction: 0.5,
+            ..GenerationConstraints::default()
+        };
+        assert_eq!(
+            constraints.check(&universe),
+            Err(ConstraintViolation::TooManyRectangles(2))
+        );
+        assert_eq!(constraints.penalty(&universe), 1);
+        let constraints = GenerationConstraints {
+            single_cells: false,
+            ..GenerationConstraints::default()
+        };
+        assert_eq!(
+            constraints.check(&universe),
+            Err(ConstraintViolation::GalaxyTooSmall(1))
+        );
+        assert_eq!(constraints.penalty(&universe), 1);
+    }
+}
@@ -0,0 +1,101 @@
+use crate::model::date::Date;
+use crate::model::difficulty::Difficulty;
+use crate::model::grid::GridType;
+use crate::model::mask::Mask;
+use crate::model::state::State;
+use crate::model::storage::Storage;
+use crate::model::topology::Topology;
+
+/// The width and height of the daily puzzle
+pub const DAILY_SIZE: usize = 10;
+
+/// The difficulty that the daily puzzle is generated for
+pub const DAILY_DIFFICULTY: Difficulty = Difficulty::Medium;
+
+/// How many seeds are tried for a puzzle of the daily difficulty,
+/// before settling for the last one
+const DAILY_ATTEMPTS: u64 = 20;
+
+/// The key that the date of the last completed daily puzzle is stored under
+const STORAGE_KEY: &str = "vintergatan.daily";
+
+/// Returns the seed of the daily puzzle of [date], the same on every platform
+pub fn daily_seed(date: Date) -> u64 {
+    // SplitMix64, so that consecutive days get unrelated seeds
+    let mut z = (date.days() as u64).wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+/// Generates the daily puzzle of [date], which is the same for every player
+pub fn generate_daily(date: Date) -> State {
+    generate_daily_of_size(date, DAILY_SIZE)
+}
+
+fn generate_daily_of_size(date: Date, size: usize) -> State {
+    let seed = daily_seed(date);
+    let mut attempt = 0;
+    loop {
+        let state = State::generate_with_seed(
+            Mask::full(size, size),
+            Topology::default(),
+            GridType::default(),
+            seed.wrapping_add(attempt),
+        )
+        .expect("Failed to generate a game of square cells");
+        attempt += 1;
+        if state.difficulty == DAILY_DIFFICULTY || attempt == DAILY_ATTEMPTS {
+            return State {
+                daily: Some(date),
+                ..state
+            };
+        }
+    }
+}
+
+/// Returns whether the daily puzzle of [date] has been completed
+pub fn is_daily_completed(storage: &impl Storage, date: Date) -> bool {
+    storage.get(STORAGE_KEY) == Some(date.to_string())
+}
+
+/// Remembers that the daily puzzle of [date] has been completed
+pub fn mark_daily_completed(storage: &mut impl Storage, date: Date) {
+    storage.set(STORAGE_KEY, &date.to_string());
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::daily::{
+        daily_seed, generate_daily_of_size, is_daily_completed, mark_daily_completed,
+    };
+    use crate::model::date::Date;
+    use crate::model::storage::MemoryStorage;
+
+    #[test]
+    fn consecutive_days_should_get_different_seeds() {
+        let today = Date::new(2024, 5, 1);
+        assert_eq!(daily_seed(today), daily_seed(today));
+        assert_ne!(daily_seed(today), daily_seed(today.next()));
+    }
+
+    #[test]
+    fn everyone_should_get_the_same_daily_puzzle() {
+        let today = Date::new(2024, 5, 1);
+        let first = generate_daily_of_size(today, 5);
+        let second = generate_daily_of_size(today, 5);
+        assert_eq!(first.daily, Some(today));
+        assert_eq!(first.seed, second.seed);
+        assert_eq!(first.universe.get_walls(), second.universe.get_walls());
+    }
+
+    #[test]
+    fn only_the_completed_day_should_be_completed() {
+        let mut storage = MemoryStorage::default();
+        let today = Date::new(2024, 5, 1);
+        assert!(!is_daily_completed(&storage, today));
+        mark_daily_completed(&mut storage, today);
+        assert!(is_daily_completed(&storage, today));
+        assert!(!is_daily_completed(&storage, today.next()));
+    }
+}
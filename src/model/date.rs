@@ -0,0 +1,108 @@
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+const MILLISECONDS_PER_DAY: f64 = 86_400_000.0;
+
+/// A day in the proleptic Gregorian calendar, in UTC
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct Date {
+    pub year: i32,
+    pub month: u32,
+    pub day: u32,
+}
+
+impl Date {
+    pub fn new(year: i32, month: u32, day: u32) -> Date {
+        Date { year, month, day }
+    }
+
+    /// Returns the day of a timestamp in milliseconds since the Unix epoch, like `Date.now()`
+    pub fn from_timestamp(milliseconds: f64) -> Date {
+        Date::from_days((milliseconds / MILLISECONDS_PER_DAY).floor() as i64)
+    }
+
+    /// Returns the date [days] after 1970-01-01
+    pub fn from_days(days: i64) -> Date {
+        // Howard Hinnant's civil_from_days, with eras of 400 years starting on March 1st
+        let z = days + 719_468;
+        let era = z.div_euclid(146_097);
+        let day_of_era = z.rem_euclid(146_097);
+        let year_of_era =
+            (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let shifted_month = (5 * day_of_year + 2) / 153;
+        let day = (day_of_year - (153 * shifted_month + 2) / 5 + 1) as u32;
+        let month = if shifted_month < 10 {
+            shifted_month + 3
+        } else {
+            shifted_month - 9
+        } as u32;
+        let year = year_of_era + era * 400 + i64::from(month <= 2);
+        Date::new(year as i32, month, day)
+    }
+
+    /// Returns the number of days since 1970-01-01
+    pub fn days(&self) -> i64 {
+        let year = i64::from(self.year) - i64::from(self.month <= 2);
+        let era = year.div_euclid(400);
+        let year_of_era = year.rem_euclid(400);
+        let shifted_month = (i64::from(self.month) + 9) % 12;
+        let day_of_year = (153 * shifted_month + 2) / 5 + i64::from(self.day) - 1;
+        let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+        era * 146_097 + day_of_era - 719_468
+    }
+
+    /// Returns the date after this one
+    pub fn next(&self) -> Date {
+        Date::from_days(self.days() + 1)
+    }
+}
+
+impl Display for Date {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+}
+
+impl FromStr for Date {
+    type Err = String;
+
+    /// Parses a date written as `YYYY-MM-DD`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = || format!("Invalid date: {s}");
+        let mut parts = s.splitn(3, '-');
+        let mut next = || parts.next().ok_or_else(error);
+        let year = next()?.parse().map_err(|_| error())?;
+        let month = next()?.parse().map_err(|_| error())?;
+        let day = next()?.parse().map_err(|_| error())?;
+        let date = Date::new(year, month, day);
+        if Date::from_days(date.days()) == date {
+            Ok(date)
+        } else {
+            Err(error())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::date::Date;
+
+    #[test]
+    fn days_should_count_from_the_epoch() {
+        assert_eq!(Date::new(1970, 1, 1).days(), 0);
+        assert_eq!(Date::new(2000, 3, 1).days(), 11_017);
+        assert_eq!(Date::from_days(-1), Date::new(1969, 12, 31));
+        for days in -1000..1000 {
+            assert_eq!(Date::from_days(days).days(), days);
+        }
+    }
+
+    #[test]
+    fn should_parse_and_format_dates() {
+        assert_eq!("2024-02-29".parse(), Ok(Date::new(2024, 2, 29)));
+        assert_eq!(Date::new(2024, 2, 29).next().to_string(), "2024-03-01");
+        assert!("2023-02-29".parse::<Date>().is_err());
+        assert!("yesterday".parse::<Date>().is_err());
+    }
+}
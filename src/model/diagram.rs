@@ -0,0 +1,425 @@
+use crate::model::board::Board;
+use crate::model::border::Border;
+use crate::model::galaxy::Galaxy;
+use crate::model::grid::GridType;
+use crate::model::mask::Mask;
+use crate::model::objective::{CenterColor, GalaxyCenter, Objective};
+use crate::model::position::Position;
+use crate::model::symmetry::Symmetry;
+use crate::model::topology::Topology;
+use crate::model::universe::Universe;
+use std::collections::{BTreeSet, HashMap};
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+/// The box-drawing characters that have a line going down from the corner
+const DOWN_GLYPHS: &str = "╷┐┌┬│┤├┼";
+
+/// A square board drawn as text, with its walls and centers.
+///
+/// Two formats can be read. The first is the box drawing written by [Universe]'s [Display],
+/// where every cell is on the board and there are no centers. The second is an ASCII format
+/// where every character is a cell, a border or a corner, e.g.
+///
+/// ```text
+/// +-+-+-+
+/// |o  | |
+/// + + +o+
+/// |   |#|
+/// +-+-+-+
+/// ```
+///
+/// Walls are written as `-` and `|`, and `#` marks a cell that isn't part of the board.
+/// A center is written on its cell, border or corner as `o`, as `*` if it's black,
+/// or as a digit if it's given the size of its galaxy. The edge of the board is always a wall,
+/// so it can be left out, and lines may be indented as long as they all are.
+/// [Display] writes the ASCII format.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Diagram {
+    pub mask: Mask,
+    /// The walls between cells of the board
+    pub walls: BTreeSet<Border>,
+    pub centers: Vec<GalaxyCenter>,
+}
+
+impl Diagram {
+    /// Draws the galaxies of [universe], with a center in each
+    pub fn from_universe(universe: &Universe) -> Diagram {
+        Diagram {
+            mask: universe.mask().clone(),
+            walls: universe.get_walls(),
+            centers: universe
+                .get_galaxies()
+                .iter()
+                .map(|galaxy| GalaxyCenter {
+                    position: galaxy.center(),
+                    size: None,
+                    color: CenterColor::White,
+                })
+                .collect(),
+        }
+    }
+
+    /// Draws the walls of [board] and the centers of [objective]
+    pub fn from_board(board: &Board, objective: &Objective) -> Diagram {
+        Diagram {
+            mask: board.get_mask().clone(),
+            walls: board.get_borders().collect(),
+            centers: objective.centers.clone(),
+        }
+    }
+
+    /// Draws the puzzle of [objective], with its given walls
+    pub fn from_objective(mask: &Mask, objective: &Objective) -> Diagram {
+        Diagram {
+            mask: mask.clone(),
+            walls: objective.walls.iter().copied().collect(),
+            centers: objective.centers.clone(),
+        }
+    }
+
+    /// Returns the universe whose galaxies are the regions separated by the walls
+    pub fn to_universe(&self) -> Universe {
+        let mut universe = Universe::new_with_mask(
+            self.mask.clone(),
+            Symmetry::default(),
+            Topology::default(),
+            GridType::Square,
+        );
+        for (p1, p2) in self.adjacent_pairs() {
+            if !self.walls.contains(&Border::new(p1, p2)) {
+                universe.add_galaxy(&Galaxy::from_positions([p1, p2]));
+            }
+        }
+        universe
+    }
+
+    /// Returns a board with the walls drawn
+    pub fn to_board(&self) -> Board {
+        let mut board =
+            Board::new_with_mask(self.mask.clone(), Topology::default(), GridType::Square);
+        for wall in &self.walls {
+            board.add_wall(wall.p1(), wall.p2());
+        }
+        board
+    }
+
+    /// Returns the puzzle of the centers, where the walls are given
+    pub fn to_objective(&self) -> Objective {
+        Objective {
+            centers: self.centers.clone(),
+            walls: self.walls.iter().copied().collect(),
+            symmetry: Symmetry::default(),
+            topology: Topology::default(),
+            grid: GridType::Square,
+        }
+    }
+
+    /// Returns the adjacent cells of the board, each pair once
+    fn adjacent_pairs(&self) -> impl Iterator<Item = (Position, Position)> + '_ {
+        self.mask.positions().flat_map(move |p| {
+            [p.right(), p.down()]
+                .into_iter()
+                .filter(|q| self.mask.contains(q))
+                .map(move |q| (p, q))
+        })
+    }
+
+    /// Reads the format written by [Universe]'s [Display], two characters per corner
+    fn parse_box_drawing(lines: &[Vec<char>]) -> Result<Diagram, String> {
+        let height = lines.len().checked_sub(1).filter(|&h| h > 0);
+        let width = lines
+            .iter()
+            .map(|line| line.len().div_ceil(2))
+            .max()
+            .and_then(|corners| corners.checked_sub(1))
+            .filter(|&w| w > 0);
+        let (Some(width), Some(height)) = (width, height) else {
+            return Err("The box drawing is too small".to_string());
+        };
+        let char_at = |row: usize, index: usize| lines[row].get(index).copied().unwrap_or(' ');
+        let mut walls = BTreeSet::new();
+        for row in 0..height {
+            for column in 1..width {
+                if DOWN_GLYPHS.contains(char_at(row, 2 * column)) {
+                    let p = Position::new(row as i32, column as i32);
+                    walls.insert(Border::new(p.left(), p));
+                }
+            }
+        }
+        for row in 1..height {
+            for column in 0..width {
+                if char_at(row, 2 * column + 1) == '─' {
+                    let p = Position::new(row as i32, column as i32);
+                    walls.insert(Border::new(p.up(), p));
+                }
+            }
+        }
+        Ok(Diagram {
+            mask: Mask::full(width, height),
+            walls,
+            centers: Vec::new(),
+        })
+    }
+
+    /// Reads the ASCII format, where the characters are at half-steps
+    fn parse_ascii(lines: &[Vec<char>]) -> Result<Diagram, String> {
+        let rows = lines.len();
+        let columns = lines.iter().map(Vec::len).max().unwrap_or(0);
+        if rows < 3 || rows.is_multiple_of(2) {
+            return Err(format!("Expected an odd number of lines, found {rows}"));
+        }
+        let (width, height) = ((columns.max(2) - 1) / 2, (rows - 1) / 2);
+        let char_at = |y: usize, x: usize| lines[y].get(x).copied().unwrap_or(' ');
+
+        let cells = (0..height)
+            .flat_map(|row| (0..width).map(move |column| (row, column)))
+            .map(|(row, column)| char_at(2 * row + 1, 2 * column + 1) != '#')
+            .collect();
+        let mask = Mask::from_cells(width, height, cells);
+
+        let mut walls = BTreeSet::new();
+        let mut centers = Vec::new();
+        for (y, line) in lines.iter().enumerate() {
+            for (x, &c) in line.iter().enumerate() {
+                // The character's position in half-steps
+                let position = Position::new(y as i32 - 1, x as i32 - 1);
+                let center = |size, color| GalaxyCenter {
+                    position,
+                    size,
+                    color,
+                };
+                match c {
+                    ' ' | '+' | '#' => {}
+                    '-' if y % 2 == 0 && x % 2 == 1 => {
+                        let p = Position::new((y / 2) as i32, (x / 2) as i32);
+                        walls.insert(Border::new(p.up(), p));
+                    }
+                    '|' if y % 2 == 1 && x % 2 == 0 => {
+                        let p = Position::new((y / 2) as i32, (x / 2) as i32);
+                        walls.insert(Border::new(p.left(), p));
+                    }
+                    'o' => centers.push(center(None, CenterColor::White)),
+                    '*' => centers.push(center(None, CenterColor::Black)),
+                    '1'..='9' => {
+                        let size = c.to_digit(10).map(|size| size as usize);
+                        centers.push(center(size, CenterColor::White))
+                    }
+                    _ => return Err(format!("Unexpected '{c}' on line {}", y + 1)),
+                }
+            }
+        }
+        // Walls on the edge of the board are implied
+        walls.retain(|wall| mask.contains(&wall.p1()) && mask.contains(&wall.p2()));
+        Ok(Diagram {
+            mask,
+            walls,
+            centers,
+        })
+    }
+}
+
+impl Display for Diagram {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let (width, height) = (self.mask.width() as i32, self.mask.height() as i32);
+        let center_by_position: HashMap<Position, &GalaxyCenter> =
+            self.centers.iter().map(|gc| (gc.position, gc)).collect();
+        // There's a wall between two cells if at least one of them is on the board, and they're not joined
+        let is_wall =
+            |p1: Position, p2: Position| match (self.mask.contains(&p1), self.mask.contains(&p2)) {
+                (true, true) => self.walls.contains(&Border::new(p1, p2)),
+                (false, false) => false,
+                _ => true,
+            };
+        for y in 0..=2 * height {
+            let line: String = (0..=2 * width)
+                .map(|x| {
+                    let position = Position::new(y - 1, x - 1);
+                    if let Some(gc) = center_by_position.get(&position) {
+                        return match (gc.color, gc.size) {
+                            (CenterColor::Black, _) => '*',
+                            (CenterColor::White, Some(size @ 1..=9)) => {
+                                char::from_digit(size as u32, 10).unwrap_or('o')
+                            }
+                            (CenterColor::White, _) => 'o',
+                        };
+                    }
+                    let p = Position::new(y.div_euclid(2), x.div_euclid(2));
+                    match (y % 2, x % 2) {
+                        (0, 0) => '+',
+                        (0, _) if is_wall(p.up(), p) => '-',
+                        (_, 0) if is_wall(p.left(), p) => '|',
+                        (1, 1) if !self.mask.contains(&p) => '#',
+                        _ => ' ',
+                    }
+                })
+                .collect();
+            write!(f, "{}", line.trim_end())?;
+            if y != 2 * height {
+                writeln!(f)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Diagram {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let non_empty: Vec<&str> = s
+            .lines()
+            .skip_while(|line| line.trim().is_empty())
+            .collect();
+        let end = non_empty
+            .iter()
+            .rposition(|line| !line.trim().is_empty())
+            .map_or(0, |index| index + 1);
+        let indent = non_empty[..end]
+            .iter()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| line.len() - line.trim_start().len())
+            .min()
+            .unwrap_or(0);
+        let lines: Vec<Vec<char>> = non_empty[..end]
+            .iter()
+            .map(|line| line.chars().skip(indent).collect())
+            .collect();
+        let is_box_drawing = lines
+            .iter()
+            .flatten()
+            .any(|c| ('\u{2500}'..='\u{257f}').contains(c));
+        if is_box_drawing {
+            Self::parse_box_drawing(&lines)
+        } else {
+            Self::parse_ascii(&lines)
+        }
+    }
+}
+
+impl FromStr for Universe {
+    type Err = String;
+
+    /// Reads the galaxies of a [Diagram], where the galaxies are the regions separated by the walls
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.parse::<Diagram>().map(|diagram| diagram.to_universe())
+    }
+}
+
+impl FromStr for Board {
+    type Err = String;
+
+    /// Reads the walls of a [Diagram]
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.parse::<Diagram>().map(|diagram| diagram.to_board())
+    }
+}
+
+impl FromStr for Objective {
+    type Err = String;
+
+    /// Reads the centers of a [Diagram], and its walls as given walls
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.parse::<Diagram>().map(|diagram| diagram.to_objective())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::board::Board;
+    use crate::model::border::Border;
+    use crate::model::diagram::Diagram;
+    use crate::model::objective::{CenterColor, Objective};
+    use crate::model::position::Position;
+    use crate::model::universe::Universe;
+
+    #[test]
+    fn box_drawing_should_round_trip() {
+        let universe = Universe::generate(6, 5);
+        let parsed: Universe = universe.to_string().parse().unwrap();
+        assert_eq!(parsed.get_walls(), universe.get_walls());
+        assert_eq!(parsed.to_string(), universe.to_string());
+    }
+
+    #[test]
+    fn universe_should_round_trip_through_ascii() {
+        let universe = Universe::generate(5, 4);
+        let text = Diagram::from_universe(&universe).to_string();
+        let diagram: Diagram = text.parse().unwrap();
+        assert_eq!(diagram.centers.len(), universe.get_galaxies().len());
+        assert_eq!(diagram.to_universe().get_walls(), universe.get_walls());
+    }
+
+    #[test]
+    fn ascii_should_round_trip() {
+        let text = "
+            +-+-+-+
+            |o  | |
+            + + +o+
+            |   |#|
+            +-+-+-+";
+        let diagram: Diagram = text.parse().unwrap();
+        assert_eq!((diagram.mask.width(), diagram.mask.height()), (3, 2));
+        assert!(!diagram.mask.contains(&Position::new(1, 2)));
+        assert_eq!(diagram.centers.len(), 2);
+        assert_eq!(diagram.centers[1].position, Position::new(1, 4));
+        let reparsed: Diagram = diagram.to_string().parse().unwrap();
+        assert_eq!(reparsed, diagram);
+    }
+
+    #[test]
+    fn ascii_should_describe_a_solvable_puzzle() {
+        let objective: Objective = "
+            +-+-+
+            | o |
+            +-+-+
+            |3  |
+            + +-+
+            | |
+            +-+"
+        .parse()
+        .unwrap();
+        assert_eq!(objective.centers[0].position, Position::new(0, 1));
+        assert_eq!(objective.centers[1].size, Some(3));
+        assert_eq!(objective.centers[1].color, CenterColor::White);
+        let board: Board = "
+            +-+-+
+            |   |
+            +-+-+
+            |   |
+            + +-+
+            | |
+            +-+"
+        .parse()
+        .unwrap();
+        assert!(board.is_wall(Position::new(0, 0), Position::new(1, 0)));
+        assert!(!board.is_wall(Position::new(1, 0), Position::new(2, 0)));
+    }
+
+    #[test]
+    fn ascii_walls_should_split_galaxies() {
+        let universe: Universe = "
+            +-+-+
+            |   |
+            +-+-+
+            | | |
+            +-+-+"
+            .parse()
+            .unwrap();
+        assert_eq!(universe.get_galaxies().len(), 3);
+        assert_eq!(
+            universe.get_walls().into_iter().collect::<Vec<_>>(),
+            vec![
+                Border::new(Position::new(0, 0), Position::new(1, 0)),
+                Border::new(Position::new(0, 1), Position::new(1, 1)),
+                Border::new(Position::new(1, 0), Position::new(1, 1)),
+            ]
+        );
+    }
+
+    #[test]
+    fn unexpected_characters_should_be_rejected() {
+        assert!("+-+\n|x|\n+-+".parse::<Diagram>().is_err());
+        assert!("+-+\n| |".parse::<Diagram>().is_err());
+    }
+}
@@ -0,0 +1,81 @@
+use crate::model::mask::Mask;
+use crate::model::objective::Objective;
+use crate::model::solver::Solver;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+/// How many guesses the solver may need for a puzzle to still count as medium
+const MEDIUM_GUESSES: usize = 3;
+
+/// How hard a puzzle is, judged by how much guessing it takes to solve it
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Default)]
+pub enum Difficulty {
+    /// Every cell follows from the centers and walls without guessing
+    #[default]
+    Easy,
+    /// A few cells need a guess
+    Medium,
+    Hard,
+}
+
+impl Difficulty {
+    pub const ALL: [Difficulty; 3] = [Difficulty::Easy, Difficulty::Medium, Difficulty::Hard];
+
+    /// Rates the puzzle of [objective] on the cells of [mask] by solving it
+    pub fn rate(mask: &Mask, objective: &Objective) -> Difficulty {
+        let solver = Solver::new(mask, objective);
+        solver.solve(1);
+        match solver.guesses() {
+            0 => Difficulty::Easy,
+            guesses if guesses <= MEDIUM_GUESSES => Difficulty::Medium,
+            _ => Difficulty::Hard,
+        }
+    }
+}
+
+impl Display for Difficulty {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Difficulty::Easy => "easy",
+            Difficulty::Medium => "medium",
+            Difficulty::Hard => "hard",
+        };
+        write!(f, "{name}")
+    }
+}
+
+impl FromStr for Difficulty {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Difficulty::ALL
+            .into_iter()
+            .find(|difficulty| difficulty.to_string() == s)
+            .ok_or_else(|| format!("Unknown difficulty: {s}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::difficulty::Difficulty;
+    use crate::model::mask::Mask;
+    use crate::model::objective::Objective;
+    use crate::model::universe::Universe;
+
+    #[test]
+    fn single_galaxy_should_be_easy() {
+        let universe = Universe::generate(1, 2);
+        let objective = Objective::generate(&universe);
+        assert_eq!(
+            Difficulty::rate(&Mask::full(1, 2), &objective),
+            Difficulty::Easy
+        );
+    }
+
+    #[test]
+    fn difficulty_should_round_trip_through_strings() {
+        for difficulty in Difficulty::ALL {
+            assert_eq!(difficulty.to_string().parse(), Ok(difficulty));
+        }
+    }
+}
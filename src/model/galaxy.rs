@@ -1,12 +1,13 @@
 use crate::model::position::Position;
 use crate::model::rectangle::Rectangle;
+use crate::model::symmetry::Symmetry;
 use petgraph::algo::connected_components;
 use petgraph::graphmap::UnGraphMap;
 use std::cmp::{max, min};
 use std::collections::HashSet;
 use crate::model::border::Border;
 
-#[derive(Clone, Eq, PartialEq, Debug)]
+#[derive(Clone, Eq, PartialEq, Debug, Default)]
 pub struct Galaxy {
     positions: HashSet<Position>,
 }
@@ -15,7 +16,7 @@ pub struct Galaxy {
 /// - It must not be empty
 /// - It must be connected
 /// - It must contain its center
-/// - It must be rotationally symmetric
+/// - It must be symmetric around its center, see [Symmetry]
 impl Galaxy {
     pub fn new() -> Galaxy {
         Galaxy {
@@ -84,21 +85,24 @@ impl Galaxy {
             .unwrap_or(Rectangle::default())
     }
 
-    pub fn mirror_position(&self, p: &Position) -> Position {
-        let center = self.center();
-        let mirrored_row = center.row - p.row;
-        let mirrored_column = center.column - p.column;
-        Position::new(mirrored_row, mirrored_column)
+    /// Returns the positions that [p] is mapped to by [symmetry] around the center of this galaxy
+    pub fn mirror_positions(&self, p: &Position, symmetry: Symmetry) -> Vec<Position> {
+        symmetry.images(&self.center(), p).to_vec()
     }
 
     pub fn contains_position(&self, p: &Position) -> bool {
         self.positions.contains(p)
     }
 
-    pub fn is_symmetric(&self) -> bool {
-        self.positions
-            .iter()
-            .all(|p| self.contains_position(&self.mirror_position(p)))
+    pub fn is_symmetric(&self, symmetry: Symmetry) -> bool {
+        let center = self.center();
+        symmetry.allows_center(&center)
+            && self.positions.iter().all(|p| {
+                symmetry
+                    .images(&center, p)
+                    .iter()
+                    .all(|image| self.contains_position(image))
+            })
     }
 
     pub fn is_connected(&self) -> bool {
@@ -144,12 +148,15 @@ impl Galaxy {
         true
     }
 
-    pub fn is_valid(&self) -> bool {
-        !self.is_empty() && self.contains_center() && self.is_connected() && self.is_symmetric()
+    pub fn is_valid(&self, symmetry: Symmetry) -> bool {
+        !self.is_empty()
+            && self.contains_center()
+            && self.is_connected()
+            && self.is_symmetric(symmetry)
     }
 
-    pub fn is_empty_or_valid(&self) -> bool {
-        self.is_empty() || self.is_valid()
+    pub fn is_empty_or_valid(&self, symmetry: Symmetry) -> bool {
+        self.is_empty() || self.is_valid(symmetry)
     }
 
     pub fn with_position(&self, p: &Position) -> Galaxy {
@@ -181,60 +188,70 @@ impl Galaxy {
     /// Returns the rectangles that make up the galaxy, by finding the largest rectangle, subtracting
     /// it from the galaxy, finding the next largest rectangle, and so forth.
     pub fn rectangles(&self) -> Vec<Rectangle> {
-        Self::rectangles_internal(self.positions.clone())
+        let positions: Vec<Position> = self.positions.iter().copied().collect();
+        rectangles_of(&positions)
     }
+}
 
-    fn rectangles_internal(mut positions: HashSet<Position>) -> Vec<Rectangle> {
-        if positions.is_empty() {
-            return vec![];
-        }
+/// Returns the rectangles that make up the given positions, see [Galaxy::rectangles]
+pub(crate) fn rectangles_of(positions: &[Position]) -> Vec<Rectangle> {
+    if positions.is_empty() {
+        return vec![];
+    }
+
+    let min_row = positions.iter().map(|p| p.row).min().unwrap();
+    let min_col = positions.iter().map(|p| p.column).min().unwrap();
+    let max_row = positions.iter().map(|p| p.row).max().unwrap() + 1;
+    let max_col = positions.iter().map(|p| p.column).max().unwrap() + 1;
+    let bounds = Rectangle::new(min_row, max_row, min_col, max_col);
+    if bounds.area() as usize == positions.len() {
+        return vec![bounds];
+    }
 
-        let min_col = positions.iter().map(|p| p.column).min().unwrap();
-        let min_row = positions.iter().map(|p| p.row).min().unwrap();
-        let max_col = positions.iter().map(|p| p.column).max().unwrap() + 1;
-        let max_row = positions.iter().map(|p| p.row).max().unwrap() + 1;
+    let width = (max_col - min_col) as usize;
+    let index_of = |row: i32, col: i32| (row - min_row) as usize * width + (col - min_col) as usize;
 
-        let width = max_col.abs_diff(min_col) as usize;
-        let mut height = vec![0; width];
-        let mut left = vec![min_col; width];
-        let mut right = vec![max_col; width];
+    // The positions that are not yet covered by a rectangle, over the bounding rectangle
+    let mut remaining = vec![false; width * (max_row - min_row) as usize];
+    for p in positions {
+        remaining[index_of(p.row, p.column)] = true;
+    }
+    let mut remaining_count = positions.len();
+
+    let mut rectangles = Vec::new();
+    let mut height = vec![0; width];
+    let mut left = vec![min_col; width];
+    let mut right = vec![max_col; width];
+    while remaining_count > 0 {
+        height.fill(0);
+        left.fill(min_col);
+        right.fill(max_col);
 
         let mut max_rectangle = Rectangle::default();
 
-        for row in min_row..max_row {
-            for col in min_col..max_col {
-                let index = (col - min_col) as usize;
-                let p = Position::new(row, col);
-                if positions.contains(&p) {
-                    height[index] += 1;
-                } else {
-                    height[index] = 0;
-                }
-            }
+        for (row, row_remaining) in (min_row..max_row).zip(remaining.chunks(width)) {
+            let columns = row_remaining.iter().zip(min_col..max_col).enumerate();
             let mut current_left = min_col;
-            for col in min_col..max_col {
-                let index = (col - min_col) as usize;
-                let p = Position::new(row, col);
-                if positions.contains(&p) {
+            for (index, (&is_remaining, col)) in columns.clone() {
+                if is_remaining {
+                    height[index] += 1;
                     left[index] = max(left[index], current_left);
                 } else {
-                    left[index] = 0;
+                    height[index] = 0;
+                    left[index] = min_col;
                     current_left = col + 1;
                 }
             }
             let mut current_right = max_col;
-            for col in (min_col..max_col).rev() {
-                let index = (col - min_col) as usize;
-                let p = Position::new(row, col);
-                if positions.contains(&p) {
+            for (index, (&is_remaining, col)) in columns.rev() {
+                if is_remaining {
                     right[index] = min(right[index], current_right);
                 } else {
                     right[index] = max_col;
                     current_right = col;
                 }
             }
-            for col in min_col..max_col {
-                let index = (col - min_col) as usize;
+            for index in 0..width {
                 let rect = Rectangle {
                     min_row: row - height[index] + 1,
                     max_row: row + 1,
@@ -247,14 +264,18 @@ impl Galaxy {
             }
         }
 
-        for p in &max_rectangle.positions() {
-            positions.remove(p);
+        for row in max_rectangle.min_row..max_rectangle.max_row {
+            for col in max_rectangle.min_column..max_rectangle.max_column {
+                remaining[index_of(row, col)] = false;
+            }
         }
-        let mut rectangles = Self::rectangles_internal(positions);
+        remaining_count -= max_rectangle.area() as usize;
         rectangles.push(max_rectangle);
-
-        rectangles
     }
+
+    // The largest rectangle is found first, but listed last
+    rectangles.reverse();
+    rectangles
 }
 
 #[cfg(test)]
@@ -316,6 +337,44 @@ mod tests {
         );
     }
 
+    mod is_symmetric {
+        use crate::model::galaxy::tests::galaxy;
+        use crate::model::symmetry::Symmetry;
+
+        #[test]
+        fn s_tetromino_should_only_be_rotationally_symmetric() {
+            let s = galaxy(&[(0, 1), (0, 2), (1, 0), (1, 1)]);
+            assert!(s.is_symmetric(Symmetry::Rotational));
+            assert!(!s.is_symmetric(Symmetry::HorizontalAxis));
+            assert!(!s.is_symmetric(Symmetry::VerticalAxis));
+            assert!(!s.is_symmetric(Symmetry::FourFold));
+        }
+
+        #[test]
+        fn t_tetromino_should_only_be_symmetric_across_vertical_axis() {
+            let t = galaxy(&[(0, 0), (0, 1), (0, 2), (1, 1)]);
+            assert!(!t.is_symmetric(Symmetry::Rotational));
+            assert!(!t.is_symmetric(Symmetry::HorizontalAxis));
+            assert!(t.is_symmetric(Symmetry::VerticalAxis));
+            assert!(!t.is_symmetric(Symmetry::FourFold));
+        }
+
+        #[test]
+        fn plus_should_have_every_symmetry() {
+            let plus = galaxy(&[(0, 1), (1, 0), (1, 1), (1, 2), (2, 1)]);
+            for symmetry in Symmetry::ALL {
+                assert!(plus.is_symmetric(symmetry));
+            }
+        }
+
+        #[test]
+        fn domino_should_not_be_four_fold_symmetric() {
+            let domino = galaxy(&[(0, 0), (0, 1)]);
+            assert!(domino.is_symmetric(Symmetry::Rotational));
+            assert!(!domino.is_symmetric(Symmetry::FourFold));
+        }
+    }
+
     mod rectangles {
         use crate::model::galaxy::Galaxy;
         use crate::model::position::Position;
@@ -0,0 +1,54 @@
+use crate::model::timer::format_duration;
+use std::fmt::{Display, Formatter};
+
+/// Counts of what the player did while solving a puzzle
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+pub struct GameStats {
+    pub wall_toggles: usize,
+    pub undos: usize,
+    pub redos: usize,
+    pub checks: usize,
+    pub hints: usize,
+}
+
+/// The summary of a solved puzzle
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Completion {
+    /// The milliseconds spent solving the puzzle, excluding pauses
+    pub time: f64,
+    pub stats: GameStats,
+}
+
+impl Display for Completion {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Time: {}", format_duration(self.time))?;
+        writeln!(f, "Walls toggled: {}", self.stats.wall_toggles)?;
+        writeln!(f, "Undos: {}", self.stats.undos)?;
+        writeln!(f, "Redos: {}", self.stats.redos)?;
+        writeln!(f, "Checks: {}", self.stats.checks)?;
+        write!(f, "Hints: {}", self.stats.hints)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::game_stats::{Completion, GameStats};
+
+    #[test]
+    fn completion_should_summarise_every_count() {
+        let completion = Completion {
+            time: 187_000.0,
+            stats: GameStats {
+                wall_toggles: 1,
+                undos: 2,
+                redos: 3,
+                checks: 4,
+                hints: 5,
+            },
+        };
+        assert_eq!(
+            completion.to_string(),
+            "Time: 3:07\nWalls toggled: 1\nUndos: 2\nRedos: 3\nChecks: 4\nHints: 5"
+        );
+    }
+}
@@ -0,0 +1,228 @@
+use crate::model::board::Board;
+use crate::model::generation_error::GenerationError;
+use crate::model::grid::GridType;
+use crate::model::mask::Mask;
+use crate::model::objective::Objective;
+use crate::model::puzzle_link::PuzzleLink;
+use crate::model::state::State;
+use crate::model::symmetry::Symmetry;
+use crate::model::topology::Topology;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+/// Asks for the puzzle of [seed] on a board, sent to the worker that generates puzzles
+/// in the background. It's written as the seed and a [PuzzleLink] to the empty board,
+/// separated by a space.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct GenerationRequest {
+    pub mask: Mask,
+    pub topology: Topology,
+    pub grid: GridType,
+    pub seed: u64,
+}
+
+impl GenerationRequest {
+    pub fn generate(&self) -> Result<State, GenerationError> {
+        State::generate_with_seed(self.mask.clone(), self.topology, self.grid, self.seed)
+    }
+
+    /// Generates the puzzle, and answers with it or with why it couldn't be generated
+    pub fn respond(&self) -> GenerationResponse {
+        let puzzle = self
+            .generate()
+            .map_err(|e| e.to_string())
+            .and_then(|state| {
+                GeneratedPuzzle::from_state(&state).ok_or("The puzzle has no seed".to_string())
+            });
+        match puzzle {
+            Ok(puzzle) => GenerationResponse::Puzzle(puzzle),
+            Err(e) => GenerationResponse::Error(e),
+        }
+    }
+}
+
+impl Display for GenerationRequest {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let link = PuzzleLink {
+            mask: self.mask.clone(),
+            objective: Objective {
+                centers: Vec::new(),
+                walls: Vec::new(),
+                symmetry: Symmetry::default(),
+                topology: self.topology,
+                grid: self.grid,
+            },
+            walls: Vec::new(),
+        };
+        write!(f, "{} {link}", self.seed)
+    }
+}
+
+impl FromStr for GenerationRequest {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (seed, link) = parse_seed_and_link(s)?;
+        Ok(GenerationRequest {
+            mask: link.mask,
+            topology: link.objective.topology,
+            grid: link.objective.grid,
+            seed,
+        })
+    }
+}
+
+/// A puzzle generated by the worker, written as its seed and a [PuzzleLink] separated by a space.
+/// The walls of the solution are sent as the drawn walls of the link,
+/// so that the puzzle doesn't have to be solved again when it's received.
+#[derive(Clone)]
+pub struct GeneratedPuzzle {
+    pub seed: u64,
+    pub link: PuzzleLink,
+}
+
+impl GeneratedPuzzle {
+    /// Returns the generated puzzle of [state], which must have been generated from a seed
+    pub fn from_state(state: &State) -> Option<GeneratedPuzzle> {
+        let seed = state.seed?;
+        let mut link = PuzzleLink::from_state(state, false);
+        link.walls = state.universe.get_walls().into_iter().collect();
+        Some(GeneratedPuzzle { seed, link })
+    }
+
+    /// Starts a game of the puzzle, where the walls of the link are the solution
+    pub fn to_state(&self) -> Result<State, String> {
+        let objective = &self.link.objective;
+        let mut board =
+            Board::new_with_mask(self.link.mask.clone(), objective.topology, objective.grid);
+        for wall in &self.link.walls {
+            board.add_wall(wall.p1(), wall.p2());
+        }
+        let universe = board
+            .to_universe(objective.symmetry)
+            .map_err(|e| e.to_string())?;
+        Ok(State::new(universe, objective.clone(), Some(self.seed)))
+    }
+}
+
+impl Display for GeneratedPuzzle {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}", self.seed, self.link)
+    }
+}
+
+impl FromStr for GeneratedPuzzle {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (seed, link) = parse_seed_and_link(s)?;
+        Ok(GeneratedPuzzle { seed, link })
+    }
+}
+
+/// The answer of the worker to a [GenerationRequest], written as the [GeneratedPuzzle],
+/// or as `error` and why the puzzle couldn't be generated, separated by a space
+#[derive(Clone)]
+pub enum GenerationResponse {
+    Puzzle(GeneratedPuzzle),
+    Error(String),
+}
+
+impl GenerationResponse {
+    /// Starts a game of the generated puzzle, or fails with why there is none
+    pub fn to_state(&self) -> Result<State, String> {
+        match self {
+            GenerationResponse::Puzzle(puzzle) => puzzle.to_state(),
+            GenerationResponse::Error(e) => Err(e.clone()),
+        }
+    }
+}
+
+impl Display for GenerationResponse {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GenerationResponse::Puzzle(puzzle) => write!(f, "{puzzle}"),
+            GenerationResponse::Error(e) => write!(f, "error {e}"),
+        }
+    }
+}
+
+impl FromStr for GenerationResponse {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.strip_prefix("error ") {
+            Some(e) => Ok(GenerationResponse::Error(e.to_string())),
+            None => Ok(GenerationResponse::Puzzle(s.parse()?)),
+        }
+    }
+}
+
+fn parse_seed_and_link(s: &str) -> Result<(u64, PuzzleLink), String> {
+    let (seed, link) = s
+        .split_once(' ')
+        .ok_or_else(|| format!("Expected a seed and a link: {s}"))?;
+    let seed = seed.parse().map_err(|_| format!("Invalid seed: {seed}"))?;
+    Ok((seed, link.parse()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::generation::{GeneratedPuzzle, GenerationRequest, GenerationResponse};
+    use crate::model::grid::GridType;
+    use crate::model::mask::Mask;
+    use crate::model::topology::Topology;
+
+    #[test]
+    fn request_should_round_trip() {
+        let request = GenerationRequest {
+            mask: Mask::heart(6),
+            topology: Topology::Torus,
+            grid: GridType::Square,
+            seed: 1234,
+        };
+        assert_eq!(request.to_string().parse(), Ok(request));
+    }
+
+    #[test]
+    fn generated_puzzle_should_be_received_with_its_solution() {
+        let request = GenerationRequest {
+            mask: Mask::full(4, 4),
+            topology: Topology::default(),
+            grid: GridType::default(),
+            seed: 7,
+        };
+        let state = request.generate().unwrap();
+        let message = GeneratedPuzzle::from_state(&state).unwrap().to_string();
+        let received = message
+            .parse::<GeneratedPuzzle>()
+            .unwrap()
+            .to_state()
+            .unwrap();
+        assert_eq!(received.seed, Some(7));
+        assert_eq!(received.universe.get_walls(), state.universe.get_walls());
+        assert_eq!(received.objective.centers, state.objective.centers);
+        assert_eq!(received.difficulty, state.difficulty);
+        assert_eq!(
+            received.board.get_borders().count(),
+            state.objective.walls.len()
+        );
+    }
+
+    #[test]
+    fn failed_generation_should_be_received_as_error() {
+        let request = GenerationRequest {
+            mask: Mask::full(3, 3),
+            topology: Topology::default(),
+            grid: GridType::Triangular,
+            seed: 7,
+        };
+        let message = request.respond().to_string();
+        assert!(message.starts_with("error "), "{message}");
+        let received = message.parse::<GenerationResponse>().unwrap();
+        assert_eq!(
+            received.to_state().err(),
+            Some("The board can't be divided into symmetric galaxies".to_string())
+        );
+    }
+}
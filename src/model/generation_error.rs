@@ -0,0 +1,94 @@
+use crate::model::grid::GridType;
+use crate::model::symmetry::Symmetry;
+use crate::model::topology::Topology;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+
+/// How a generated universe breaks its [crate::model::constraints::GenerationConstraints]
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum ConstraintViolation {
+    /// A galaxy has fewer cells than allowed
+    GalaxyTooSmall(usize),
+    /// A galaxy has more cells than allowed
+    GalaxyTooLarge(usize),
+    TooFewGalaxies(usize),
+    TooManyGalaxies(usize),
+    /// More of the galaxies are rectangles than allowed
+    TooManyRectangles(usize),
+}
+
+impl Display for ConstraintViolation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConstraintViolation::GalaxyTooSmall(size) => {
+                write!(f, "a galaxy of {size} cells is too small")
+            }
+            ConstraintViolation::GalaxyTooLarge(size) => {
+                write!(f, "a galaxy of {size} cells is too large")
+            }
+            ConstraintViolation::TooFewGalaxies(count) => write!(f, "{count} galaxies are too few"),
+            ConstraintViolation::TooManyGalaxies(count) => {
+                write!(f, "{count} galaxies are too many")
+            }
+            ConstraintViolation::TooManyRectangles(count) => {
+                write!(f, "{count} rectangular galaxies are too many")
+            }
+        }
+    }
+}
+
+/// Why a universe couldn't be generated, on the given board or within the given
+/// [crate::model::constraints::GenerationConstraints]
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum GenerationError {
+    /// The mask has no cells
+    EmptyMask,
+    /// Galaxies on the grid can't have the symmetry
+    UnsupportedSymmetry(GridType, Symmetry),
+    /// Boards of the grid can't wrap around like the topology
+    UnsupportedTopology(GridType, Topology),
+    /// Some region of the mask can't be divided into symmetric galaxies, see [crate::model::grid::Grid::can_tile]
+    Untileable,
+    /// No galaxy size is allowed, e.g. the minimum is above the maximum
+    NoGalaxySizes,
+    /// No galaxy count is allowed, e.g. the minimum is above the maximum
+    NoGalaxyCounts,
+    /// The cells of the mask can't be divided into an allowed number of galaxies of allowed sizes
+    IndivisibleMask(usize),
+    /// Every attempt failed to divide the cells into valid galaxies, or broke the constraints.
+    /// The violation of the last attempt is given, unless it failed to cover the cells.
+    Unsatisfied(usize, Option<ConstraintViolation>),
+}
+
+impl Display for GenerationError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GenerationError::EmptyMask => write!(f, "The board has no cells"),
+            GenerationError::UnsupportedSymmetry(grid, symmetry) => {
+                write!(f, "{grid} grids don't support {symmetry} symmetry")
+            }
+            GenerationError::UnsupportedTopology(grid, topology) => {
+                write!(f, "{grid} grids don't support the {topology} topology")
+            }
+            GenerationError::Untileable => {
+                write!(f, "The board can't be divided into symmetric galaxies")
+            }
+            GenerationError::NoGalaxySizes => write!(f, "No galaxy size is allowed"),
+            GenerationError::NoGalaxyCounts => write!(f, "No number of galaxies is allowed"),
+            GenerationError::IndivisibleMask(cells) => write!(
+                f,
+                "The {cells} cells can't be divided into the allowed number of galaxies of the allowed sizes"
+            ),
+            GenerationError::Unsatisfied(attempts, None) => write!(
+                f,
+                "No valid universe was generated in {attempts} attempts"
+            ),
+            GenerationError::Unsatisfied(attempts, Some(violation)) => write!(
+                f,
+                "The constraints could not be satisfied in {attempts} attempts, in the last one {violation}"
+            ),
+        }
+    }
+}
+
+impl Error for GenerationError {}
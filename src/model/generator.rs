@@ -0,0 +1,494 @@
+use crate::model::constraints::GenerationConstraints;
+use crate::model::galaxy::Galaxy;
+use crate::model::generation_error::GenerationError;
+use crate::model::grid::GridType;
+use crate::model::mask::Mask;
+use crate::model::position::Position;
+use crate::model::symmetry::Symmetry;
+use crate::model::topology::{Surface, Topology};
+use crate::model::universe::Universe;
+use rand::prelude::SliceRandom;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+/// The sizes that grown galaxies aim for, galaxies that run out of room end up smaller
+const MIN_GROWN_SIZE: usize = 2;
+const MAX_GROWN_SIZE: usize = 12;
+
+/// How many times growing is restarted when the cells can't be covered in time
+const GROWTH_ATTEMPTS: usize = 20;
+
+/// A way of dividing the cells of a mask into valid galaxies
+pub trait Generator {
+    /// Generates a universe of [grid] cells that covers the cells of [mask], where every galaxy
+    /// has [symmetry] and the same [seed] always generates the same universe, or returns why it couldn't
+    fn generate(
+        &self,
+        mask: Mask,
+        symmetry: Symmetry,
+        topology: Topology,
+        grid: GridType,
+        seed: u64,
+    ) -> Result<Universe, GenerationError>;
+
+    /// Generates a universe like [Generator::generate] that satisfies [constraints],
+    /// or returns why it couldn't
+    fn generate_with_constraints(
+        &self,
+        mask: Mask,
+        symmetry: Symmetry,
+        topology: Topology,
+        grid: GridType,
+        seed: u64,
+        constraints: &GenerationConstraints,
+    ) -> Result<Universe, GenerationError>;
+}
+
+/// Starts from single cell galaxies and improves them by random local moves,
+/// see [Universe::generate_with_seed]
+pub struct ClimbingGenerator;
+
+impl Generator for ClimbingGenerator {
+    fn generate(
+        &self,
+        mask: Mask,
+        symmetry: Symmetry,
+        topology: Topology,
+        grid: GridType,
+        seed: u64,
+    ) -> Result<Universe, GenerationError> {
+        Universe::generate_with_seed(mask, symmetry, topology, grid, seed)
+    }
+
+    fn generate_with_constraints(
+        &self,
+        mask: Mask,
+        symmetry: Symmetry,
+        topology: Topology,
+        grid: GridType,
+        seed: u64,
+        constraints: &GenerationConstraints,
+    ) -> Result<Universe, GenerationError> {
+        Universe::generate_with_constraints(mask, symmetry, topology, grid, seed, constraints)
+    }
+}
+
+/// Places a center on an uncovered cell and grows a symmetric galaxy outwards from it,
+/// adding each cell together with its mirrors, until every cell is covered.
+/// The most enclosed cells are covered first, and a cell that can no longer be covered
+/// undoes the galaxies around it so that they can be grown again.
+///
+/// Galaxies are grown within the allowed sizes of the constraints,
+/// while the number of galaxies and rectangles are only checked once every cell is covered.
+pub struct GrowingGenerator;
+
+impl Generator for GrowingGenerator {
+    fn generate(
+        &self,
+        mask: Mask,
+        symmetry: Symmetry,
+        topology: Topology,
+        grid: GridType,
+        seed: u64,
+    ) -> Result<Universe, GenerationError> {
+        self.generate_with_constraints(
+            mask,
+            symmetry,
+            topology,
+            grid,
+            seed,
+            &GenerationConstraints::default(),
+        )
+    }
+
+    fn generate_with_constraints(
+        &self,
+        mask: Mask,
+        symmetry: Symmetry,
+        topology: Topology,
+        grid: GridType,
+        seed: u64,
+        constraints: &GenerationConstraints,
+    ) -> Result<Universe, GenerationError> {
+        Universe::check_generation(&mask, symmetry, topology, grid)?;
+        constraints.validate(mask.size())?;
+        let surface = Surface::new(grid, topology, mask.width(), mask.height());
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut violation = None;
+        for _attempt in 0..GROWTH_ATTEMPTS {
+            let growth = Growth::new(&mask, &surface, symmetry, constraints);
+            let Some(galaxies) = growth.fill(&mut rng) else {
+                violation = None;
+                continue;
+            };
+            let mut universe = Universe::new_with_mask(mask.clone(), symmetry, topology, grid);
+            for galaxy in &galaxies {
+                universe.add_galaxy(galaxy);
+            }
+            assert!(universe.is_valid());
+            match constraints.check(&universe) {
+                Ok(()) => return Ok(universe),
+                Err(v) => violation = Some(v),
+            }
+        }
+        Err(GenerationError::Unsatisfied(GROWTH_ATTEMPTS, violation))
+    }
+}
+
+/// The galaxies grown so far by a [GrowingGenerator]
+struct Growth<'a> {
+    mask: &'a Mask,
+    surface: &'a Surface,
+    symmetry: Symmetry,
+    /// The sizes that galaxies may have
+    min_size: usize,
+    max_size: usize,
+    /// The grown galaxies, undone galaxies are left as None so that the indices stay the same
+    galaxies: Vec<Option<Galaxy>>,
+    /// The index of the galaxy of every covered cell
+    owners: HashMap<Position, usize>,
+}
+
+impl<'a> Growth<'a> {
+    fn new(
+        mask: &'a Mask,
+        surface: &'a Surface,
+        symmetry: Symmetry,
+        constraints: &GenerationConstraints,
+    ) -> Growth<'a> {
+        Growth {
+            mask,
+            surface,
+            symmetry,
+            min_size: constraints.min_galaxy_size(),
+            max_size: constraints.max_galaxy_size(),
+            galaxies: Vec::new(),
+            owners: HashMap::new(),
+        }
+    }
+
+    /// Grows galaxies until every cell is covered, or returns None if that takes too long
+    fn fill(mut self, rng: &mut impl Rng) -> Option<Vec<Galaxy>> {
+        for _step in 0..self.mask.size() * 10 {
+            let Some(p) = self.most_enclosed_cell(rng) else {
+                return Some(self.galaxies.into_iter().flatten().collect());
+            };
+            if !self.place(&p, rng) {
+                // Undo the galaxies around p, so that p can be covered together with their cells
+                for q in self.surface.adjacent(&p) {
+                    if let Some(&index) = self.owners.get(&q) {
+                        self.undo(index);
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    /// Returns a random one of the uncovered cells with the fewest uncovered neighbours,
+    /// or None if every cell is covered
+    fn most_enclosed_cell(&self, rng: &mut impl Rng) -> Option<Position> {
+        let uncovered: Vec<(Position, usize)> = self
+            .mask
+            .positions()
+            .filter(|p| self.is_uncovered(p))
+            .map(|p| {
+                let neighbours = self
+                    .surface
+                    .adjacent(&p)
+                    .iter()
+                    .filter(|q| self.is_uncovered(q))
+                    .count();
+                (p, neighbours)
+            })
+            .collect();
+        let fewest = uncovered.iter().map(|&(_, neighbours)| neighbours).min()?;
+        let candidates: Vec<Position> = uncovered
+            .into_iter()
+            .filter(|&(_, neighbours)| neighbours == fewest)
+            .map(|(p, _)| p)
+            .collect();
+        candidates.choose(rng).copied()
+    }
+
+    /// Places a galaxy on [p], centered on the cell itself or between it and an uncovered neighbour,
+    /// and grows it towards a random size. Every center is tried, and the one that grows the largest
+    /// galaxy is kept, since the galaxies that run into their neighbours early would be left small.
+    /// Returns false if no such galaxy is valid and within the allowed sizes.
+    fn place(&mut self, p: &Position, rng: &mut impl Rng) -> bool {
+        let mut seeds = vec![Galaxy::from_positions([*p])];
+        for q in self.surface.adjacent(p) {
+            if self.is_uncovered(&q) {
+                seeds.push(Galaxy::from_positions([*p, q]));
+            }
+        }
+        seeds.shuffle(rng);
+        let smallest_target = MIN_GROWN_SIZE.clamp(self.min_size, self.max_size);
+        let target_size =
+            rng.gen_range(smallest_target..=MAX_GROWN_SIZE.clamp(smallest_target, self.max_size));
+        let mut largest: Option<Galaxy> = None;
+        for seed in seeds {
+            if seed.size() > self.max_size || !self.surface.is_valid(&seed, self.symmetry) {
+                continue;
+            }
+            let Some(center) = self.surface.center(&seed) else {
+                continue;
+            };
+            let galaxy = self.grow(seed, &center, target_size, rng);
+            if largest
+                .as_ref()
+                .is_none_or(|largest| galaxy.size() > largest.size())
+            {
+                largest = Some(galaxy);
+            }
+        }
+        let Some(galaxy) = largest.filter(|galaxy| galaxy.size() >= self.min_size) else {
+            return false;
+        };
+        let index = self.galaxies.len();
+        for q in galaxy.get_positions() {
+            self.owners.insert(*q, index);
+        }
+        self.galaxies.push(Some(galaxy));
+        true
+    }
+
+    /// Adds uncovered cells next to [galaxy] along with their mirrors around [center],
+    /// until it reaches [target_size] or can't grow any further
+    fn grow(
+        &self,
+        mut galaxy: Galaxy,
+        center: &Position,
+        target_size: usize,
+        rng: &mut impl Rng,
+    ) -> Galaxy {
+        while galaxy.size() < target_size {
+            let mut frontier: Vec<Position> = galaxy
+                .get_positions()
+                .flat_map(|p| self.surface.adjacent(p))
+                .filter(|q| self.is_uncovered(q) && !galaxy.contains_position(q))
+                .collect();
+            // The positions of a galaxy come in no particular order, so they're sorted before shuffling
+            frontier.sort();
+            frontier.dedup();
+            frontier.shuffle(rng);
+            let grown = frontier.iter().find_map(|q| {
+                let mut next = galaxy.clone();
+                next.add_position(*q);
+                for image in self.surface.images(self.symmetry, center, q) {
+                    if !self.is_uncovered(&image) {
+                        return None;
+                    }
+                    next.add_position(image);
+                }
+                (next.size() <= self.max_size && self.surface.is_valid(&next, self.symmetry))
+                    .then_some(next)
+            });
+            match grown {
+                Some(next) => galaxy = next,
+                None => break,
+            }
+        }
+        galaxy
+    }
+
+    fn undo(&mut self, index: usize) {
+        if let Some(galaxy) = self.galaxies[index].take() {
+            for p in galaxy.get_positions() {
+                self.owners.remove(p);
+            }
+        }
+    }
+
+    fn is_uncovered(&self, p: &Position) -> bool {
+        self.mask.contains(p) && !self.owners.contains_key(p)
+    }
+}
+
+/// The kinds of [Generator] that puzzles can be generated with
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Default, Hash)]
+pub enum GeneratorType {
+    #[default]
+    Climbing,
+    Growing,
+}
+
+impl GeneratorType {
+    pub const ALL: [GeneratorType; 2] = [GeneratorType::Climbing, GeneratorType::Growing];
+
+    pub fn generator(&self) -> &'static dyn Generator {
+        match self {
+            GeneratorType::Climbing => &ClimbingGenerator,
+            GeneratorType::Growing => &GrowingGenerator,
+        }
+    }
+}
+
+impl Display for GeneratorType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            GeneratorType::Climbing => "climbing",
+            GeneratorType::Growing => "growing",
+        };
+        write!(f, "{name}")
+    }
+}
+
+impl FromStr for GeneratorType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        GeneratorType::ALL
+            .into_iter()
+            .find(|generator| generator.to_string() == s)
+            .ok_or_else(|| format!("Unknown generator: {s}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::constraints::GenerationConstraints;
+    use crate::model::generation_error::GenerationError;
+    use crate::model::generator::{Generator, GeneratorType, GrowingGenerator};
+    use crate::model::grid::GridType;
+    use crate::model::mask::Mask;
+    use crate::model::symmetry::Symmetry;
+    use crate::model::topology::Topology;
+
+    #[test]
+    fn grown_universe_should_be_valid_for_every_symmetry() {
+        for symmetry in Symmetry::ALL {
+            let universe = GrowingGenerator
+                .generate(
+                    Mask::full(6, 6),
+                    symmetry,
+                    Topology::default(),
+                    GridType::default(),
+                    1,
+                )
+                .unwrap();
+            assert!(universe.is_valid(), "{symmetry}\n{universe}");
+        }
+    }
+
+    #[test]
+    fn grown_universe_should_be_valid_for_every_topology_and_grid() {
+        for topology in Topology::ALL {
+            for grid in GridType::ALL
+                .into_iter()
+                .filter(|grid| grid.supports_topology(topology))
+            {
+                let universe = GrowingGenerator
+                    .generate(Mask::full(6, 6), Symmetry::default(), topology, grid, 2)
+                    .unwrap();
+                assert!(universe.is_valid(), "{topology} {grid}\n{universe}");
+            }
+        }
+    }
+
+    #[test]
+    fn grown_universe_should_only_cover_mask() {
+        let mask = Mask::heart(8);
+        let universe = GrowingGenerator
+            .generate(
+                mask.clone(),
+                Symmetry::default(),
+                Topology::default(),
+                GridType::default(),
+                3,
+            )
+            .unwrap();
+        assert!(universe.is_valid());
+        let covered: usize = universe.get_galaxies().iter().map(|g| g.size()).sum();
+        assert_eq!(covered, mask.size());
+    }
+
+    #[test]
+    fn same_seed_should_grow_same_universe() {
+        let generate = |seed| {
+            GrowingGenerator
+                .generate(
+                    Mask::full(8, 8),
+                    Symmetry::default(),
+                    Topology::default(),
+                    GridType::default(),
+                    seed,
+                )
+                .unwrap()
+        };
+        assert_eq!(generate(5).get_walls(), generate(5).get_walls());
+    }
+
+    #[test]
+    fn generator_type_should_round_trip() {
+        for generator in GeneratorType::ALL {
+            assert_eq!(generator.to_string().parse(), Ok(generator));
+        }
+    }
+
+    #[test]
+    fn every_generator_should_satisfy_constraints() {
+        let constraints = GenerationConstraints {
+            galaxy_size: 2..=8,
+            single_cells: false,
+            max_rectangular_fraction: 0.8,
+            ..GenerationConstraints::default()
+        };
+        for generator in GeneratorType::ALL {
+            let universe = generator
+                .generator()
+                .generate_with_constraints(
+                    Mask::full(6, 6),
+                    Symmetry::default(),
+                    Topology::default(),
+                    GridType::default(),
+                    4,
+                    &constraints,
+                )
+                .unwrap();
+            assert_eq!(constraints.check(&universe), Ok(()), "{generator}");
+        }
+    }
+
+    #[test]
+    fn untileable_board_should_be_an_error() {
+        for generator in GeneratorType::ALL {
+            let result = generator.generator().generate_with_constraints(
+                Mask::heart(5),
+                Symmetry::default(),
+                Topology::default(),
+                GridType::Triangular,
+                0,
+                &GenerationConstraints::default(),
+            );
+            assert_eq!(
+                result.err(),
+                Some(GenerationError::Untileable),
+                "{generator}"
+            );
+        }
+    }
+
+    #[test]
+    fn unsatisfiable_constraints_should_be_an_error() {
+        let constraints = GenerationConstraints {
+            galaxy_count: 20..=30,
+            galaxy_size: 2..=4,
+            ..GenerationConstraints::default()
+        };
+        for generator in GeneratorType::ALL {
+            let result = generator.generator().generate_with_constraints(
+                Mask::full(6, 6),
+                Symmetry::default(),
+                Topology::default(),
+                GridType::default(),
+                4,
+                &constraints,
+            );
+            assert_eq!(result.err(), Some(GenerationError::IndivisibleMask(36)));
+        }
+    }
+}
@@ -0,0 +1,572 @@
+use crate::model::galaxy::Galaxy;
+use crate::model::position::Position;
+use crate::model::symmetry::{Images, Symmetry};
+use crate::model::topology::Topology;
+use std::collections::{HashSet, VecDeque};
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+/// The shape of the cells of a board and how they fit together.
+///
+/// Cells are identified by a [Position], whose meaning depends on the grid. Each cell also has
+/// lattice coordinates, in which rotating a cell 180° around a center `c` maps it to `2c - p`.
+/// Centers are given in doubled lattice coordinates, i.e. the sum of two lattice coordinates,
+/// which for the square grid are the usual half-steps.
+pub trait Grid {
+    /// Returns the cells that share a border with [p]
+    fn adjacent(&self, p: &Position) -> Vec<Position>;
+
+    /// Returns, for each corner of [p], the cells that meet at that corner in cyclic order, including [p]
+    fn corners(&self, p: &Position) -> Vec<Vec<Position>>;
+
+    /// Returns the lattice coordinates of [p]
+    fn to_lattice(&self, p: &Position) -> Position;
+
+    /// Returns whether a galaxy with [symmetry] can have its center at [center]
+    fn allows_center(&self, symmetry: Symmetry, center: &Position) -> bool;
+
+    /// Returns the cells surrounding [center], which all must belong to the center's galaxy
+    fn center_positions(&self, center: &Position) -> Vec<Position>;
+
+    /// Returns the cells that [p] is mapped to by the non-identity transformations of [symmetry] around [center]
+    fn images(&self, symmetry: Symmetry, center: &Position, p: &Position) -> Images;
+
+    /// Returns the corners of [p] when drawn, in units where adjacent cells of the square grid are one unit apart
+    fn polygon(&self, p: &Position) -> Vec<(f64, f64)>;
+
+    /// Returns where [center] is drawn, in the same units as [Grid::polygon]
+    fn center_point(&self, center: &Position) -> (f64, f64);
+
+    /// Returns the width and height of a board of [width] by [height] cells when drawn
+    fn extent(&self, width: usize, height: usize) -> (f64, f64);
+
+    /// Returns the center of [galaxy], the center of the bounding box of its lattice coordinates
+    fn center(&self, galaxy: &Galaxy) -> Position {
+        lattice_center(galaxy.get_positions().map(|p| self.to_lattice(p)))
+    }
+
+    fn is_symmetric(&self, galaxy: &Galaxy, symmetry: Symmetry) -> bool {
+        self.is_symmetric_around(galaxy, symmetry, &self.center(galaxy))
+    }
+
+    fn is_symmetric_around(&self, galaxy: &Galaxy, symmetry: Symmetry, center: &Position) -> bool {
+        self.allows_center(symmetry, center)
+            && galaxy.get_positions().all(|p| {
+                self.images(symmetry, center, p)
+                    .iter()
+                    .all(|image| galaxy.contains_position(image))
+            })
+    }
+
+    fn is_connected(&self, galaxy: &Galaxy) -> bool {
+        let Some(&start) = galaxy.get_positions().next() else {
+            return true;
+        };
+        let mut visited = HashSet::from([start]);
+        let mut queue = VecDeque::from([start]);
+        while let Some(p) = queue.pop_front() {
+            for q in self.adjacent(&p) {
+                if galaxy.contains_position(&q) && visited.insert(q) {
+                    queue.push_back(q);
+                }
+            }
+        }
+        visited.len() == galaxy.size()
+    }
+
+    fn contains_center(&self, galaxy: &Galaxy) -> bool {
+        self.contains_center_at(galaxy, &self.center(galaxy))
+    }
+
+    fn contains_center_at(&self, galaxy: &Galaxy, center: &Position) -> bool {
+        let positions = self.center_positions(center);
+        !positions.is_empty() && positions.iter().all(|p| galaxy.contains_position(p))
+    }
+
+    /// Returns whether the connected region of [cells] might be divided into valid galaxies.
+    /// It's a quick count that can't rule out every region, but single cells are valid galaxies
+    /// on most grids, so any region of them can be divided.
+    fn can_tile(&self, cells: &[Position]) -> bool {
+        !cells.is_empty()
+    }
+
+    /// Returns whether [galaxy] is non-empty, connected, and symmetric around a center that it contains
+    fn is_valid(&self, galaxy: &Galaxy, symmetry: Symmetry) -> bool {
+        if galaxy.is_empty() {
+            return false;
+        }
+        // Connectivity is the most expensive check, so it's done last
+        let center = self.center(galaxy);
+        self.contains_center_at(galaxy, &center)
+            && self.is_symmetric_around(galaxy, symmetry, &center)
+            && self.is_connected(galaxy)
+    }
+
+    /// Returns the cells that [p] is mapped to by [symmetry] around the center of [galaxy]
+    fn mirror_positions(&self, galaxy: &Galaxy, p: &Position, symmetry: Symmetry) -> Vec<Position> {
+        self.images(symmetry, &self.center(galaxy), p).to_vec()
+    }
+}
+
+/// Returns the center of the bounding box of [lattice] positions in doubled lattice coordinates,
+/// see [Grid::center]
+pub(crate) fn lattice_center(lattice: impl IntoIterator<Item = Position>) -> Position {
+    let bounds = lattice.into_iter().fold(None, |bounds, p| match bounds {
+        None => Some((p, p)),
+        Some((min, max)) => Some((
+            Position::new(p.row.min(min.row), p.column.min(min.column)),
+            Position::new(p.row.max(max.row), p.column.max(max.column)),
+        )),
+    });
+    bounds.map_or(Position::new(0, 0), |(min, max)| {
+        Position::new(min.row + max.row, min.column + max.column)
+    })
+}
+
+/// The classic grid of square cells, where [Position] is the row and column of the cell
+pub struct SquareGrid;
+
+impl Grid for SquareGrid {
+    fn adjacent(&self, p: &Position) -> Vec<Position> {
+        p.adjacent()
+    }
+
+    fn corners(&self, p: &Position) -> Vec<Vec<Position>> {
+        let (up, down, left, right) = (p.up(), p.down(), p.left(), p.right());
+        vec![
+            vec![up.left(), up, *p, left],
+            vec![up, up.right(), right, *p],
+            vec![*p, right, down.right(), down],
+            vec![left, *p, down, down.left()],
+        ]
+    }
+
+    fn to_lattice(&self, p: &Position) -> Position {
+        *p
+    }
+
+    fn allows_center(&self, symmetry: Symmetry, center: &Position) -> bool {
+        symmetry.allows_center(center)
+    }
+
+    fn center_positions(&self, center: &Position) -> Vec<Position> {
+        center.get_center_placement().positions()
+    }
+
+    fn images(&self, symmetry: Symmetry, center: &Position, p: &Position) -> Images {
+        symmetry.images(center, p)
+    }
+
+    fn polygon(&self, p: &Position) -> Vec<(f64, f64)> {
+        let x = p.column as f64;
+        let y = p.row as f64;
+        vec![(x, y), (x + 1.0, y), (x + 1.0, y + 1.0), (x, y + 1.0)]
+    }
+
+    fn center_point(&self, center: &Position) -> (f64, f64) {
+        (
+            (center.column + 1) as f64 / 2.0,
+            (center.row + 1) as f64 / 2.0,
+        )
+    }
+
+    fn extent(&self, width: usize, height: usize) -> (f64, f64) {
+        (width as f64, height as f64)
+    }
+}
+
+/// A grid of pointy-topped hexagons, where [Position] is the row and column of the cell,
+/// and odd rows are shifted half a cell to the right.
+/// Only [Symmetry::Rotational] is supported.
+pub struct HexagonalGrid;
+
+const SQRT_3: f64 = 1.732_050_807_568_877_2;
+
+impl HexagonalGrid {
+    /// The lattice offsets of the adjacent cells, in counter-clockwise order starting to the right
+    const DIRECTIONS: [(i32, i32); 6] = [(0, 1), (-1, 1), (-1, 0), (0, -1), (1, -1), (1, 0)];
+
+    fn from_lattice(lattice: &Position) -> Position {
+        Position::new(lattice.row, lattice.column + lattice.row.div_euclid(2))
+    }
+
+    fn neighbours(&self, p: &Position) -> Vec<Position> {
+        let lattice = self.to_lattice(p);
+        Self::DIRECTIONS
+            .iter()
+            .map(|&(row, column)| {
+                Self::from_lattice(&Position::new(lattice.row + row, lattice.column + column))
+            })
+            .collect()
+    }
+
+    /// Returns the drawn center of the cell with the given, possibly fractional, lattice coordinates
+    fn point(&self, row: f64, column: f64) -> (f64, f64) {
+        (SQRT_3 * (column + row / 2.0 + 0.5), 1.5 * row + 1.0)
+    }
+}
+
+impl Grid for HexagonalGrid {
+    fn adjacent(&self, p: &Position) -> Vec<Position> {
+        self.neighbours(p)
+    }
+
+    fn corners(&self, p: &Position) -> Vec<Vec<Position>> {
+        let neighbours = self.neighbours(p);
+        (0..neighbours.len())
+            .map(|i| vec![*p, neighbours[i], neighbours[(i + 1) % neighbours.len()]])
+            .collect()
+    }
+
+    /// Axial coordinates, where the column is skewed so that rows are straight lines
+    fn to_lattice(&self, p: &Position) -> Position {
+        Position::new(p.row, p.column - p.row.div_euclid(2))
+    }
+
+    fn allows_center(&self, symmetry: Symmetry, _center: &Position) -> bool {
+        // Every center is either a cell or the middle of a border between two cells
+        symmetry == Symmetry::Rotational
+    }
+
+    fn center_positions(&self, center: &Position) -> Vec<Position> {
+        if center.row % 2 == 0 && center.column % 2 == 0 {
+            return vec![Self::from_lattice(&Position::new(
+                center.row / 2,
+                center.column / 2,
+            ))];
+        }
+        // The center is the sum of the lattice coordinates of two adjacent cells
+        [(0, 1), (1, 0), (1, -1)]
+            .into_iter()
+            .find(|(row, column)| (center.row - row) % 2 == 0 && (center.column - column) % 2 == 0)
+            .map(|(row, column)| {
+                let first = Position::new((center.row - row) / 2, (center.column - column) / 2);
+                let second = Position::new(first.row + row, first.column + column);
+                vec![Self::from_lattice(&first), Self::from_lattice(&second)]
+            })
+            .unwrap_or_default()
+    }
+
+    fn images(&self, symmetry: Symmetry, center: &Position, p: &Position) -> Images {
+        if symmetry != Symmetry::Rotational {
+            return Images::new(&[]);
+        }
+        let lattice = self.to_lattice(p);
+        Images::new(&[Self::from_lattice(&Position::new(
+            center.row - lattice.row,
+            center.column - lattice.column,
+        ))])
+    }
+
+    fn polygon(&self, p: &Position) -> Vec<(f64, f64)> {
+        let lattice = self.to_lattice(p);
+        let (x, y) = self.point(lattice.row as f64, lattice.column as f64);
+        let dx = SQRT_3 / 2.0;
+        vec![
+            (x, y - 1.0),
+            (x + dx, y - 0.5),
+            (x + dx, y + 0.5),
+            (x, y + 1.0),
+            (x - dx, y + 0.5),
+            (x - dx, y - 0.5),
+        ]
+    }
+
+    fn center_point(&self, center: &Position) -> (f64, f64) {
+        self.point(center.row as f64 / 2.0, center.column as f64 / 2.0)
+    }
+
+    fn extent(&self, width: usize, height: usize) -> (f64, f64) {
+        let shift = if height > 1 { 0.5 } else { 0.0 };
+        (SQRT_3 * (width as f64 + shift), 1.5 * height as f64 + 0.5)
+    }
+}
+
+/// A grid of triangles, where [Position] is the row and column of the cell,
+/// and the cell points up if the sum of its row and column is even.
+/// Only [Symmetry::Rotational] is supported, around the corners and the middle of the borders,
+/// so a galaxy needs at least two cells.
+pub struct TriangularGrid;
+
+/// The height of a triangle with sides of length one
+const TRIANGLE_HEIGHT: f64 = SQRT_3 / 2.0;
+
+impl TriangularGrid {
+    fn points_up(p: &Position) -> bool {
+        (p.row + p.column) % 2 == 0
+    }
+
+    /// Returns the cells around the corner on the line above [row] at [column], in cyclic order
+    fn corner(row: i32, column: i32) -> Vec<Position> {
+        vec![
+            Position::new(row - 1, column - 1),
+            Position::new(row - 1, column),
+            Position::new(row - 1, column + 1),
+            Position::new(row, column + 1),
+            Position::new(row, column),
+            Position::new(row, column - 1),
+        ]
+    }
+}
+
+impl Grid for TriangularGrid {
+    fn adjacent(&self, p: &Position) -> Vec<Position> {
+        let vertical = if Self::points_up(p) { p.down() } else { p.up() };
+        vec![p.left(), p.right(), vertical]
+    }
+
+    fn corners(&self, p: &Position) -> Vec<Vec<Position>> {
+        if Self::points_up(p) {
+            vec![
+                Self::corner(p.row, p.column),
+                Self::corner(p.row + 1, p.column + 1),
+                Self::corner(p.row + 1, p.column - 1),
+            ]
+        } else {
+            vec![
+                Self::corner(p.row, p.column - 1),
+                Self::corner(p.row, p.column + 1),
+                Self::corner(p.row + 1, p.column),
+            ]
+        }
+    }
+
+    /// The centroid of the cell, in thirds of the height and halves of the side of a triangle
+    fn to_lattice(&self, p: &Position) -> Position {
+        let offset = if Self::points_up(p) { 2 } else { 1 };
+        Position::new(3 * p.row + offset, p.column)
+    }
+
+    fn allows_center(&self, symmetry: Symmetry, center: &Position) -> bool {
+        // Corners and the middle of horizontal borders are on the lines between rows,
+        // the middle of the slanted borders are halfway between them
+        symmetry == Symmetry::Rotational
+            && match center.row.rem_euclid(6) {
+                0 => center.column % 2 == 0,
+                3 => center.column % 2 != 0,
+                _ => false,
+            }
+    }
+
+    fn center_positions(&self, center: &Position) -> Vec<Position> {
+        let row = center.row.div_euclid(6);
+        match center.row.rem_euclid(6) {
+            0 if center.column % 2 == 0 => {
+                let column = center.column / 2;
+                if (row + column) % 2 == 0 {
+                    Self::corner(row, column)
+                } else {
+                    vec![Position::new(row - 1, column), Position::new(row, column)]
+                }
+            }
+            3 if center.column % 2 != 0 => {
+                let column = center.column.div_euclid(2);
+                vec![Position::new(row, column), Position::new(row, column + 1)]
+            }
+            _ => vec![],
+        }
+    }
+
+    /// A half turn maps triangles that point up to triangles that point down, so every galaxy,
+    /// and thereby every region, has as many of both
+    fn can_tile(&self, cells: &[Position]) -> bool {
+        let up = cells.iter().filter(|p| Self::points_up(p)).count();
+        !cells.is_empty() && 2 * up == cells.len()
+    }
+
+    fn images(&self, symmetry: Symmetry, center: &Position, p: &Position) -> Images {
+        if symmetry != Symmetry::Rotational {
+            return Images::new(&[]);
+        }
+        let lattice = self.to_lattice(p);
+        Images::new(&[Position::new(
+            (center.row - lattice.row).div_euclid(3),
+            center.column - lattice.column,
+        )])
+    }
+
+    fn polygon(&self, p: &Position) -> Vec<(f64, f64)> {
+        let left = p.column as f64 / 2.0;
+        let top = p.row as f64 * TRIANGLE_HEIGHT;
+        let bottom = top + TRIANGLE_HEIGHT;
+        if Self::points_up(p) {
+            vec![(left + 0.5, top), (left + 1.0, bottom), (left, bottom)]
+        } else {
+            vec![(left, top), (left + 1.0, top), (left + 0.5, bottom)]
+        }
+    }
+
+    fn center_point(&self, center: &Position) -> (f64, f64) {
+        (
+            (center.column as f64 / 2.0 + 1.0) / 2.0,
+            center.row as f64 / 6.0 * TRIANGLE_HEIGHT,
+        )
+    }
+
+    fn extent(&self, width: usize, height: usize) -> (f64, f64) {
+        ((width as f64 + 1.0) / 2.0, height as f64 * TRIANGLE_HEIGHT)
+    }
+}
+
+/// The kinds of [Grid] that a board can have
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Default, Hash)]
+pub enum GridType {
+    #[default]
+    Square,
+    Hexagonal,
+    Triangular,
+}
+
+impl GridType {
+    pub const ALL: [GridType; 3] = [GridType::Square, GridType::Hexagonal, GridType::Triangular];
+
+    pub fn grid(&self) -> &'static dyn Grid {
+        match self {
+            GridType::Square => &SquareGrid,
+            GridType::Hexagonal => &HexagonalGrid,
+            GridType::Triangular => &TriangularGrid,
+        }
+    }
+
+    /// Returns whether galaxies on this grid can have [symmetry]
+    pub fn supports(&self, symmetry: Symmetry) -> bool {
+        *self == GridType::Square || symmetry == Symmetry::Rotational
+    }
+
+    /// Returns whether boards of this grid can have [topology], only square grids wrap around
+    pub fn supports_topology(&self, topology: Topology) -> bool {
+        *self == GridType::Square || topology == Topology::Plane
+    }
+}
+
+impl Display for GridType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            GridType::Square => "square",
+            GridType::Hexagonal => "hexagonal",
+            GridType::Triangular => "triangular",
+        };
+        write!(f, "{name}")
+    }
+}
+
+impl FromStr for GridType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        GridType::ALL
+            .into_iter()
+            .find(|grid| grid.to_string() == s)
+            .ok_or_else(|| format!("Unknown grid: {s}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::galaxy::Galaxy;
+    use crate::model::grid::{Grid, GridType, HexagonalGrid, SquareGrid, TriangularGrid};
+    use crate::model::position::Position;
+    use crate::model::symmetry::Symmetry;
+
+    fn galaxy(positions: &[(i32, i32)]) -> Galaxy {
+        Galaxy::from_positions(positions.iter().copied().map(Position::from))
+    }
+
+    #[test]
+    fn square_grid_should_agree_with_galaxy() {
+        let s = galaxy(&[(0, 1), (0, 2), (1, 0), (1, 1)]);
+        assert_eq!(SquareGrid.center(&s), s.center());
+        assert!(SquareGrid.is_valid(&s, Symmetry::Rotational));
+        assert!(!SquareGrid.is_valid(&s, Symmetry::VerticalAxis));
+    }
+
+    #[test]
+    fn adjacent_cells_should_be_adjacent_to_each_other() {
+        for grid_type in GridType::ALL {
+            let grid = grid_type.grid();
+            for row in 0..4 {
+                for column in 0..4 {
+                    let p = Position::new(row, column);
+                    for q in grid.adjacent(&p) {
+                        assert!(grid.adjacent(&q).contains(&p), "{grid_type} {p} {q}");
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn single_hexagon_should_be_valid() {
+        let single = galaxy(&[(3, 2)]);
+        assert!(HexagonalGrid.is_valid(&single, Symmetry::Rotational));
+        assert_eq!(
+            HexagonalGrid.center_positions(&HexagonalGrid.center(&single)),
+            vec![Position::new(3, 2)]
+        );
+    }
+
+    #[test]
+    fn hexagons_across_rows_should_be_symmetric() {
+        // (1, 0) is below and to the right of (0, 0), since odd rows are shifted
+        let pair = galaxy(&[(0, 0), (1, 0)]);
+        assert!(HexagonalGrid.is_valid(&pair, Symmetry::Rotational));
+        let center = HexagonalGrid.center(&pair);
+        assert_eq!(
+            *HexagonalGrid.images(Symmetry::Rotational, &center, &Position::new(0, 0)),
+            [Position::new(1, 0)]
+        );
+        assert!(!HexagonalGrid.is_valid(&galaxy(&[(0, 0), (1, 0), (0, 1)]), Symmetry::Rotational));
+    }
+
+    #[test]
+    fn single_triangle_should_be_invalid() {
+        assert!(!TriangularGrid.is_valid(&galaxy(&[(0, 0)]), Symmetry::Rotational));
+    }
+
+    #[test]
+    fn triangles_sharing_a_border_should_be_valid() {
+        // Pointing up and down next to each other
+        assert!(TriangularGrid.is_valid(&galaxy(&[(0, 0), (0, 1)]), Symmetry::Rotational));
+        // Pointing up above pointing down
+        assert!(TriangularGrid.is_valid(&galaxy(&[(0, 0), (1, 0)]), Symmetry::Rotational));
+    }
+
+    #[test]
+    fn hexagon_of_triangles_should_be_valid() {
+        let hexagon = galaxy(&[(0, 0), (0, 1), (0, 2), (1, 2), (1, 1), (1, 0)]);
+        assert!(TriangularGrid.is_valid(&hexagon, Symmetry::Rotational));
+        let center = TriangularGrid.center(&hexagon);
+        let mut positions = TriangularGrid.center_positions(&center);
+        positions.sort();
+        let mut expected: Vec<Position> = hexagon.get_positions().copied().collect();
+        expected.sort();
+        assert_eq!(positions, expected);
+    }
+
+    #[test]
+    fn triangles_should_only_tile_balanced_regions() {
+        let grid = GridType::Triangular.grid();
+        assert!(!grid.can_tile(&[Position::new(0, 0)]));
+        assert!(!grid.can_tile(&[Position::new(0, 0), Position::new(0, 2)]));
+        assert!(grid.can_tile(&[Position::new(0, 0), Position::new(0, 1)]));
+        assert!(GridType::Square.grid().can_tile(&[Position::new(0, 0)]));
+    }
+
+    #[test]
+    fn corners_should_contain_the_cell() {
+        for grid_type in GridType::ALL {
+            let grid = grid_type.grid();
+            let p = Position::new(2, 3);
+            for corner in grid.corners(&p) {
+                assert!(corner.contains(&p), "{grid_type}");
+            }
+        }
+    }
+
+    #[test]
+    fn should_parse_displayed_grid() {
+        for grid in GridType::ALL {
+            assert_eq!(grid.to_string().parse(), Ok(grid));
+        }
+    }
+}
@@ -10,6 +10,12 @@ pub enum HistoryEntry {
     ToggleBorder(Border),
 }
 
+impl Default for History {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl History {
     pub fn new() -> Self {
         History {
@@ -0,0 +1,231 @@
+use crate::model::picture::Picture;
+use crate::model::position::Position;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+/// The shape of a board, i.e. which cells of its bounding rectangle are part of it
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct Mask {
+    width: usize,
+    height: usize,
+    cells: Vec<bool>,
+}
+
+impl Mask {
+    /// Creates a mask where every cell of the rectangle is part of the board
+    pub fn full(width: usize, height: usize) -> Mask {
+        Mask {
+            width,
+            height,
+            cells: vec![true; width * height],
+        }
+    }
+
+    /// Creates a mask of the cells whose centers lie within the circle inscribed in a square of the given size
+    pub fn circle(size: usize) -> Mask {
+        Self::from_shape(size, size, |x, y| x * x + y * y <= 1.0)
+    }
+
+    /// Creates a heart shaped mask that fits within a square of the given size
+    pub fn heart(size: usize) -> Mask {
+        Self::from_shape(size, size, |x, y| {
+            // The heart curve (x² + y² - 1)³ = x²y³, scaled to fit within [-1, 1] and flipped upside down
+            let x = 1.15 * x;
+            let y = 0.125 - 1.125 * y;
+            (x * x + y * y - 1.0).powi(3) <= x * x * y.powi(3)
+        })
+    }
+
+    /// Creates a mask from the cells of its bounding rectangle, row by row
+    pub fn from_cells(width: usize, height: usize, cells: Vec<bool>) -> Mask {
+        assert_eq!(cells.len(), width * height);
+        Mask {
+            width,
+            height,
+            cells,
+        }
+    }
+
+    /// Creates a mask where the black pixels of the picture are part of the board
+    pub fn from_picture(picture: &Picture) -> Mask {
+        let width = picture.width();
+        let height = picture.height();
+        let cells = (0..height)
+            .flat_map(|row| (0..width).map(move |column| Position::from((row, column))))
+            .map(|p| picture.is_black(&p))
+            .collect();
+        Mask {
+            width,
+            height,
+            cells,
+        }
+    }
+
+    /// Creates a mask from a predicate on coordinates that are scaled to [-1, 1],
+    /// evaluated at the center of each cell
+    fn from_shape(width: usize, height: usize, contains: impl Fn(f64, f64) -> bool) -> Mask {
+        let mut cells = Vec::with_capacity(width * height);
+        for row in 0..height {
+            for column in 0..width {
+                let x = (2.0 * column as f64 + 1.0) / width as f64 - 1.0;
+                let y = (2.0 * row as f64 + 1.0) / height as f64 - 1.0;
+                cells.push(contains(x, y));
+            }
+        }
+        Mask {
+            width,
+            height,
+            cells,
+        }
+    }
+
+    /// Returns a copy of this mask where the given cell is not part of the board
+    pub fn without(&self, p: &Position) -> Mask {
+        let mut mask = self.clone();
+        if self.contains(p) {
+            mask.cells[p.row as usize * self.width + p.column as usize] = false;
+        }
+        mask
+    }
+
+    /// The width of the bounding rectangle of the mask
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// The height of the bounding rectangle of the mask
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Returns whether [p] is a cell of the board
+    pub fn contains(&self, p: &Position) -> bool {
+        p.row >= 0
+            && p.row < self.height as i32
+            && p.column >= 0
+            && p.column < self.width as i32
+            && self.cells[p.row as usize * self.width + p.column as usize]
+    }
+
+    /// Returns the cells of the board, row by row
+    pub fn positions(&self) -> impl Iterator<Item = Position> + use<'_> {
+        (0..self.height)
+            .flat_map(move |row| (0..self.width).map(move |column| Position::from((row, column))))
+            .filter(|p| self.contains(p))
+    }
+
+    /// Returns the number of cells of the board
+    pub fn size(&self) -> usize {
+        self.cells.iter().filter(|&&cell| cell).count()
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.cells.iter().all(|&cell| cell)
+    }
+}
+
+/// The built-in shapes of a board
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Default)]
+pub enum Shape {
+    #[default]
+    Square,
+    Circle,
+    Heart,
+}
+
+impl Shape {
+    pub const ALL: [Shape; 3] = [Shape::Square, Shape::Circle, Shape::Heart];
+
+    /// Returns the mask of this shape, fitted within a square of the given size
+    pub fn mask(&self, size: usize) -> Mask {
+        match self {
+            Shape::Square => Mask::full(size, size),
+            Shape::Circle => Mask::circle(size),
+            Shape::Heart => Mask::heart(size),
+        }
+    }
+}
+
+impl Display for Shape {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Shape::Square => "square",
+            Shape::Circle => "circle",
+            Shape::Heart => "heart",
+        };
+        write!(f, "{name}")
+    }
+}
+
+impl FromStr for Shape {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Shape::ALL
+            .into_iter()
+            .find(|shape| shape.to_string() == s)
+            .ok_or_else(|| format!("Unknown shape: {s}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::mask::{Mask, Shape};
+    use crate::model::picture::Picture;
+    use crate::model::position::Position;
+
+    #[test]
+    fn full_mask_should_contain_every_cell() {
+        let mask = Mask::full(3, 2);
+        assert_eq!(mask.size(), 6);
+        assert!(mask.is_full());
+        assert!(mask.contains(&Position::new(1, 2)));
+        assert!(!mask.contains(&Position::new(2, 0)));
+        assert!(!mask.contains(&Position::new(0, -1)));
+    }
+
+    #[test]
+    fn circle_should_not_contain_corners() {
+        let mask = Mask::circle(8);
+        assert!(!mask.contains(&Position::new(0, 0)));
+        assert!(!mask.contains(&Position::new(7, 7)));
+        assert!(mask.contains(&Position::new(0, 4)));
+        assert!(mask.contains(&Position::new(4, 4)));
+    }
+
+    #[test]
+    fn heart_should_have_a_dip_at_the_top() {
+        let mask = Mask::heart(10);
+        assert!(!mask.contains(&Position::new(0, 4)));
+        assert!(mask.contains(&Position::new(1, 2)));
+        assert!(mask.contains(&Position::new(1, 7)));
+        assert!(mask.contains(&Position::new(8, 4)));
+        assert!(!mask.contains(&Position::new(9, 0)));
+    }
+
+    #[test]
+    fn without_should_remove_cell() {
+        let mask = Mask::full(2, 2).without(&Position::new(0, 1));
+        let positions: Vec<Position> = mask.positions().collect();
+        assert_eq!(
+            positions,
+            vec![Position::new(0, 0), Position::new(1, 0), Position::new(1, 1)]
+        );
+    }
+
+    #[test]
+    fn black_pixels_should_be_cells() {
+        let picture = Picture::from_rows(&[vec![true, false], vec![true, true]]);
+        assert_eq!(
+            Mask::from_picture(&picture),
+            Mask::full(2, 2).without(&Position::new(0, 1))
+        );
+    }
+
+    #[test]
+    fn should_parse_displayed_shape() {
+        for shape in Shape::ALL {
+            assert_eq!(shape.to_string().parse(), Ok(shape));
+        }
+    }
+}
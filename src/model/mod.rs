@@ -1,10 +1,34 @@
 pub mod position;
 pub mod galaxy;
+pub mod grid;
 pub mod universe;
 pub mod rectangle;
 pub mod board;
+#[cfg(feature = "parallel")]
+pub mod batch;
 pub mod border;
 pub mod state;
 pub mod objective;
+pub mod objective_error;
 pub mod board_error;
 pub mod history;
+pub mod game_stats;
+pub mod generation;
+pub mod generation_error;
+pub mod generator;
+pub mod timer;
+pub mod daily;
+pub mod date;
+pub mod diagram;
+pub mod difficulty;
+pub mod statistics;
+pub mod storage;
+pub mod mask;
+pub mod picture;
+pub mod puzzle_link;
+pub mod saved_game;
+pub mod solution_error;
+pub mod solver;
+pub mod symmetry;
+pub mod topology;
+pub mod constraints;
@@ -1,31 +1,409 @@
 use crate::model::border::Border;
+use crate::model::galaxy::Galaxy;
+use crate::model::grid::GridType;
+use crate::model::mask::Mask;
+use crate::model::objective_error::ObjectiveError;
+use crate::model::picture::Picture;
 use crate::model::position::Position;
+use crate::model::solver::{Solver, STEPS_PER_CELL};
+use crate::model::symmetry::Symmetry;
+use crate::model::topology::{Surface, Topology};
 use crate::model::universe::Universe;
+use std::cmp::max;
+use std::collections::HashMap;
 
-#[derive(Debug, Copy, Clone)]
+/// When the solver runs out of steps while generating, every this many of the walls of the solution
+/// that aren't given yet are given, see [Objective::add_walls_until_unique]
+const EXHAUSTED_WALL_SPACING: usize = 32;
+
+/// The colour of a center. When the puzzle is solved, the galaxies of black centers
+/// are shaded, revealing a picture.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Default)]
+pub enum CenterColor {
+    #[default]
+    White,
+    Black,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
 pub struct GalaxyCenter {
     pub position: Position,
     pub size: Option<usize>,
+    pub color: CenterColor,
 }
 
+#[derive(Clone)]
 pub struct Objective {
     pub centers: Vec<GalaxyCenter>,
+    /// Walls that are given as part of the puzzle, the player cannot remove them
     pub walls: Vec<Border>,
+    /// The symmetry that every galaxy must have around its center
+    pub symmetry: Symmetry,
+    /// How the edges of the board are connected
+    pub topology: Topology,
+    /// The shape of the cells, which determines how the positions of the centers are interpreted
+    pub grid: GridType,
 }
 
 impl Objective {
     pub fn generate(universe: &Universe) -> Self {
+        Self::generate_with_colors(universe, |_| CenterColor::White)
+    }
+
+    /// Generates an objective where the galaxies that mostly cover black pixels of [picture]
+    /// get black centers, so that the shaded galaxies of the solution approximate the picture.
+    /// This is the colouring that [Universe::get_picture_mismatch] scores while generating with a picture.
+    /// The picture is scaled to the size of the universe.
+    pub fn generate_with_picture(universe: &Universe, picture: &Picture) -> Self {
+        let picture = picture.scaled(universe.width(), universe.height());
+        Self::generate_with_colors(universe, |galaxy| {
+            let black_count = galaxy
+                .get_positions()
+                .filter(|p| picture.is_black(p))
+                .count();
+            if 2 * black_count > galaxy.size() {
+                CenterColor::Black
+            } else {
+                CenterColor::White
+            }
+        })
+    }
+
+    /// Generates an objective where centers are given the size of their galaxy, and
+    /// then the sizes and walls that aren't needed for a unique solution are removed
+    pub fn generate_with_sizes(universe: &Universe) -> Self {
+        let mut objective = Self::generate(universe);
+        for center in &mut objective.centers {
+            let cells = universe.surface().center_positions(&center.position);
+            center.size = cells.first().map(|p| universe.get_galaxy(p).size());
+        }
+        objective.minimize_clues(universe.mask());
+        objective
+    }
+
+    fn generate_with_colors(universe: &Universe, color: impl Fn(&Galaxy) -> CenterColor) -> Self {
         let walls = Vec::new();
+        let surface = universe.surface();
         let centers = universe
             .get_galaxies()
             .iter()
             .map(|galaxy| GalaxyCenter {
-                position: galaxy.center(),
+                position: surface
+                    .center(galaxy)
+                    .expect("Galaxies of a valid universe should have a center"),
                 size: None,
                 // size: Some(galaxy.size()),
+                color: color(galaxy),
             })
             .collect();
 
-        Objective { centers, walls }
+        let symmetry = universe.symmetry();
+        let topology = universe.topology();
+        let grid = universe.grid_type();
+        let mut objective = Objective {
+            centers,
+            walls,
+            symmetry,
+            topology,
+            grid,
+        };
+        objective.add_walls_until_unique(universe);
+        objective
+    }
+
+    /// Checks that the objective can be played on a board of the cells of [mask],
+    /// i.e. that every center and given wall is on the board, and that no two centers share a cell
+    pub fn validate(&self, mask: &Mask) -> Result<(), ObjectiveError> {
+        let surface = Surface::new(self.grid, self.topology, mask.width(), mask.height());
+        let is_on_board = |p: &Position| mask.contains(p);
+        let mut center_by_cell: HashMap<Position, Position> = HashMap::new();
+        for gc in &self.centers {
+            let center = gc.position;
+            if !surface.allows_center(self.symmetry, &center) {
+                return Err(ObjectiveError::InvalidCenter(center));
+            }
+            let positions = surface.center_positions(&center);
+            if positions.is_empty() {
+                return Err(ObjectiveError::InvalidCenter(center));
+            }
+            if !positions.iter().all(is_on_board) {
+                return Err(ObjectiveError::CenterOutOfBounds(center));
+            }
+            for p in positions {
+                match center_by_cell.insert(p, center) {
+                    Some(other) if other == center => {
+                        return Err(ObjectiveError::DuplicateCenter(center))
+                    }
+                    Some(other) => return Err(ObjectiveError::OverlappingCenters(other, center)),
+                    None => {}
+                }
+            }
+        }
+        for wall in &self.walls {
+            if !is_on_board(&wall.p1())
+                || !is_on_board(&wall.p2())
+                || !surface.are_adjacent(&wall.p1(), &wall.p2())
+            {
+                return Err(ObjectiveError::WallOutOfBounds(*wall));
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns whether the given border is one of the objective's given walls
+    pub fn is_given_wall(&self, border: &Border) -> bool {
+        self.walls.contains(border)
+    }
+
+    /// Greedily removes given walls and then sizes of centers, keeping each removal
+    /// unless the objective stops having a unique solution. Objectives without
+    /// a unique solution are left as they are.
+    pub fn minimize_clues(&mut self, mask: &Mask) {
+        if !self.is_provably_unique(mask) {
+            return;
+        }
+        for index in (0..self.walls.len()).rev() {
+            let wall = self.walls.remove(index);
+            if !self.is_provably_unique(mask) {
+                self.walls.insert(index, wall);
+            }
+        }
+        for index in 0..self.centers.len() {
+            let Some(size) = self.centers[index].size.take() else {
+                continue;
+            };
+            if !self.is_provably_unique(mask) {
+                self.centers[index].size = Some(size);
+            }
+        }
+    }
+
+    /// Returns a solver of the objective whose steps are limited by the size of [mask]
+    fn limited_solver(&self, mask: &Mask) -> Solver {
+        Solver::new(mask, self).with_max_steps(STEPS_PER_CELL * mask.size())
+    }
+
+    /// Returns whether the solver finds that the objective has exactly one solution in time
+    fn is_provably_unique(&self, mask: &Mask) -> bool {
+        let solver = self.limited_solver(mask);
+        solver.is_unique() && !solver.is_exhausted()
+    }
+
+    /// Adds walls from [universe] until it is the only solution to this objective,
+    /// and then removes the added walls that turned out not to be needed.
+    /// When the solver runs out of steps, walls of the solution spread over the board are given
+    /// to narrow down the search, twice as many of the remaining ones each time.
+    fn add_walls_until_unique(&mut self, universe: &Universe) {
+        let solution_walls = universe.get_walls();
+        let mask = universe.mask();
+        let given_walls = self.walls.len();
+        let mut spacing = EXHAUSTED_WALL_SPACING;
+
+        loop {
+            let solver = self.limited_solver(mask);
+            let solutions = solver.solve(2);
+            if solver.is_exhausted() {
+                let missing: Vec<Border> = solution_walls
+                    .iter()
+                    .filter(|wall| !self.is_given_wall(wall))
+                    .copied()
+                    .collect();
+                if missing.is_empty() {
+                    break;
+                }
+                self.walls.extend(missing.iter().step_by(spacing));
+                spacing = max(spacing / 2, 1);
+                continue;
+            }
+            let alternative = solutions
+                .into_iter()
+                .map(|solution| solution.get_walls())
+                .find(|walls| walls != &solution_walls);
+            let Some(alternative_walls) = alternative else {
+                break;
+            };
+            // Any wall in the solution that the alternative lacks rules out the alternative
+            let wall = solution_walls
+                .difference(&alternative_walls)
+                .next()
+                .copied()
+                .expect("Alternative solution should lack a wall of the solution");
+            self.walls.push(wall);
+        }
+
+        for index in (given_walls..self.walls.len()).rev() {
+            let wall = self.walls.remove(index);
+            if !self.is_provably_unique(mask) {
+                self.walls.insert(index, wall);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::border::Border;
+    use crate::model::galaxy::Galaxy;
+    use crate::model::grid::GridType;
+    use crate::model::mask::Mask;
+    use crate::model::objective::{CenterColor, GalaxyCenter, Objective};
+    use crate::model::objective_error::ObjectiveError;
+    use crate::model::picture::Picture;
+    use crate::model::position::Position;
+    use crate::model::solver::Solver;
+    use crate::model::symmetry::Symmetry;
+    use crate::model::topology::Topology;
+    use crate::model::universe::Universe;
+
+    fn objective(centers: &[(i32, i32)]) -> Objective {
+        Objective {
+            centers: centers
+                .iter()
+                .map(|&(row, column)| GalaxyCenter {
+                    position: Position::new(row, column),
+                    size: None,
+                    color: CenterColor::White,
+                })
+                .collect(),
+            walls: vec![],
+            symmetry: Symmetry::default(),
+            topology: Topology::default(),
+            grid: GridType::default(),
+        }
+    }
+
+    #[test]
+    fn generated_objective_should_be_valid() {
+        let universe = Universe::generate(5, 5);
+        assert_eq!(
+            Objective::generate(&universe).validate(universe.mask()),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn generated_objective_should_give_few_walls() {
+        // The search of the objective of seed 9 runs out of steps without any given walls
+        for seed in [4, 9] {
+            let universe = Universe::generate_with_seed(
+                Mask::full(16, 16),
+                Symmetry::default(),
+                Topology::default(),
+                GridType::Square,
+                seed,
+            )
+            .unwrap();
+            let objective = Objective::generate(&universe);
+            assert!(objective.walls.len() * 10 <= universe.get_walls().len());
+        }
+    }
+
+    #[test]
+    fn center_outside_board_should_be_invalid() {
+        assert_eq!(
+            objective(&[(0, 0), (0, 5)]).validate(&Mask::full(3, 1)),
+            Err(ObjectiveError::CenterOutOfBounds(Position::new(0, 5)))
+        );
+        assert_eq!(
+            objective(&[(-1, 0)]).validate(&Mask::full(3, 1)),
+            Err(ObjectiveError::CenterOutOfBounds(Position::new(-1, 0)))
+        );
+    }
+
+    #[test]
+    fn center_on_hole_should_be_invalid() {
+        let mask = Mask::full(3, 1).without(&Position::new(0, 2));
+        assert_eq!(
+            objective(&[(0, 0), (0, 4)]).validate(&mask),
+            Err(ObjectiveError::CenterOutOfBounds(Position::new(0, 4)))
+        );
+        let mut objective = objective(&[(0, 0)]);
+        let wall = Border::new(Position::new(0, 1), Position::new(0, 2));
+        objective.walls.push(wall);
+        assert_eq!(
+            objective.validate(&mask),
+            Err(ObjectiveError::WallOutOfBounds(wall))
+        );
+    }
+
+    #[test]
+    fn duplicate_and_overlapping_centers_should_be_invalid() {
+        assert_eq!(
+            objective(&[(0, 0), (0, 0)]).validate(&Mask::full(3, 1)),
+            Err(ObjectiveError::DuplicateCenter(Position::new(0, 0)))
+        );
+        assert_eq!(
+            objective(&[(0, 0), (0, 1)]).validate(&Mask::full(3, 1)),
+            Err(ObjectiveError::OverlappingCenters(
+                Position::new(0, 0),
+                Position::new(0, 1)
+            ))
+        );
+    }
+
+    #[test]
+    fn wall_outside_board_should_be_invalid() {
+        let mut objective = objective(&[(0, 0)]);
+        let wall = Border::new(Position::new(0, 0), Position::new(1, 0));
+        objective.walls.push(wall);
+        assert_eq!(
+            objective.validate(&Mask::full(1, 1)),
+            Err(ObjectiveError::WallOutOfBounds(wall))
+        );
+    }
+
+    #[test]
+    fn galaxies_covering_black_pixels_should_get_black_centers() {
+        let galaxies = [
+            Galaxy::from_positions([Position::new(0, 0), Position::new(0, 1)]),
+            Galaxy::from_positions([Position::new(1, 0), Position::new(1, 1)]),
+        ];
+        let universe = Universe::from(galaxies.as_slice());
+        let picture = Picture::from_rows(&[vec![true, true], vec![true, false]]);
+        let objective = Objective::generate_with_picture(&universe, &picture);
+        let color_of = |row| {
+            objective
+                .centers
+                .iter()
+                .find(|gc| gc.position == Position::new(row, 1))
+                .unwrap()
+                .color
+        };
+        assert_eq!(color_of(0), CenterColor::Black);
+        assert_eq!(color_of(2), CenterColor::White);
+    }
+
+    #[test]
+    fn redundant_clues_should_be_removed() {
+        let mut objective = objective(&[(0, 1), (2, 1)]);
+        objective.centers[0].size = Some(2);
+        objective
+            .walls
+            .push(Border::new(Position::new(0, 0), Position::new(1, 0)));
+        objective.minimize_clues(&Mask::full(2, 2));
+        assert!(objective.walls.is_empty());
+        assert_eq!(objective.centers[0].size, None);
+    }
+
+    #[test]
+    fn remaining_size_clues_should_all_be_needed() {
+        let universe = Universe::generate(5, 5);
+        let mask = universe.mask();
+        let objective = Objective::generate_with_sizes(&universe);
+        let solutions = Solver::new(mask, &objective).solve(2);
+        assert_eq!(solutions.len(), 1);
+        assert_eq!(solutions[0].get_walls(), universe.get_walls());
+        for index in 0..objective.centers.len() {
+            if objective.centers[index].size.is_some() {
+                let mut without_size = objective.clone();
+                without_size.centers[index].size = None;
+                assert!(!Solver::new(mask, &without_size).is_unique());
+            }
+        }
+        for index in 0..objective.walls.len() {
+            let mut without_wall = objective.clone();
+            without_wall.walls.remove(index);
+            assert!(!Solver::new(mask, &without_wall).is_unique());
+        }
     }
 }
@@ -0,0 +1,46 @@
+use crate::model::border::Border;
+use crate::model::position::Position;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+
+/// Why an [crate::model::objective::Objective] can't be played on a board
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum ObjectiveError {
+    /// The center isn't at a place where a galaxy of the objective's grid and symmetry can be centered
+    InvalidCenter(Position),
+    /// Some of the cells surrounding the center are outside the board or on one of its holes
+    CenterOutOfBounds(Position),
+    /// The same center is given twice
+    DuplicateCenter(Position),
+    /// The two centers share a cell, so they can't belong to different galaxies
+    OverlappingCenters(Position, Position),
+    /// The given wall isn't between two adjacent cells of the board, e.g. next to a hole
+    WallOutOfBounds(Border),
+}
+
+impl Display for ObjectiveError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ObjectiveError::InvalidCenter(center) => {
+                write!(f, "The center {center} is not at a valid position")
+            }
+            ObjectiveError::CenterOutOfBounds(center) => {
+                write!(f, "The center {center} is outside the board")
+            }
+            ObjectiveError::DuplicateCenter(center) => {
+                write!(f, "The center {center} is given more than once")
+            }
+            ObjectiveError::OverlappingCenters(c1, c2) => {
+                write!(f, "The centers {c1} and {c2} share a cell")
+            }
+            ObjectiveError::WallOutOfBounds(border) => write!(
+                f,
+                "The wall between {} and {} is not on the board",
+                border.p1(),
+                border.p2()
+            ),
+        }
+    }
+}
+
+impl Error for ObjectiveError {}
@@ -0,0 +1,216 @@
+use crate::model::position::Position;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+/// A monochrome bitmap, where each pixel is either black or white
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct Picture {
+    width: usize,
+    height: usize,
+    pixels: Vec<bool>,
+}
+
+impl Picture {
+    /// Creates a picture from rows of pixels, where true is black.
+    /// All rows must have the same length.
+    pub fn from_rows(rows: &[Vec<bool>]) -> Picture {
+        let height = rows.len();
+        let width = rows.first().map(|row| row.len()).unwrap_or(0);
+        debug_assert!(rows.iter().all(|row| row.len() == width));
+        Picture {
+            width,
+            height,
+            pixels: rows.concat(),
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Returns whether the pixel at [p] is black, positions outside the picture are white
+    pub fn is_black(&self, p: &Position) -> bool {
+        if p.row < 0 || p.row >= self.height as i32 || p.column < 0 || p.column >= self.width as i32
+        {
+            return false;
+        }
+        self.pixels[p.row as usize * self.width + p.column as usize]
+    }
+
+    /// Returns the number of black pixels in the picture
+    pub fn black_count(&self) -> usize {
+        self.pixels.iter().filter(|&&black| black).count()
+    }
+
+    /// Returns a copy of this picture resized to the given dimensions, using nearest neighbour sampling
+    pub fn scaled(&self, width: usize, height: usize) -> Picture {
+        if width == self.width && height == self.height {
+            return self.clone();
+        }
+        let pixels = (0..height)
+            .flat_map(|row| (0..width).map(move |column| (row, column)))
+            .map(|(row, column)| {
+                let source_row = (2 * row + 1) * self.height / (2 * height);
+                let source_column = (2 * column + 1) * self.width / (2 * width);
+                self.is_black(&Position::from((source_row, source_column)))
+            })
+            .collect();
+        Picture {
+            width,
+            height,
+            pixels,
+        }
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+pub enum PictureError {
+    /// The magic number is not one of the supported plain formats, P1 (PBM) or P2 (PGM)
+    UnsupportedFormat(String),
+    /// The input ended before the header or all pixels were read
+    UnexpectedEnd,
+    /// A token could not be parsed as a valid number
+    InvalidValue(String),
+    /// The width and height in the header have more pixels than can be counted
+    TooLarge(usize, usize),
+}
+
+impl Display for PictureError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PictureError::UnsupportedFormat(magic) => {
+                write!(f, "unsupported format {magic}, expected P1 or P2")
+            }
+            PictureError::UnexpectedEnd => write!(f, "unexpected end of picture"),
+            PictureError::InvalidValue(value) => write!(f, "invalid value {value}"),
+            PictureError::TooLarge(width, height) => {
+                write!(f, "picture of {width}x{height} pixels is too large")
+            }
+        }
+    }
+}
+
+fn parse_number(token: Option<&str>) -> Result<usize, PictureError> {
+    let token = token.ok_or(PictureError::UnexpectedEnd)?;
+    token
+        .parse()
+        .map_err(|_| PictureError::InvalidValue(token.to_string()))
+}
+
+/// Parses a plain PBM (P1) or plain PGM (P2) picture.
+/// In PBM, 1 is black. In PGM, values darker than half of the max value are black.
+impl FromStr for Picture {
+    type Err = PictureError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut tokens = s
+            .lines()
+            .map(|line| line.split('#').next().unwrap_or_default())
+            .flat_map(|line| line.split_whitespace());
+        let magic = tokens.next().ok_or(PictureError::UnexpectedEnd)?;
+        if magic != "P1" && magic != "P2" {
+            return Err(PictureError::UnsupportedFormat(magic.to_string()));
+        }
+        let width = parse_number(tokens.next())?;
+        let height = parse_number(tokens.next())?;
+        let size = width
+            .checked_mul(height)
+            .ok_or(PictureError::TooLarge(width, height))?;
+
+        // Each pixel takes at least one character of the remaining tokens,
+        // so the header can't make room for more pixels than the picture has
+        let mut tokens = tokens.collect::<Vec<&str>>().into_iter();
+        let available: usize = tokens.as_slice().iter().map(|token| token.len()).sum();
+        let mut pixels = Vec::with_capacity(size.min(available));
+        if magic == "P1" {
+            // Pixels in plain PBM don't need to be separated by whitespace
+            let mut digits = tokens.flat_map(|token| token.split("").filter(|d| !d.is_empty()));
+            while pixels.len() < size {
+                pixels.push(parse_number(digits.next())? != 0);
+            }
+        } else {
+            let max_value = parse_number(tokens.next())?;
+            while pixels.len() < size {
+                pixels.push(2 * parse_number(tokens.next())? < max_value);
+            }
+        }
+        Ok(Picture {
+            width,
+            height,
+            pixels,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::picture::{Picture, PictureError};
+    use crate::model::position::Position;
+
+    fn checkerboard() -> Picture {
+        Picture::from_rows(&[vec![true, false], vec![false, true]])
+    }
+
+    #[test]
+    fn outside_should_be_white() {
+        let picture = checkerboard();
+        assert!(!picture.is_black(&Position::new(-1, 0)));
+        assert!(!picture.is_black(&Position::new(0, 2)));
+    }
+
+    #[test]
+    fn scaled_up_picture_should_keep_quadrants() {
+        let picture = checkerboard().scaled(4, 4);
+        assert_eq!(picture.black_count(), 8);
+        assert!(picture.is_black(&Position::new(1, 1)));
+        assert!(!picture.is_black(&Position::new(1, 2)));
+        assert!(picture.is_black(&Position::new(3, 3)));
+    }
+
+    #[test]
+    fn scaled_down_picture_should_sample_nearest_pixel() {
+        let picture = checkerboard().scaled(4, 4).scaled(2, 2);
+        assert_eq!(picture, checkerboard());
+    }
+
+    #[test]
+    fn should_parse_pbm() {
+        let pbm = "P1\n# A checkerboard\n2 2\n1 0\n0 1\n";
+        assert_eq!(pbm.parse(), Ok(checkerboard()));
+        let compact_pbm = "P1 2 2 10 01";
+        assert_eq!(compact_pbm.parse(), Ok(checkerboard()));
+    }
+
+    #[test]
+    fn should_parse_pgm() {
+        let pgm = "P2\n2 2\n255\n0 200\n255 127\n";
+        assert_eq!(pgm.parse(), Ok(checkerboard()));
+    }
+
+    #[test]
+    fn should_reject_binary_formats() {
+        let result: Result<Picture, _> = "P4\n2 2\n".parse();
+        assert_eq!(result, Err(PictureError::UnsupportedFormat("P4".to_string())));
+    }
+
+    #[test]
+    fn should_reject_truncated_pixels() {
+        let result: Result<Picture, _> = "P1 2 2 1 0 0".parse();
+        assert_eq!(result, Err(PictureError::UnexpectedEnd));
+    }
+
+    #[test]
+    fn should_reject_huge_headers() {
+        let result: Result<Picture, _> = "P1 99999999999 99999999999 1".parse();
+        assert_eq!(
+            result,
+            Err(PictureError::TooLarge(99999999999, 99999999999))
+        );
+        let result: Result<Picture, _> = "P2 100000 100000 255 0".parse();
+        assert_eq!(result, Err(PictureError::UnexpectedEnd));
+    }
+}
@@ -19,6 +19,17 @@ pub enum CenterPlacement {
     Intersection(Rectangle),
 }
 
+impl CenterPlacement {
+    /// Returns the cells surrounding the center, which all must belong to the center's galaxy
+    pub fn positions(&self) -> Vec<Position> {
+        match self {
+            Center(p) => vec![*p],
+            VerticalBorder(border) | HorizontalBorder(border) => vec![border.p1(), border.p2()],
+            Intersection(rect) => rect.positions(),
+        }
+    }
+}
+
 impl Position {
     pub fn new(row: i32, column: i32) -> Position {
         Position { row, column }
@@ -0,0 +1,363 @@
+use crate::model::border::Border;
+use crate::model::grid::GridType;
+use crate::model::mask::Mask;
+use crate::model::objective::{CenterColor, GalaxyCenter, Objective};
+use crate::model::position::Position;
+use crate::model::solver::{Solver, STEPS_PER_CELL};
+use crate::model::state::State;
+use crate::model::topology::Surface;
+use std::collections::BTreeSet;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+/// The version of the format, the first field of every link
+const VERSION: &str = "1";
+
+/// The largest number of cells along each side of a linked board
+const MAX_SIDE: usize = 50;
+
+/// The characters of the URL safe base64 alphabet, each holding six bits
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+/// A puzzle, and optionally the walls that a player has drawn, written compactly enough
+/// to fit in the fragment of a URL, e.g.
+/// `1.square.plane.rotational.3x2..1,1_1,4..I`
+///
+/// The fields are separated by dots: the version, the grid, the topology, the symmetry,
+/// the size, the cells of the board (empty if every cell is part of it), the centers,
+/// the given walls and finally the drawn walls, which are left out when there are none.
+/// Each center is written in half-steps as `row,column`, followed by `:size` if the size is given,
+/// and by `b` if the center is black. The cells and walls are written as bits in base64,
+/// where the walls are the borders between the cells of the board, ordered as by [Border::cmp].
+#[derive(Clone)]
+pub struct PuzzleLink {
+    pub mask: Mask,
+    pub objective: Objective,
+    /// The walls drawn by the player, not including the given walls
+    pub walls: Vec<Border>,
+}
+
+impl PuzzleLink {
+    /// Creates a link to the puzzle of [state], with the walls drawn so far if [with_walls] is set
+    pub fn from_state(state: &State, with_walls: bool) -> PuzzleLink {
+        let walls = if with_walls {
+            state
+                .board
+                .get_borders()
+                .filter(|border| !state.objective.is_given_wall(border))
+                .collect()
+        } else {
+            Vec::new()
+        };
+        PuzzleLink {
+            mask: state.board.get_mask().clone(),
+            objective: state.objective.clone(),
+            walls,
+        }
+    }
+
+    /// Starts a game of the linked puzzle, with the linked walls drawn.
+    /// Fails if the puzzle doesn't fit its board, or has no solution that the solver finds in time.
+    pub fn to_state(&self) -> Result<State, String> {
+        let (width, height) = (self.mask.width(), self.mask.height());
+        self.objective
+            .validate(&self.mask)
+            .map_err(|e| e.to_string())?;
+        let solver = Solver::new(&self.mask, &self.objective)
+            .with_max_steps(STEPS_PER_CELL * width * height);
+        let universe = match solver.solve(1).into_iter().next() {
+            Some(universe) => universe,
+            None if solver.is_exhausted() => return Err("The puzzle is too hard to solve".into()),
+            None => return Err("The puzzle has no solution".into()),
+        };
+        let mut state = State::new(universe, self.objective.clone(), None);
+        let borders = self.borders();
+        for wall in &self.walls {
+            if !borders.contains(wall) {
+                return Err(format!("The wall {wall:?} is not on the board"));
+            }
+            state.board.add_wall(wall.p1(), wall.p2());
+        }
+        Ok(state)
+    }
+
+    fn surface(&self) -> Surface {
+        Surface::new(
+            self.objective.grid,
+            self.objective.topology,
+            self.mask.width(),
+            self.mask.height(),
+        )
+    }
+
+    /// Returns the borders between the cells of the board, in the order of the wall bits
+    fn borders(&self) -> BTreeSet<Border> {
+        let surface = self.surface();
+        self.mask
+            .positions()
+            .flat_map(|p1| surface.adjacent(&p1).into_iter().map(move |p2| (p1, p2)))
+            .filter(|(p1, p2)| p1 != p2 && self.mask.contains(p2))
+            .map(Border::from)
+            .collect()
+    }
+}
+
+impl Display for PuzzleLink {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let objective = &self.objective;
+        let cells = if self.mask.is_full() {
+            String::new()
+        } else {
+            let cells = (0..self.mask.height() as i32).flat_map(|row| {
+                (0..self.mask.width() as i32).map(move |column| Position::new(row, column))
+            });
+            encode_bits(cells.map(|p| self.mask.contains(&p)))
+        };
+        let centers: Vec<String> = objective.centers.iter().map(encode_center).collect();
+        let borders = self.borders();
+        let given_walls = encode_bits(borders.iter().map(|b| objective.walls.contains(b)));
+        write!(
+            f,
+            "{VERSION}.{}.{}.{}.{}x{}.{cells}.{}.{given_walls}",
+            objective.grid,
+            objective.topology,
+            objective.symmetry,
+            self.mask.width(),
+            self.mask.height(),
+            centers.join("_"),
+        )?;
+        if !self.walls.is_empty() {
+            let walls = encode_bits(borders.iter().map(|b| self.walls.contains(b)));
+            write!(f, ".{walls}")?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for PuzzleLink {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let fields: Vec<&str> = s.split('.').collect();
+        let (version, fields) = fields.split_first().ok_or("Empty link")?;
+        if *version != VERSION {
+            return Err(format!("Unknown link version: {version}"));
+        }
+        let (given_walls, walls) = match fields {
+            [_, _, _, _, _, _, given_walls] => (*given_walls, ""),
+            [_, _, _, _, _, _, given_walls, walls] => (*given_walls, *walls),
+            _ => return Err(format!("Expected 8 or 9 fields: {s}")),
+        };
+        let grid: GridType = fields[0].parse()?;
+        let topology = fields[1].parse()?;
+        let symmetry = fields[2].parse()?;
+        if !grid.supports(symmetry) {
+            return Err(format!("{grid} grids don't support {symmetry} symmetry"));
+        }
+        if !grid.supports_topology(topology) {
+            return Err(format!(
+                "{grid} grids don't support the {topology} topology"
+            ));
+        }
+        let (width, height) = fields[3]
+            .split_once('x')
+            .and_then(|(w, h)| Some((w.parse().ok()?, h.parse().ok()?)))
+            .ok_or_else(|| format!("Invalid size: {}", fields[3]))?;
+        if !(1..=MAX_SIDE).contains(&width) || !(1..=MAX_SIDE).contains(&height) {
+            return Err(format!(
+                "The sides must have 1 to {MAX_SIDE} cells: {}",
+                fields[3]
+            ));
+        }
+        let cells = width
+            .checked_mul(height)
+            .ok_or_else(|| format!("Invalid size: {}", fields[3]))?;
+        let mask = if fields[4].is_empty() {
+            Mask::full(width, height)
+        } else {
+            Mask::from_cells(width, height, decode_bits(fields[4], cells)?)
+        };
+        let centers = match fields[5] {
+            "" => Vec::new(),
+            centers => centers
+                .split('_')
+                .map(decode_center)
+                .collect::<Result<_, _>>()?,
+        };
+        let mut link = PuzzleLink {
+            mask,
+            objective: Objective {
+                centers,
+                walls: Vec::new(),
+                symmetry,
+                topology,
+                grid,
+            },
+            walls: Vec::new(),
+        };
+        let borders = link.borders();
+        let select = |bits: Vec<bool>| -> Vec<Border> {
+            borders
+                .iter()
+                .zip(bits)
+                .filter(|(_, bit)| *bit)
+                .map(|(border, _)| *border)
+                .collect()
+        };
+        link.objective.walls = select(decode_bits(given_walls, borders.len())?);
+        link.walls = select(decode_bits(walls, borders.len())?);
+        Ok(link)
+    }
+}
+
+fn encode_center(center: &GalaxyCenter) -> String {
+    let mut code = format!("{},{}", center.position.row, center.position.column);
+    if let Some(size) = center.size {
+        code.push_str(&format!(":{size}"));
+    }
+    if center.color == CenterColor::Black {
+        code.push('b');
+    }
+    code
+}
+
+fn decode_center(code: &str) -> Result<GalaxyCenter, String> {
+    let error = || format!("Invalid center: {code}");
+    let (code, color) = match code.strip_suffix('b') {
+        Some(code) => (code, CenterColor::Black),
+        None => (code, CenterColor::White),
+    };
+    let (position, size) = match code.split_once(':') {
+        Some((position, size)) => (position, Some(size.parse().map_err(|_| error())?)),
+        None => (code, None),
+    };
+    let (row, column) = position.split_once(',').ok_or_else(error)?;
+    Ok(GalaxyCenter {
+        position: Position::new(
+            row.parse().map_err(|_| error())?,
+            column.parse().map_err(|_| error())?,
+        ),
+        size,
+        color,
+    })
+}
+
+/// Writes the bits six at a time, leaving out the trailing characters with no bits set
+fn encode_bits(bits: impl Iterator<Item = bool>) -> String {
+    let bits: Vec<bool> = bits.collect();
+    let code: String = bits
+        .chunks(6)
+        .map(|chunk| {
+            let value = chunk
+                .iter()
+                .enumerate()
+                .fold(0, |value, (i, &bit)| value | (usize::from(bit) << (5 - i)));
+            BASE64[value] as char
+        })
+        .collect();
+    code.trim_end_matches('A').to_string()
+}
+
+/// Reads [count] bits written by [encode_bits]
+fn decode_bits(code: &str, count: usize) -> Result<Vec<bool>, String> {
+    if code.len() > count.div_ceil(6) {
+        return Err(format!("Too many bits: {code}"));
+    }
+    let mut bits = Vec::with_capacity(count);
+    for c in code.bytes() {
+        let value = BASE64
+            .iter()
+            .position(|&b| b == c)
+            .ok_or_else(|| format!("Invalid character in {code}"))?;
+        bits.extend((0..6).map(|i| value & (1 << (5 - i)) != 0));
+    }
+    if bits.iter().skip(count).any(|&bit| bit) {
+        return Err(format!("Too many bits: {code}"));
+    }
+    bits.resize(count, false);
+    Ok(bits)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::border::Border;
+    use crate::model::grid::GridType;
+    use crate::model::mask::Mask;
+    use crate::model::position::Position;
+    use crate::model::puzzle_link::PuzzleLink;
+    use crate::model::state::State;
+    use crate::model::topology::Topology;
+
+    #[test]
+    fn link_should_describe_the_same_puzzle() {
+        let mut state = State::generate(5);
+        state.hint();
+        let link = PuzzleLink::from_state(&state, true);
+        let parsed: PuzzleLink = link.to_string().parse().unwrap();
+        assert_eq!(parsed.to_string(), link.to_string());
+        let linked = parsed.to_state().unwrap();
+        assert_eq!(linked.universe.get_walls(), state.universe.get_walls());
+        assert_eq!(
+            linked.board.get_borders().collect::<Vec<_>>(),
+            state.board.get_borders().collect::<Vec<_>>()
+        );
+        assert_eq!(linked.seed, None);
+    }
+
+    #[test]
+    fn link_should_keep_the_shape_and_topology() {
+        let mask = Mask::full(4, 4).without(&Position::new(0, 0));
+        let state =
+            State::generate_with_mask(mask.clone(), Topology::Torus, GridType::Square).unwrap();
+        let link: PuzzleLink = PuzzleLink::from_state(&state, false)
+            .to_string()
+            .parse()
+            .unwrap();
+        assert_eq!(link.mask, mask);
+        assert_eq!(link.objective.topology, Topology::Torus);
+        assert!(link.walls.is_empty());
+    }
+
+    #[test]
+    fn should_parse_documented_example() {
+        let link: PuzzleLink = "1.square.plane.rotational.3x2..1,1_1,4..I".parse().unwrap();
+        assert_eq!(link.objective.centers.len(), 2);
+        assert_eq!(
+            link.walls,
+            vec![Border::new(Position::new(0, 1), Position::new(0, 2))]
+        );
+        assert!(link.to_state().is_ok());
+    }
+
+    #[test]
+    fn malformed_links_should_be_rejected() {
+        assert!("".parse::<PuzzleLink>().is_err());
+        assert!("2.square.plane.rotational.3x2..1,1.."
+            .parse::<PuzzleLink>()
+            .is_err());
+        assert!("1.square.plane.rotational.3x2..1;1.."
+            .parse::<PuzzleLink>()
+            .is_err());
+        assert!("1.square.plane.rotational.3x2..1,1..____"
+            .parse::<PuzzleLink>()
+            .is_err());
+        assert!("1.hexagonal.torus.rotational.2x1..0,1."
+            .parse::<PuzzleLink>()
+            .is_err());
+        assert!("1.triangular.plane.four-fold.2x1..0,1."
+            .parse::<PuzzleLink>()
+            .is_err());
+        for size in [
+            "99999999999x99999999999",
+            "100000x100000",
+            "5000x5000",
+            "0x0",
+            "3x0",
+        ] {
+            let link = format!("1.square.plane.rotational.{size}...");
+            assert!(link.parse::<PuzzleLink>().is_err(), "{size}");
+        }
+        let unsolvable: PuzzleLink = "1.square.plane.rotational.3x2..0,1..".parse().unwrap();
+        assert!(unsolvable.to_state().is_err());
+    }
+}
@@ -0,0 +1,152 @@
+use crate::model::date::Date;
+use crate::model::game_stats::GameStats;
+use crate::model::puzzle_link::PuzzleLink;
+use crate::model::state::State;
+use crate::model::storage::Storage;
+use crate::model::timer::Timer;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+/// The key that the game in progress is stored under
+const STORAGE_KEY: &str = "vintergatan.game";
+
+/// A game in progress, kept in storage so that it can be continued after the page is reloaded,
+/// with the time spent on it and what the player did so far
+#[derive(Clone)]
+pub struct SavedGame {
+    /// The puzzle with the walls drawn so far
+    pub link: PuzzleLink,
+    /// The milliseconds spent on the puzzle
+    pub elapsed: f64,
+    pub stats: GameStats,
+    /// The date of the daily puzzle, if this is one
+    pub daily: Option<Date>,
+}
+
+impl SavedGame {
+    /// Saves the game of [state] as it is at [now]
+    pub fn from_state(state: &State, now: f64) -> SavedGame {
+        SavedGame {
+            link: PuzzleLink::from_state(state, true),
+            elapsed: state.timer.elapsed(now),
+            stats: state.stats,
+            daily: state.daily,
+        }
+    }
+
+    /// Continues the saved game, with its timer paused
+    pub fn to_state(&self) -> Result<State, String> {
+        let mut state = self.link.to_state()?;
+        state.timer = Timer::with_elapsed(self.elapsed);
+        state.stats = self.stats;
+        state.daily = self.daily;
+        Ok(state)
+    }
+
+    /// Reads the saved game from [storage], if there is one that can be parsed
+    pub fn load(storage: &impl Storage) -> Option<SavedGame> {
+        storage.get(STORAGE_KEY)?.parse().ok()
+    }
+
+    pub fn save(&self, storage: &mut impl Storage) {
+        storage.set(STORAGE_KEY, &self.to_string());
+    }
+
+    /// Forgets the saved game, e.g. once it's completed
+    pub fn clear(storage: &mut impl Storage) {
+        storage.set(STORAGE_KEY, "");
+    }
+}
+
+impl Display for SavedGame {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let stats = &self.stats;
+        write!(
+            f,
+            "{} {} {} {} {} {} {} {}",
+            self.elapsed,
+            stats.wall_toggles,
+            stats.undos,
+            stats.redos,
+            stats.checks,
+            stats.hints,
+            self.daily.map_or("-".to_string(), |date| date.to_string()),
+            self.link
+        )
+    }
+}
+
+impl FromStr for SavedGame {
+    type Err = String;
+
+    /// Parses a saved game in the format written by [Display]
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = || format!("Invalid saved game: {s}");
+        let parts: Vec<&str> = s.split_whitespace().collect();
+        let [elapsed, wall_toggles, undos, redos, checks, hints, daily, link] = parts.as_slice()
+        else {
+            return Err(error());
+        };
+        let count = |value: &str| value.parse::<usize>().map_err(|_| error());
+        Ok(SavedGame {
+            link: link.parse()?,
+            elapsed: elapsed.parse().map_err(|_| error())?,
+            stats: GameStats {
+                wall_toggles: count(wall_toggles)?,
+                undos: count(undos)?,
+                redos: count(redos)?,
+                checks: count(checks)?,
+                hints: count(hints)?,
+            },
+            daily: match *daily {
+                "-" => None,
+                daily => Some(daily.parse()?),
+            },
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::date::Date;
+    use crate::model::grid::GridType;
+    use crate::model::mask::Mask;
+    use crate::model::saved_game::SavedGame;
+    use crate::model::state::State;
+    use crate::model::storage::{MemoryStorage, Storage};
+    use crate::model::topology::Topology;
+
+    #[test]
+    fn saved_game_should_continue_where_it_was_left() {
+        let mut state =
+            State::generate_with_seed(Mask::full(5, 5), Topology::default(), GridType::Square, 42)
+                .unwrap();
+        state.daily = Some(Date::new(2024, 5, 1));
+        state.resume(1000.0);
+        let border = state.hint().unwrap();
+        state.undo();
+        state.redo();
+        state.check(2000.0);
+
+        let mut storage = MemoryStorage::default();
+        SavedGame::from_state(&state, 61_000.0).save(&mut storage);
+        let restored = SavedGame::load(&storage).unwrap().to_state().unwrap();
+
+        assert_eq!(restored.timer.elapsed(100_000.0), 60_000.0);
+        assert!(!restored.timer.is_running());
+        assert_eq!(restored.stats, state.stats);
+        assert_eq!(restored.stats.redos, 1);
+        assert_eq!(restored.daily, state.daily);
+        assert!(restored.board.get_borders().any(|b| b == border));
+    }
+
+    #[test]
+    fn cleared_or_malformed_games_should_not_be_loaded() {
+        let mut storage = MemoryStorage::default();
+        assert!(SavedGame::load(&storage).is_none());
+        storage.set("vintergatan.game", "1000 0 0 0 0 0 - garbage");
+        assert!(SavedGame::load(&storage).is_none());
+        SavedGame::clear(&mut storage);
+        assert!(SavedGame::load(&storage).is_none());
+    }
+}
@@ -0,0 +1,31 @@
+use crate::model::border::Border;
+use crate::model::position::Position;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+
+/// Why the walls of a [crate::model::board::Board] don't divide it into galaxies
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum SolutionError {
+    /// The wall has the same galaxy on both sides, so it doesn't separate galaxies
+    InnerWall(Border),
+    /// The region containing the position isn't a valid galaxy, it isn't symmetric around its center
+    InvalidGalaxy(Position),
+}
+
+impl Display for SolutionError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SolutionError::InnerWall(border) => write!(
+                f,
+                "The wall between {} and {} is inside a galaxy",
+                border.p1(),
+                border.p2()
+            ),
+            SolutionError::InvalidGalaxy(position) => {
+                write!(f, "The region containing {position} is not a valid galaxy")
+            }
+        }
+    }
+}
+
+impl Error for SolutionError {}
@@ -0,0 +1,600 @@
+use crate::model::border::Border;
+use crate::model::galaxy::Galaxy;
+use crate::model::grid::GridType;
+use crate::model::mask::Mask;
+use crate::model::objective::Objective;
+use crate::model::position::Position;
+use crate::model::symmetry::Symmetry;
+use crate::model::topology::Surface;
+use crate::model::universe::Universe;
+use std::cell::Cell;
+use std::cmp::{max, min};
+use std::collections::{HashSet, VecDeque};
+
+/// How many assignments per cell of the board the search may step through when generating
+/// or starting a puzzle, see [Solver::with_max_steps]. The search of some generated puzzles
+/// of 16 by 16 cells and more runs out of steps without any given walls, in which case
+/// walls of the solution are given, see [Objective::generate].
+pub const STEPS_PER_CELL: usize = 4;
+
+/// Finds the universes that satisfy an [Objective], i.e. partitions of the board into galaxies
+/// such that every galaxy is symmetric around exactly one of the objective's centers,
+/// has the requested size (if any), and does not cross any of the given walls.
+pub struct Solver {
+    mask: Mask,
+    centers: Vec<Position>,
+    sizes: Vec<Option<usize>>,
+    walls: HashSet<Border>,
+    symmetry: Symmetry,
+    surface: Surface,
+    /// The open adjacent cells of each cell, by index, see [Solver::open_adjacent]
+    open_adjacent: Vec<Vec<usize>>,
+    /// The number of times the search has branched on a cell with several candidate galaxies
+    guesses: Cell<usize>,
+    /// The number of assignments the search has stepped through
+    steps: Cell<usize>,
+    /// How many assignments the search may step through before it gives up
+    max_steps: usize,
+}
+
+type Assignment = Vec<Option<usize>>;
+
+/// What the search makes of a partial assignment
+enum Step {
+    /// The assignment can't be completed into a solution
+    DeadEnd,
+    /// Every cell is assigned, and the galaxies satisfy the objective
+    Solution,
+    /// The assignments with one more cell assigned that the search continues with, in order
+    Branches(Vec<Assignment>),
+}
+
+/// The solutions of an objective, found by a depth-first search that continues
+/// where it left off every time the next solution is requested
+pub struct Solutions<'a> {
+    solver: &'a Solver,
+    /// The assignments that remain to be searched, the last one first
+    stack: Vec<Assignment>,
+}
+
+impl Iterator for Solutions<'_> {
+    type Item = Universe;
+
+    fn next(&mut self) -> Option<Universe> {
+        while let Some(assignment) = self.stack.pop() {
+            let steps = &self.solver.steps;
+            steps.set(steps.get() + 1);
+            if self.solver.is_exhausted() {
+                self.stack.clear();
+                return None;
+            }
+            match self.solver.step(&assignment) {
+                Step::DeadEnd => {}
+                Step::Solution => return Some(self.solver.to_universe(&assignment)),
+                Step::Branches(branches) => self.stack.extend(branches.into_iter().rev()),
+            }
+        }
+        None
+    }
+}
+
+/// The number of cells and bounding box of a set of cells
+struct Bounds {
+    count: usize,
+    min: Position,
+    max: Position,
+}
+
+impl Default for Bounds {
+    fn default() -> Self {
+        Bounds {
+            count: 0,
+            min: Position::new(i32::MAX, i32::MAX),
+            max: Position::new(i32::MIN, i32::MIN),
+        }
+    }
+}
+
+impl Bounds {
+    fn add(&mut self, p: &Position) {
+        self.count += 1;
+        self.min = Position::new(min(self.min.row, p.row), min(self.min.column, p.column));
+        self.max = Position::new(max(self.max.row, p.row), max(self.max.column, p.column));
+    }
+
+    /// Returns whether some bounding box containing these bounds, but contained within [outer],
+    /// can have its center at [center], given in half-steps
+    fn can_be_centered_at(&self, outer: &Bounds, center: &Position) -> bool {
+        outer.min.row + self.max.row <= center.row
+            && center.row <= self.min.row + outer.max.row
+            && outer.min.column + self.max.column <= center.column
+            && center.column <= self.min.column + outer.max.column
+    }
+}
+
+impl Solver {
+    pub fn new(mask: &Mask, objective: &Objective) -> Solver {
+        let walls = objective.walls.iter().copied().collect();
+        let surface = Surface::new(
+            objective.grid,
+            objective.topology,
+            mask.width(),
+            mask.height(),
+        );
+        Solver {
+            mask: mask.clone(),
+            centers: objective.centers.iter().map(|gc| gc.position).collect(),
+            sizes: objective.centers.iter().map(|gc| gc.size).collect(),
+            open_adjacent: Self::open_adjacent(mask, &surface, &walls),
+            walls,
+            symmetry: objective.symmetry,
+            surface,
+            guesses: Cell::new(0),
+            steps: Cell::new(0),
+            max_steps: usize::MAX,
+        }
+    }
+
+    /// Limits the search to [max_steps] assignments, counted over every search of this solver.
+    /// Once they're used up the search stops as if there were no more solutions,
+    /// see [Solver::is_exhausted].
+    pub fn with_max_steps(self, max_steps: usize) -> Solver {
+        Solver { max_steps, ..self }
+    }
+
+    /// Returns whether the search gave up because it used up its steps,
+    /// in which case there may be more solutions than were found
+    pub fn is_exhausted(&self) -> bool {
+        self.steps.get() > self.max_steps
+    }
+
+    /// Returns at most [limit] distinct solutions of the objective
+    pub fn solve(&self, limit: usize) -> Vec<Universe> {
+        self.solutions().take(limit).collect()
+    }
+
+    /// Returns the solutions of the objective, which are searched for as they're iterated over
+    pub fn solutions(&self) -> Solutions<'_> {
+        Solutions {
+            solver: self,
+            stack: self.initial_assignment().into_iter().collect(),
+        }
+    }
+
+    /// Returns how many solutions the objective has, counting no further than [limit]
+    pub fn count_solutions(&self, limit: usize) -> usize {
+        self.solutions().take(limit).count()
+    }
+
+    /// Returns how many times the search has had to guess which galaxy a cell belongs to,
+    /// counted over every call to [Solver::solve]
+    pub fn guesses(&self) -> usize {
+        self.guesses.get()
+    }
+
+    /// Returns true iff the objective has exactly one solution
+    pub fn is_unique(&self) -> bool {
+        self.solve(2).len() == 1
+    }
+
+    fn index(&self, p: &Position) -> usize {
+        p.row as usize * self.mask.width() + p.column as usize
+    }
+
+    fn position(&self, index: usize) -> Position {
+        Position::from((index / self.mask.width(), index % self.mask.width()))
+    }
+
+    fn contains(&self, p: &Position) -> bool {
+        self.mask.contains(p)
+    }
+
+    fn mirrors(&self, galaxy: usize, p: &Position) -> Vec<Position> {
+        self.surface.images(self.symmetry, &self.centers[galaxy], p)
+    }
+
+    /// Returns the indices of the cells adjacent to each cell of the bounding rectangle that are
+    /// inside the board and not separated from it by a given wall, which never change during the search
+    fn open_adjacent(mask: &Mask, surface: &Surface, walls: &HashSet<Border>) -> Vec<Vec<usize>> {
+        (0..mask.height())
+            .flat_map(|row| (0..mask.width()).map(move |column| Position::from((row, column))))
+            .map(|p| {
+                surface
+                    .adjacent(&p)
+                    .into_iter()
+                    .filter(|q| mask.contains(q) && !walls.contains(&Border::new(p, *q)))
+                    .map(|q| q.row as usize * mask.width() + q.column as usize)
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Assigns all cells surrounding each center to that center's galaxy
+    fn initial_assignment(&self) -> Option<Assignment> {
+        let mut assignment = vec![None; self.mask.width() * self.mask.height()];
+        for (galaxy, center) in self.centers.iter().enumerate() {
+            if !self.surface.allows_center(self.symmetry, center) {
+                return None;
+            }
+            for p in self.surface.center_positions(center) {
+                if !self.assign(&mut assignment, galaxy, &p) {
+                    return None;
+                }
+            }
+        }
+        Some(assignment)
+    }
+
+    /// Assigns [p] and its mirrors to [galaxy], returns false if that violates any constraint
+    fn assign(&self, assignment: &mut Assignment, galaxy: usize, p: &Position) -> bool {
+        for q in [*p].into_iter().chain(self.mirrors(galaxy, p)) {
+            if !self.contains(&q) {
+                return false;
+            }
+            let index = self.index(&q);
+            match assignment[index] {
+                Some(other) if other != galaxy => return false,
+                Some(_) => continue,
+                None => {}
+            }
+            let crosses_wall = self.surface.adjacent(&q).iter().any(|adjacent| {
+                self.contains(adjacent)
+                    && assignment[self.index(adjacent)] == Some(galaxy)
+                    && self.walls.contains(&Border::new(q, *adjacent))
+            });
+            if crosses_wall {
+                return false;
+            }
+            assignment[index] = Some(galaxy);
+        }
+        true
+    }
+
+    /// Returns whether [p] could still be added to [galaxy]
+    fn is_candidate(&self, assignment: &Assignment, galaxy: usize, p: &Position) -> bool {
+        match assignment[self.index(p)] {
+            Some(other) => other == galaxy,
+            None => self.mirrors(galaxy, p).iter().all(|mirror| {
+                self.contains(mirror)
+                    && assignment[self.index(mirror)].is_none_or(|other| other == galaxy)
+            }),
+        }
+    }
+
+    /// Returns the cells that can be reached from the center of [galaxy]
+    /// by only walking through cells that are, or could be, part of that galaxy.
+    fn reachable(&self, assignment: &Assignment, galaxy: usize) -> Vec<bool> {
+        let mut reachable = vec![false; assignment.len()];
+        let mut queue: VecDeque<usize> = (0..assignment.len())
+            .filter(|&index| assignment[index] == Some(galaxy))
+            .take(1)
+            .collect();
+        for &index in &queue {
+            reachable[index] = true;
+        }
+        while let Some(index) = queue.pop_front() {
+            for &adjacent in &self.open_adjacent[index] {
+                if !reachable[adjacent]
+                    && self.is_candidate(assignment, galaxy, &self.position(adjacent))
+                {
+                    reachable[adjacent] = true;
+                    queue.push_back(adjacent);
+                }
+            }
+        }
+        reachable
+    }
+
+    /// Decides whether [assignment] is a solution, can't be completed,
+    /// or which assignments the search continues with
+    fn step(&self, assignment: &Assignment) -> Step {
+        let reachable: Vec<Vec<bool>> = (0..self.centers.len())
+            .map(|galaxy| self.reachable(assignment, galaxy))
+            .collect();
+
+        for (galaxy, reachable) in reachable.iter().enumerate() {
+            let mut assigned = Bounds::default();
+            let mut potential = Bounds::default();
+            for (index, &galaxy_of_cell) in assignment.iter().enumerate() {
+                if galaxy_of_cell == Some(galaxy) {
+                    if !reachable[index] {
+                        // The galaxy can no longer be connected
+                        return Step::DeadEnd;
+                    }
+                    assigned.add(&self.position(index));
+                }
+                if reachable[index] {
+                    potential.add(&self.position(index));
+                }
+            }
+            if let Some(size) = self.sizes[galaxy] {
+                if assigned.count > size || potential.count < size {
+                    return Step::DeadEnd;
+                }
+            }
+            // Bounding boxes don't say much about galaxies that can span wrapped edges,
+            // and the center is only the middle of the bounding box of the cells on square grids
+            if self.surface.is_plane()
+                && self.surface.grid_type() == GridType::Square
+                && !assigned.can_be_centered_at(&potential, &self.centers[galaxy])
+            {
+                return Step::DeadEnd;
+            }
+        }
+
+        // Assign every cell that only one galaxy can reach, or else branch on the unassigned cell
+        // with the fewest candidate galaxies
+        let mut best: Option<(usize, Vec<usize>)> = None;
+        let mut forced: Vec<(usize, usize)> = Vec::new();
+        let unassigned = (0..assignment.len())
+            .filter(|&index| assignment[index].is_none() && self.contains(&self.position(index)));
+        for index in unassigned {
+            let candidates: Vec<usize> = (0..self.centers.len())
+                .filter(|&galaxy| reachable[galaxy][index])
+                .collect();
+            match candidates.as_slice() {
+                [] => return Step::DeadEnd,
+                &[galaxy] => forced.push((index, galaxy)),
+                _ if best
+                    .as_ref()
+                    .is_none_or(|(_, best)| candidates.len() < best.len()) =>
+                {
+                    best = Some((index, candidates));
+                }
+                _ => {}
+            }
+        }
+        if !forced.is_empty() {
+            let mut next = assignment.clone();
+            for (index, galaxy) in forced {
+                if !self.assign(&mut next, galaxy, &self.position(index)) {
+                    return Step::DeadEnd;
+                }
+            }
+            return Step::Branches(vec![next]);
+        }
+
+        match best {
+            None => {
+                // Mirror symmetry only determines the center along one axis, and the images across
+                // wrapped edges assume small galaxies, so the center and symmetry need to be checked
+                let is_solution =
+                    self.galaxies(assignment)
+                        .iter()
+                        .enumerate()
+                        .all(|(galaxy, positions)| {
+                            self.surface.center(positions) == Some(self.centers[galaxy])
+                                && self.surface.is_valid(positions, self.symmetry)
+                                && self.sizes[galaxy].is_none_or(|size| positions.size() == size)
+                        });
+                if is_solution {
+                    Step::Solution
+                } else {
+                    Step::DeadEnd
+                }
+            }
+            Some((index, candidates)) => {
+                if candidates.len() > 1 {
+                    self.guesses.set(self.guesses.get() + 1);
+                }
+                let p = self.position(index);
+                Step::Branches(
+                    candidates
+                        .into_iter()
+                        .filter_map(|galaxy| {
+                            let mut next = assignment.clone();
+                            self.assign(&mut next, galaxy, &p).then_some(next)
+                        })
+                        .collect(),
+                )
+            }
+        }
+    }
+
+    /// Returns the galaxy of each center, in the same order as the centers
+    fn galaxies(&self, assignment: &Assignment) -> Vec<Galaxy> {
+        (0..self.centers.len())
+            .map(|galaxy| {
+                Galaxy::from_positions(
+                    (0..assignment.len())
+                        .filter(|&index| assignment[index] == Some(galaxy))
+                        .map(|index| self.position(index)),
+                )
+            })
+            .collect()
+    }
+
+    fn to_universe(&self, assignment: &Assignment) -> Universe {
+        let mut universe = Universe::new_with_mask(
+            self.mask.clone(),
+            self.symmetry,
+            self.surface.topology(),
+            self.surface.grid_type(),
+        );
+        for galaxy in self.galaxies(assignment) {
+            universe.add_galaxy(&galaxy);
+        }
+        universe
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::border::Border;
+    use crate::model::grid::GridType;
+    use crate::model::mask::Mask;
+    use crate::model::objective::{CenterColor, GalaxyCenter, Objective};
+    use crate::model::position::Position;
+    use crate::model::solver::Solver;
+    use crate::model::symmetry::Symmetry;
+    use crate::model::topology::Topology;
+    use crate::model::universe::Universe;
+
+    fn objective(centers: &[(i32, i32)], walls: &[Border]) -> Objective {
+        Objective {
+            centers: centers
+                .iter()
+                .map(|&(row, column)| GalaxyCenter {
+                    position: Position::new(row, column),
+                    size: None,
+                    color: CenterColor::White,
+                })
+                .collect(),
+            walls: walls.to_vec(),
+            symmetry: Symmetry::default(),
+            topology: Topology::default(),
+            grid: GridType::default(),
+        }
+    }
+
+    #[test]
+    fn single_center_should_have_single_solution() {
+        let solutions = Solver::new(&Mask::full(3, 3), &objective(&[(2, 2)], &[])).solve(10);
+        assert_eq!(solutions.len(), 1);
+        assert_eq!(solutions[0].get_galaxies().len(), 1);
+    }
+
+    #[test]
+    fn off_center_single_center_should_have_no_solution() {
+        let solutions = Solver::new(&Mask::full(2, 2), &objective(&[(0, 0)], &[])).solve(10);
+        assert!(solutions.is_empty());
+    }
+
+    #[test]
+    fn two_rows_should_have_unique_solution() {
+        let solver = Solver::new(&Mask::full(2, 2), &objective(&[(0, 1), (2, 1)], &[]));
+        assert!(solver.is_unique());
+    }
+
+    /// The corners can either belong to the rows or to the columns:
+    /// ```text
+    /// ┌─────┐    ┌─┬─┬─┐
+    /// ├─┬─┬─┤    │ ├─┤ │
+    /// ├─┴─┴─┤    │ ├─┤ │
+    /// └─────┘    └─┴─┴─┘
+    /// ```
+    #[test]
+    fn ambiguous_objective_should_be_resolved_by_wall() {
+        let centers = [(0, 2), (4, 2), (2, 0), (2, 4), (2, 2)];
+        let solutions = Solver::new(&Mask::full(3, 3), &objective(&centers, &[])).solve(10);
+        assert_eq!(solutions.len(), 2);
+
+        let solver = Solver::new(
+            &Mask::full(3, 3),
+            &objective(
+                &centers,
+                &[Border::new(Position::new(0, 0), Position::new(0, 1))],
+            ),
+        );
+        let solutions = solver.solve(10);
+        assert_eq!(solutions.len(), 1);
+        assert_eq!(solutions[0].get_galaxy(&Position::new(0, 0)).size(), 3);
+        assert!(solutions[0].are_neighbours(&Position::new(0, 0), &Position::new(1, 0)));
+    }
+
+    #[test]
+    fn mirror_symmetry_should_allow_t_shape() {
+        // A T-shape centered on the top middle cell, and the two bottom corners
+        let mut objective = objective(&[(1, 2), (2, 0), (2, 4)], &[]);
+        assert!(Solver::new(&Mask::full(3, 2), &objective)
+            .solve(10)
+            .is_empty());
+
+        objective.symmetry = Symmetry::VerticalAxis;
+        let solutions = Solver::new(&Mask::full(3, 2), &objective).solve(10);
+        assert_eq!(solutions.len(), 1);
+        assert_eq!(solutions[0].get_galaxy(&Position::new(1, 1)).size(), 4);
+    }
+
+    #[test]
+    fn solve_should_respect_limit() {
+        let centers = [(0, 2), (4, 2), (2, 0), (2, 4), (2, 2)];
+        let solutions = Solver::new(&Mask::full(3, 3), &objective(&centers, &[])).solve(1);
+        assert_eq!(solutions.len(), 1);
+    }
+
+    #[test]
+    fn search_should_give_up_when_its_steps_are_used_up() {
+        let centers = [(0, 2), (4, 2), (2, 0), (2, 4), (2, 2)];
+        let solver = Solver::new(&Mask::full(3, 3), &objective(&centers, &[])).with_max_steps(3);
+        assert!(solver.solve(10).len() < 2);
+        assert!(solver.is_exhausted());
+
+        let solver = Solver::new(&Mask::full(3, 3), &objective(&centers, &[])).with_max_steps(100);
+        assert_eq!(solver.solve(10).len(), 2);
+        assert!(!solver.is_exhausted());
+    }
+
+    #[test]
+    fn solutions_should_be_found_lazily() {
+        let centers = [(0, 2), (4, 2), (2, 0), (2, 4), (2, 2)];
+        let solver = Solver::new(&Mask::full(3, 3), &objective(&centers, &[]));
+        let mut solutions = solver.solutions();
+        let first = solutions.next().unwrap();
+        let second = solutions.next().unwrap();
+        assert!(solutions.next().is_none());
+        assert_eq!(solver.count_solutions(10), 2);
+        assert_eq!(solver.count_solutions(1), 1);
+        // The rows and columns disagree on the walls beside the four corners
+        assert_eq!(first.wall_differences(&second).len(), 8);
+    }
+
+    #[test]
+    fn generated_universe_should_be_a_solution_of_its_objective() {
+        let universe = Universe::generate(6, 6);
+        let objective = Objective::generate(&universe);
+        let solutions = Solver::new(&Mask::full(6, 6), &objective).solve(2);
+        assert_eq!(solutions.len(), 1);
+        assert_eq!(solutions[0].get_walls(), universe.get_walls());
+    }
+
+    #[test]
+    fn torus_universe_should_be_a_solution_of_its_objective() {
+        let mask = Mask::full(6, 6);
+        let universe = Universe::generate_with_mask(
+            mask.clone(),
+            Symmetry::default(),
+            Topology::Torus,
+            GridType::default(),
+        )
+        .unwrap();
+        let objective = Objective::generate(&universe);
+        let solutions = Solver::new(&mask, &objective).solve(2);
+        assert_eq!(solutions.len(), 1);
+        assert_eq!(solutions[0].get_walls(), universe.get_walls());
+    }
+
+    #[test]
+    fn masked_universe_should_be_a_solution_of_its_objective() {
+        let mask = Mask::heart(8).without(&Position::new(4, 3));
+        let universe = Universe::generate_with_mask(
+            mask.clone(),
+            Symmetry::default(),
+            Topology::default(),
+            GridType::default(),
+        )
+        .unwrap();
+        let objective = Objective::generate(&universe);
+        let solutions = Solver::new(&mask, &objective).solve(2);
+        assert_eq!(solutions.len(), 1);
+        assert_eq!(solutions[0].get_walls(), universe.get_walls());
+    }
+
+    #[test]
+    fn hexagonal_and_triangular_universes_should_be_solutions_of_their_objectives() {
+        let mask = Mask::full(6, 6);
+        for grid in [GridType::Hexagonal, GridType::Triangular] {
+            let universe = Universe::generate_with_mask(
+                mask.clone(),
+                Symmetry::default(),
+                Topology::default(),
+                grid,
+            )
+            .unwrap();
+            let objective = Objective::generate(&universe);
+            let solutions = Solver::new(&mask, &objective).solve(2);
+            assert_eq!(solutions.len(), 1, "{grid}\n{universe}");
+            assert_eq!(solutions[0].get_walls(), universe.get_walls());
+        }
+    }
+}
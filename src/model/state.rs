@@ -1,8 +1,21 @@
 use crate::model::board::Board;
 use crate::model::board_error::BoardError;
-use crate::model::history::History;
+use crate::model::border::Border;
+use crate::model::date::Date;
+use crate::model::difficulty::Difficulty;
+use crate::model::game_stats::{Completion, GameStats};
+use crate::model::generation_error::GenerationError;
+use crate::model::generator::GeneratorType;
+use crate::model::grid::GridType;
+use crate::model::history::{History, HistoryEntry};
+use crate::model::mask::Mask;
 use crate::model::objective::Objective;
+use crate::model::statistics::GameRecord;
+use crate::model::symmetry::Symmetry;
+use crate::model::timer::Timer;
+use crate::model::topology::Topology;
 use crate::model::universe::Universe;
+use rand::random;
 
 const GENERATE_SOLVED: bool = false;
 
@@ -12,28 +25,281 @@ pub struct State {
     pub objective: Objective,
     pub error: Option<BoardError>,
     pub history: History,
+    /// The seed that the universe was generated from, if it was generated
+    pub seed: Option<u64>,
+    pub difficulty: Difficulty,
+    /// The date of the daily puzzle, if this is one
+    pub daily: Option<Date>,
+    /// The time spent on the puzzle, the timer is paused while the game is paused or completed
+    pub timer: Timer,
+    pub stats: GameStats,
+    /// Set when a check finds the board error-free
+    pub completion: Option<Completion>,
 }
 
 impl State {
+    /// Generates a game of square cells, [size] must be positive
     pub fn generate(size: usize) -> State {
-        let universe = Universe::generate(size, size);
+        Self::generate_with_mask(
+            Mask::full(size, size),
+            Topology::default(),
+            GridType::default(),
+        )
+        .expect("Failed to generate a game of square cells")
+    }
+
+    pub fn generate_with_mask(
+        mask: Mask,
+        topology: Topology,
+        grid: GridType,
+    ) -> Result<State, GenerationError> {
+        Self::generate_with_seed(mask, topology, grid, random())
+    }
+
+    /// Generates a game like [State::generate_with_mask], where the same [seed] always
+    /// generates the same puzzle
+    pub fn generate_with_seed(
+        mask: Mask,
+        topology: Topology,
+        grid: GridType,
+        seed: u64,
+    ) -> Result<State, GenerationError> {
+        Self::generate_with_generator(mask, topology, grid, seed, GeneratorType::default())
+    }
+
+    /// Generates a game like [State::generate_with_seed], whose solution is generated by [generator]
+    pub fn generate_with_generator(
+        mask: Mask,
+        topology: Topology,
+        grid: GridType,
+        seed: u64,
+        generator: GeneratorType,
+    ) -> Result<State, GenerationError> {
+        let universe =
+            generator
+                .generator()
+                .generate(mask, Symmetry::default(), topology, grid, seed)?;
         let objective = Objective::generate(&universe);
-        let mut board = Board::new(size, size);
-        let error = Option::default();
-        let history = History::new();
+        let mut state = Self::new(universe, objective, Some(seed));
 
         if GENERATE_SOLVED {
-            for border in universe.get_galaxies().iter().flat_map(|g| g.get_borders()) {
-                board.add_wall(border.p1(), border.p2());
+            for border in state.universe.get_walls() {
+                state.board.add_wall(border.p1(), border.p2());
             }
         }
 
+        Ok(state)
+    }
+
+    /// Creates a new game of [objective], where [universe] is its solution
+    pub fn new(universe: Universe, objective: Objective, seed: Option<u64>) -> State {
+        let mask = universe.mask().clone();
+        let difficulty = Difficulty::rate(&mask, &objective);
+        let mut board = Board::new_with_mask(mask, universe.topology(), universe.grid_type());
+        let error = Option::default();
+        let history = History::new();
+
+        for wall in &objective.walls {
+            board.add_wall(wall.p1(), wall.p2());
+        }
+
         State {
             universe,
             board,
             objective,
             error,
             history,
+            seed,
+            difficulty,
+            daily: None,
+            timer: Timer::default(),
+            stats: GameStats::default(),
+            completion: None,
+        }
+    }
+
+    pub fn is_completed(&self) -> bool {
+        self.completion.is_some()
+    }
+
+    /// Returns the record of the completed game, played on [date]
+    pub fn record(&self, date: Date) -> Option<GameRecord> {
+        let completion = self.completion?;
+        Some(GameRecord {
+            size: self.board.get_width(),
+            difficulty: self.difficulty,
+            seed: self.seed,
+            time: completion.time,
+            hints: completion.stats.hints,
+            date,
+        })
+    }
+
+    /// Toggles the wall on [border], unless it's a given wall.
+    /// Returns whether the board changed.
+    pub fn toggle_wall(&mut self, border: Border) -> bool {
+        if self.objective.is_given_wall(&border) {
+            return false;
+        }
+        self.board.toggle_wall(border.p1(), border.p2());
+        self.error = None;
+        self.history.push(HistoryEntry::ToggleBorder(border));
+        self.stats.wall_toggles += 1;
+        true
+    }
+
+    /// Reverts the last move, returns whether there was a move to revert
+    pub fn undo(&mut self) -> bool {
+        let Some(entry) = self.history.undo() else {
+            return false;
+        };
+        match entry {
+            HistoryEntry::ToggleBorder(border) => {
+                self.board.toggle_wall(border.p1(), border.p2());
+            }
+        }
+        self.error = None;
+        self.stats.undos += 1;
+        true
+    }
+
+    /// Reapplies the last reverted move, returns whether there was a move to reapply
+    pub fn redo(&mut self) -> bool {
+        let Some(entry) = self.history.redo() else {
+            return false;
+        };
+        match entry {
+            HistoryEntry::ToggleBorder(border) => {
+                self.board.toggle_wall(border.p1(), border.p2());
+            }
+        }
+        self.error = None;
+        self.stats.redos += 1;
+        true
+    }
+
+    /// Computes the errors of the board. If there are none, the puzzle is completed
+    /// and the timer is stopped at [now].
+    pub fn check(&mut self, now: f64) {
+        let error = self.board.compute_error(&self.objective);
+        self.stats.checks += 1;
+        if error.is_error_free() && self.completion.is_none() {
+            self.timer.pause(now);
+            self.completion = Some(Completion {
+                time: self.timer.elapsed(now),
+                stats: self.stats,
+            });
+        }
+        self.error = Some(error);
+    }
+
+    /// Fixes one wall of the board, preferring to add a missing wall of the solution
+    /// over removing a wall that isn't part of it. Returns the toggled border,
+    /// or `None` if the board already matches the solution.
+    pub fn hint(&mut self) -> Option<Border> {
+        let solution = self.universe.get_walls();
+        let missing = solution
+            .iter()
+            .find(|border| !self.board.is_active(border))
+            .copied();
+        let border = missing.or_else(|| {
+            self.board
+                .get_borders()
+                .filter(|border| !solution.contains(border))
+                .min()
+        })?;
+        self.board.toggle_wall(border.p1(), border.p2());
+        self.error = None;
+        self.history.push(HistoryEntry::ToggleBorder(border));
+        self.stats.hints += 1;
+        Some(border)
+    }
+
+    /// Pauses the timer, a completed game stays paused
+    pub fn pause(&mut self, now: f64) {
+        self.timer.pause(now);
+    }
+
+    /// Starts or resumes the timer, unless the game is completed
+    pub fn resume(&mut self, now: f64) {
+        if !self.is_completed() {
+            self.timer.start(now);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::border::Border;
+    use crate::model::date::Date;
+    use crate::model::generator::GeneratorType;
+    use crate::model::grid::GridType;
+    use crate::model::mask::Mask;
+    use crate::model::position::Position;
+    use crate::model::solver::Solver;
+    use crate::model::state::State;
+    use crate::model::topology::Topology;
+
+    #[test]
+    fn given_walls_should_not_toggle() {
+        let mut state = State::generate(3);
+        let border = Border::new(Position::new(0, 0), Position::new(0, 1));
+        state.objective.walls.push(border);
+        assert!(!state.toggle_wall(border));
+        assert_eq!(state.stats.wall_toggles, 0);
+    }
+
+    #[test]
+    fn moves_should_be_counted() {
+        let mut state = State::generate(3);
+        let border = Border::new(Position::new(0, 0), Position::new(0, 1));
+        state.objective.walls.retain(|wall| wall != &border);
+        assert!(state.toggle_wall(border));
+        assert!(state.undo());
+        assert!(state.redo());
+        assert!(!state.redo());
+        assert_eq!(state.stats.wall_toggles, 1);
+        assert_eq!(state.stats.undos, 1);
+        assert_eq!(state.stats.redos, 1);
+    }
+
+    #[test]
+    fn solving_with_hints_should_complete_the_game() {
+        let mut state = State::generate(4);
+        state.resume(0.0);
+        while state.hint().is_some() {}
+        assert_eq!(
+            state.board.get_borders().count(),
+            state.universe.get_walls().len()
+        );
+        state.check(5000.0);
+        let completion = state.completion.expect("Hints should solve the puzzle");
+        assert_eq!(completion.time, 5000.0);
+        assert_eq!(completion.stats.checks, 1);
+        assert!(!state.timer.is_running());
+        let record = state.record(Date::new(2024, 5, 1)).unwrap();
+        assert_eq!(record.hints, state.stats.hints);
+        assert_eq!(record.seed, state.seed);
+        state.resume(6000.0);
+        assert!(!state.timer.is_running());
+    }
+
+    #[test]
+    fn every_generator_should_generate_unique_puzzles() {
+        for generator in GeneratorType::ALL {
+            let state = State::generate_with_generator(
+                Mask::full(5, 5),
+                Topology::default(),
+                GridType::default(),
+                11,
+                generator,
+            )
+            .unwrap();
+            let mask = state.universe.mask();
+            assert!(
+                Solver::new(mask, &state.objective).is_unique(),
+                "{generator}"
+            );
         }
     }
 }
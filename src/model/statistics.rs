@@ -0,0 +1,247 @@
+use crate::model::date::Date;
+use crate::model::difficulty::Difficulty;
+use crate::model::storage::Storage;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+/// The key that the records are stored under
+const STORAGE_KEY: &str = "vintergatan.records";
+
+/// A completed game
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct GameRecord {
+    pub size: usize,
+    pub difficulty: Difficulty,
+    /// The seed that the puzzle was generated from, if it was generated
+    pub seed: Option<u64>,
+    /// The milliseconds spent solving the puzzle
+    pub time: f64,
+    pub hints: usize,
+    pub date: Date,
+}
+
+impl Display for GameRecord {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} {} {} {} {} {}",
+            self.date,
+            self.size,
+            self.difficulty,
+            self.seed.map_or("-".to_string(), |seed| seed.to_string()),
+            self.time,
+            self.hints
+        )
+    }
+}
+
+impl FromStr for GameRecord {
+    type Err = String;
+
+    /// Parses a record in the format written by [Display]
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = || format!("Invalid record: {s}");
+        let parts: Vec<&str> = s.split_whitespace().collect();
+        let [date, size, difficulty, seed, time, hints] = parts.as_slice() else {
+            return Err(error());
+        };
+        Ok(GameRecord {
+            date: date.parse()?,
+            size: size.parse().map_err(|_| error())?,
+            difficulty: difficulty.parse()?,
+            seed: match *seed {
+                "-" => None,
+                seed => Some(seed.parse().map_err(|_| error())?),
+            },
+            time: time.parse().map_err(|_| error())?,
+            hints: hints.parse().map_err(|_| error())?,
+        })
+    }
+}
+
+/// The statistics of the games of one size and difficulty
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Summary {
+    pub size: usize,
+    pub difficulty: Difficulty,
+    pub games: usize,
+    pub average_time: f64,
+    pub best_time: f64,
+}
+
+/// The records of every completed game
+#[derive(Debug, Clone, Default)]
+pub struct Statistics {
+    records: Vec<GameRecord>,
+}
+
+impl Statistics {
+    /// Reads the records from [storage], skipping records that can't be parsed
+    pub fn load(storage: &impl Storage) -> Statistics {
+        let records = storage
+            .get(STORAGE_KEY)
+            .unwrap_or_default()
+            .lines()
+            .filter_map(|line| line.parse().ok())
+            .collect();
+        Statistics { records }
+    }
+
+    pub fn save(&self, storage: &mut impl Storage) {
+        let value: Vec<String> = self.records.iter().map(|r| r.to_string()).collect();
+        storage.set(STORAGE_KEY, &value.join("\n"));
+    }
+
+    pub fn records(&self) -> &[GameRecord] {
+        &self.records
+    }
+
+    pub fn add(&mut self, record: GameRecord) {
+        self.records.push(record);
+    }
+
+    /// Returns the fastest time of the games of [size] and [difficulty], if any
+    pub fn best_time(&self, size: usize, difficulty: Difficulty) -> Option<f64> {
+        self.records
+            .iter()
+            .filter(|r| r.size == size && r.difficulty == difficulty)
+            .map(|r| r.time)
+            .min_by(f64::total_cmp)
+    }
+
+    /// Returns a summary for every size and difficulty that has been played, ordered by size
+    pub fn summaries(&self) -> Vec<Summary> {
+        let mut times: BTreeMap<(usize, Difficulty), Vec<f64>> = BTreeMap::new();
+        for record in &self.records {
+            times
+                .entry((record.size, record.difficulty))
+                .or_default()
+                .push(record.time);
+        }
+        times
+            .into_iter()
+            .map(|((size, difficulty), times)| Summary {
+                size,
+                difficulty,
+                games: times.len(),
+                average_time: times.iter().sum::<f64>() / times.len() as f64,
+                best_time: times.iter().copied().fold(f64::INFINITY, f64::min),
+            })
+            .collect()
+    }
+
+    /// Returns the number of consecutive days with a completed game that end [today],
+    /// or yesterday if no game has been completed yet today
+    pub fn current_streak(&self, today: Date) -> usize {
+        let days = self.days();
+        let today = today.days();
+        let last = if days.contains(&today) {
+            today
+        } else {
+            today - 1
+        };
+        (0..)
+            .take_while(|offset| days.contains(&(last - offset)))
+            .count()
+    }
+
+    /// Returns the longest number of consecutive days with a completed game
+    pub fn longest_streak(&self) -> usize {
+        let mut longest = 0;
+        let mut current = 0;
+        let mut previous = None;
+        for day in self.days() {
+            current = if previous == Some(day - 1) {
+                current + 1
+            } else {
+                1
+            };
+            longest = longest.max(current);
+            previous = Some(day);
+        }
+        longest
+    }
+
+    /// Returns the days with a completed game, counted from the epoch
+    fn days(&self) -> BTreeSet<i64> {
+        self.records.iter().map(|r| r.date.days()).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::date::Date;
+    use crate::model::difficulty::Difficulty;
+    use crate::model::statistics::{GameRecord, Statistics};
+    use crate::model::storage::{MemoryStorage, Storage};
+
+    fn record(date: &str, size: usize, time: f64) -> GameRecord {
+        GameRecord {
+            size,
+            difficulty: Difficulty::Easy,
+            seed: Some(42),
+            time,
+            hints: 1,
+            date: date.parse().unwrap(),
+        }
+    }
+
+    #[test]
+    fn records_should_survive_storage() {
+        let mut statistics = Statistics::default();
+        statistics.add(record("2024-05-01", 10, 61_500.0));
+        statistics.add(GameRecord {
+            seed: None,
+            ..record("2024-05-02", 7, 30_000.0)
+        });
+        let mut storage = MemoryStorage::default();
+        statistics.save(&mut storage);
+        assert_eq!(Statistics::load(&storage).records(), statistics.records());
+    }
+
+    #[test]
+    fn malformed_records_should_be_skipped() {
+        let mut storage = MemoryStorage::default();
+        storage.set(
+            "vintergatan.records",
+            "garbage\n2024-05-01 10 easy 42 1000 0",
+        );
+        assert_eq!(Statistics::load(&storage).records().len(), 1);
+    }
+
+    #[test]
+    fn summaries_should_group_by_size() {
+        let mut statistics = Statistics::default();
+        statistics.add(record("2024-05-01", 10, 60_000.0));
+        statistics.add(record("2024-05-01", 10, 40_000.0));
+        statistics.add(record("2024-05-01", 5, 10_000.0));
+        let summaries = statistics.summaries();
+        assert_eq!(summaries.len(), 2);
+        assert_eq!(summaries[1].size, 10);
+        assert_eq!(summaries[1].games, 2);
+        assert_eq!(summaries[1].average_time, 50_000.0);
+        assert_eq!(summaries[1].best_time, 40_000.0);
+        assert_eq!(statistics.best_time(5, Difficulty::Easy), Some(10_000.0));
+        assert_eq!(statistics.best_time(5, Difficulty::Hard), None);
+    }
+
+    #[test]
+    fn streaks_should_count_consecutive_days() {
+        let mut statistics = Statistics::default();
+        for date in [
+            "2024-02-27",
+            "2024-02-28",
+            "2024-02-29",
+            "2024-03-01",
+            "2024-03-05",
+        ] {
+            statistics.add(record(date, 5, 1000.0));
+        }
+        statistics.add(record("2024-03-06", 5, 1000.0));
+        assert_eq!(statistics.longest_streak(), 4);
+        assert_eq!(statistics.current_streak(Date::new(2024, 3, 6)), 2);
+        assert_eq!(statistics.current_streak(Date::new(2024, 3, 7)), 2);
+        assert_eq!(statistics.current_streak(Date::new(2024, 3, 8)), 0);
+    }
+}
@@ -0,0 +1,23 @@
+use std::collections::HashMap;
+
+/// A key-value store for the data that outlives a game, like `localStorage` in the browser
+pub trait Storage {
+    fn get(&self, key: &str) -> Option<String>;
+    fn set(&mut self, key: &str, value: &str);
+}
+
+/// A [Storage] that only lives as long as the value, for tests and the command line
+#[derive(Debug, Clone, Default)]
+pub struct MemoryStorage {
+    values: HashMap<String, String>,
+}
+
+impl Storage for MemoryStorage {
+    fn get(&self, key: &str) -> Option<String> {
+        self.values.get(key).cloned()
+    }
+
+    fn set(&mut self, key: &str, value: &str) {
+        self.values.insert(key.to_string(), value.to_string());
+    }
+}
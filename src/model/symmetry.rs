@@ -0,0 +1,180 @@
+use crate::model::position::Position;
+use std::fmt::{Display, Formatter};
+use std::ops::Deref;
+use std::str::FromStr;
+
+/// The kind of symmetry that every galaxy must have around its center
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Default, Hash)]
+pub enum Symmetry {
+    /// Unchanged by a 180° rotation around the center, the classic rule
+    #[default]
+    Rotational,
+    /// Unchanged by a reflection across the horizontal line through the center
+    HorizontalAxis,
+    /// Unchanged by a reflection across the vertical line through the center
+    VerticalAxis,
+    /// Unchanged by a 90° rotation around the center
+    FourFold,
+}
+
+impl Symmetry {
+    pub const ALL: [Symmetry; 4] = [
+        Symmetry::Rotational,
+        Symmetry::HorizontalAxis,
+        Symmetry::VerticalAxis,
+        Symmetry::FourFold,
+    ];
+
+    /// Returns whether a galaxy with this symmetry can have its center at [center], given in half-steps.
+    /// A four-fold symmetric galaxy can't be centered on a border, since rotating a cell
+    /// 90° around it doesn't end up on a cell.
+    pub fn allows_center(&self, center: &Position) -> bool {
+        match self {
+            Symmetry::FourFold => (center.row - center.column) % 2 == 0,
+            _ => true,
+        }
+    }
+
+    /// Returns the positions that [p] is mapped to by the non-identity transformations of this symmetry,
+    /// around [center] given in half-steps. The result may contain [p] itself,
+    /// e.g. when [p] lies on the axis of reflection.
+    pub fn images(&self, center: &Position, p: &Position) -> Images {
+        let rotated_180 = Position::new(center.row - p.row, center.column - p.column);
+        match self {
+            Symmetry::Rotational => Images::new(&[rotated_180]),
+            Symmetry::HorizontalAxis => Images::new(&[Position::new(center.row - p.row, p.column)]),
+            Symmetry::VerticalAxis => {
+                Images::new(&[Position::new(p.row, center.column - p.column)])
+            }
+            Symmetry::FourFold => {
+                // Offsets from the center in half-steps
+                let delta_row = 2 * p.row - center.row;
+                let delta_column = 2 * p.column - center.column;
+                let rotated_90 = Position::new(
+                    (center.row + delta_column).div_euclid(2),
+                    (center.column - delta_row).div_euclid(2),
+                );
+                let rotated_270 = Position::new(
+                    (center.row - delta_column).div_euclid(2),
+                    (center.column + delta_row).div_euclid(2),
+                );
+                Images::new(&[rotated_90, rotated_180, rotated_270])
+            }
+        }
+    }
+}
+
+/// The positions that a position is mapped to by a symmetry, see [Symmetry::images].
+/// There are at most three of them, so they're kept inline rather than allocated,
+/// since they're needed for every cell of a galaxy when checking its symmetry.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Images {
+    positions: [Position; 3],
+    len: usize,
+}
+
+impl Images {
+    pub fn new(images: &[Position]) -> Images {
+        let mut positions = [Position::new(0, 0); 3];
+        positions[..images.len()].copy_from_slice(images);
+        Images {
+            positions,
+            len: images.len(),
+        }
+    }
+}
+
+impl Deref for Images {
+    type Target = [Position];
+
+    fn deref(&self) -> &[Position] {
+        &self.positions[..self.len]
+    }
+}
+
+impl IntoIterator for Images {
+    type Item = Position;
+    type IntoIter = std::iter::Take<std::array::IntoIter<Position, 3>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.positions.into_iter().take(self.len)
+    }
+}
+
+impl Display for Symmetry {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Symmetry::Rotational => "rotational",
+            Symmetry::HorizontalAxis => "horizontal",
+            Symmetry::VerticalAxis => "vertical",
+            Symmetry::FourFold => "four-fold",
+        };
+        write!(f, "{name}")
+    }
+}
+
+impl FromStr for Symmetry {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Symmetry::ALL
+            .into_iter()
+            .find(|symmetry| symmetry.to_string() == s)
+            .ok_or_else(|| format!("Unknown symmetry: {s}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::position::Position;
+    use crate::model::symmetry::Symmetry;
+
+    #[test]
+    fn rotational_image_should_be_point_reflection() {
+        let images = Symmetry::Rotational.images(&Position::new(2, 2), &Position::new(0, 0));
+        assert_eq!(*images, [Position::new(2, 2)]);
+    }
+
+    #[test]
+    fn axis_images_should_keep_the_other_coordinate() {
+        let center = Position::new(2, 4);
+        let p = Position::new(0, 1);
+        assert_eq!(
+            *Symmetry::HorizontalAxis.images(&center, &p),
+            [Position::new(2, 1)]
+        );
+        assert_eq!(
+            *Symmetry::VerticalAxis.images(&center, &p),
+            [Position::new(0, 3)]
+        );
+    }
+
+    #[test]
+    fn four_fold_images_should_go_around_the_center() {
+        // Center at the intersection between (0, 0), (0, 1), (1, 0) and (1, 1)
+        let images = Symmetry::FourFold.images(&Position::new(1, 1), &Position::new(0, 0));
+        assert_eq!(
+            *images,
+            [
+                Position::new(0, 1),
+                Position::new(1, 1),
+                Position::new(1, 0)
+            ]
+        );
+    }
+
+    #[test]
+    fn four_fold_should_not_allow_centers_on_borders() {
+        assert!(Symmetry::FourFold.allows_center(&Position::new(2, 2)));
+        assert!(Symmetry::FourFold.allows_center(&Position::new(1, 3)));
+        assert!(!Symmetry::FourFold.allows_center(&Position::new(1, 2)));
+        assert!(Symmetry::Rotational.allows_center(&Position::new(1, 2)));
+    }
+
+    #[test]
+    fn should_parse_displayed_symmetry() {
+        for symmetry in Symmetry::ALL {
+            assert_eq!(symmetry.to_string().parse(), Ok(symmetry));
+        }
+    }
+}
@@ -0,0 +1,78 @@
+/// Measures the time spent on a puzzle in milliseconds, excluding the time while paused.
+/// The current time is passed in by the caller, so that the model doesn't depend on a clock.
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub struct Timer {
+    /// The time accumulated before the current run
+    elapsed: f64,
+    /// When the current run started, if the timer is running
+    started_at: Option<f64>,
+}
+
+impl Timer {
+    /// Creates a timer that has already run for [elapsed] milliseconds, and is paused
+    pub fn with_elapsed(elapsed: f64) -> Timer {
+        Timer {
+            elapsed,
+            started_at: None,
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.started_at.is_some()
+    }
+
+    /// Starts or resumes the timer, does nothing if it's already running
+    pub fn start(&mut self, now: f64) {
+        if self.started_at.is_none() {
+            self.started_at = Some(now);
+        }
+    }
+
+    /// Pauses the timer, does nothing if it's already paused
+    pub fn pause(&mut self, now: f64) {
+        self.elapsed = self.elapsed(now);
+        self.started_at = None;
+    }
+
+    /// Returns the milliseconds the timer has been running at [now]
+    pub fn elapsed(&self, now: f64) -> f64 {
+        match self.started_at {
+            Some(started_at) => self.elapsed + (now - started_at).max(0.0),
+            None => self.elapsed,
+        }
+    }
+}
+
+/// Formats [milliseconds] as minutes and seconds, e.g. `3:07`, or with hours, e.g. `1:03:07`
+pub fn format_duration(milliseconds: f64) -> String {
+    let seconds = (milliseconds / 1000.0).floor().max(0.0) as u64;
+    let (hours, minutes, seconds) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+    if hours > 0 {
+        format!("{hours}:{minutes:02}:{seconds:02}")
+    } else {
+        format!("{minutes}:{seconds:02}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::timer::{format_duration, Timer};
+
+    #[test]
+    fn paused_time_should_not_count() {
+        let mut timer = Timer::default();
+        timer.start(1000.0);
+        timer.pause(3000.0);
+        assert_eq!(timer.elapsed(10000.0), 2000.0);
+        timer.start(10000.0);
+        assert!(timer.is_running());
+        assert_eq!(timer.elapsed(10500.0), 2500.0);
+    }
+
+    #[test]
+    fn should_format_duration() {
+        assert_eq!(format_duration(0.0), "0:00");
+        assert_eq!(format_duration(187_999.0), "3:07");
+        assert_eq!(format_duration(3_787_000.0), "1:03:07");
+    }
+}
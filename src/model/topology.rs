@@ -0,0 +1,429 @@
+use crate::model::galaxy::Galaxy;
+use crate::model::grid::{Grid, GridType};
+use crate::model::mask::Mask;
+use crate::model::position::Position;
+use crate::model::symmetry::Symmetry;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+/// How the edges of the board are connected to each other
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Default, Hash)]
+pub enum Topology {
+    /// The edges are not connected, the classic rule
+    #[default]
+    Plane,
+    /// The left edge is connected to the right edge
+    HorizontalWrap,
+    /// The top edge is connected to the bottom edge
+    VerticalWrap,
+    /// Both the left and right edges, and the top and bottom edges, are connected
+    Torus,
+}
+
+impl Topology {
+    pub const ALL: [Topology; 4] = [
+        Topology::Plane,
+        Topology::HorizontalWrap,
+        Topology::VerticalWrap,
+        Topology::Torus,
+    ];
+}
+
+impl Display for Topology {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Topology::Plane => "plane",
+            Topology::HorizontalWrap => "horizontal-wrap",
+            Topology::VerticalWrap => "vertical-wrap",
+            Topology::Torus => "torus",
+        };
+        write!(f, "{name}")
+    }
+}
+
+impl FromStr for Topology {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Topology::ALL
+            .into_iter()
+            .find(|topology| topology.to_string() == s)
+            .ok_or_else(|| format!("Unknown topology: {s}"))
+    }
+}
+
+/// A rectangle of cells of a [GridType] whose edges are connected according to a [Topology].
+///
+/// Positions are normalized to lie within the rectangle along the axes that wrap,
+/// and galaxies that span a wrapped edge are unwrapped into the plane to find their center and symmetry.
+/// Wrapping only applies to square grids, and to dimensions of at least three cells,
+/// since two cells can't be adjacent across two different borders.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Surface {
+    grid: GridType,
+    topology: Topology,
+    width: usize,
+    height: usize,
+}
+
+impl Surface {
+    pub fn new(grid: GridType, topology: Topology, width: usize, height: usize) -> Surface {
+        Surface {
+            grid,
+            topology,
+            width,
+            height,
+        }
+    }
+
+    pub fn topology(&self) -> Topology {
+        self.topology
+    }
+
+    pub fn grid_type(&self) -> GridType {
+        self.grid
+    }
+
+    pub fn grid(&self) -> &'static dyn Grid {
+        self.grid.grid()
+    }
+
+    /// Returns whether the left edge is connected to the right edge
+    pub fn wraps_columns(&self) -> bool {
+        self.grid == GridType::Square
+            && matches!(self.topology, Topology::HorizontalWrap | Topology::Torus)
+            && self.width >= 3
+    }
+
+    /// Returns whether the top edge is connected to the bottom edge
+    pub fn wraps_rows(&self) -> bool {
+        self.grid == GridType::Square
+            && matches!(self.topology, Topology::VerticalWrap | Topology::Torus)
+            && self.height >= 3
+    }
+
+    pub fn is_plane(&self) -> bool {
+        !self.wraps_columns() && !self.wraps_rows()
+    }
+
+    /// Moves [p] into the rectangle along the axes that wrap,
+    /// positions outside along the other axes are left outside
+    pub fn normalize(&self, p: &Position) -> Position {
+        let row = if self.wraps_rows() {
+            p.row.rem_euclid(self.height as i32)
+        } else {
+            p.row
+        };
+        let column = if self.wraps_columns() {
+            p.column.rem_euclid(self.width as i32)
+        } else {
+            p.column
+        };
+        Position::new(row, column)
+    }
+
+    /// Moves [center], given in doubled lattice coordinates, into the rectangle along the axes that wrap
+    pub fn normalize_center(&self, center: &Position) -> Position {
+        let row = if self.wraps_rows() {
+            center.row.rem_euclid(2 * self.height as i32)
+        } else {
+            center.row
+        };
+        let column = if self.wraps_columns() {
+            center.column.rem_euclid(2 * self.width as i32)
+        } else {
+            center.column
+        };
+        Position::new(row, column)
+    }
+
+    /// Returns the positions adjacent to [p], normalized
+    pub fn adjacent(&self, p: &Position) -> Vec<Position> {
+        self.grid()
+            .adjacent(p)
+            .iter()
+            .map(|q| self.normalize(q))
+            .collect()
+    }
+
+    /// Returns whether every connected region of the cells of [mask] might be divided into galaxies,
+    /// see [Grid::can_tile]
+    pub fn can_tile(&self, mask: &Mask) -> bool {
+        let mut visited = HashSet::new();
+        for start in mask.positions() {
+            if !visited.insert(start) {
+                continue;
+            }
+            let mut region = vec![start];
+            let mut next = 0;
+            while let Some(&p) = region.get(next) {
+                for q in self.adjacent(&p) {
+                    if mask.contains(&q) && visited.insert(q) {
+                        region.push(q);
+                    }
+                }
+                next += 1;
+            }
+            if !self.grid().can_tile(&region) {
+                return false;
+            }
+        }
+        true
+    }
+
+    pub fn are_adjacent(&self, p1: &Position, p2: &Position) -> bool {
+        self.adjacent(p1).contains(p2)
+    }
+
+    /// Returns whether the border between the adjacent [p1] and [p2] lies on a wrapped edge
+    pub fn crosses_wrapped_edge(&self, p1: &Position, p2: &Position) -> bool {
+        !self.grid().adjacent(p1).contains(p2)
+    }
+
+    /// Returns the normalized cells around each corner of [p], see [Grid::corners]
+    pub fn corners(&self, p: &Position) -> Vec<Vec<Position>> {
+        self.grid()
+            .corners(p)
+            .iter()
+            .map(|corner| corner.iter().map(|q| self.normalize(q)).collect())
+            .collect()
+    }
+
+    /// Returns whether a galaxy with [symmetry] can have its center at [center]
+    pub fn allows_center(&self, symmetry: Symmetry, center: &Position) -> bool {
+        self.grid().allows_center(symmetry, center)
+    }
+
+    /// Returns the normalized positions of the cells surrounding [center]
+    pub fn center_positions(&self, center: &Position) -> Vec<Position> {
+        self.grid()
+            .center_positions(center)
+            .iter()
+            .map(|p| self.normalize(p))
+            .collect()
+    }
+
+    /// Returns the position in the plane of each position of [galaxy], such that the galaxy is connected
+    /// without crossing a wrapped edge. Returns None if the galaxy wraps all the way around the surface.
+    fn unwrapped_positions(&self, galaxy: &Galaxy) -> Option<HashMap<Position, Position>> {
+        let mut unwrapped: HashMap<Position, Position> = HashMap::new();
+        let mut remaining: Vec<Position> = galaxy.get_positions().copied().collect();
+        remaining.sort();
+        for start in remaining {
+            if unwrapped.contains_key(&start) {
+                continue;
+            }
+            unwrapped.insert(start, start);
+            let mut queue = VecDeque::from([start]);
+            while let Some(u) = queue.pop_front() {
+                for v in u.adjacent() {
+                    let p = self.normalize(&v);
+                    if !galaxy.contains_position(&p) {
+                        continue;
+                    }
+                    match unwrapped.get(&p) {
+                        None => {
+                            unwrapped.insert(p, v);
+                            queue.push_back(v);
+                        }
+                        // Reaching the same cell from two directions means that the galaxy wraps around
+                        Some(&w) if w != v => return None,
+                        Some(_) => {}
+                    }
+                }
+            }
+        }
+        // Shift the galaxy by whole periods to non-negative positions, where half-steps are well-behaved
+        let shift = |min: i32, period: usize| {
+            let period = period as i32;
+            -period * min.div_euclid(period)
+        };
+        let row_shift = shift(
+            unwrapped.values().map(|p| p.row).min().unwrap_or(0),
+            self.height,
+        );
+        let column_shift = shift(
+            unwrapped.values().map(|p| p.column).min().unwrap_or(0),
+            self.width,
+        );
+        for p in unwrapped.values_mut() {
+            *p = Position::new(p.row + row_shift, p.column + column_shift);
+        }
+        Some(unwrapped)
+    }
+
+    /// Returns [galaxy] moved into the plane, see [Surface::unwrapped_positions]
+    pub fn unwrap(&self, galaxy: &Galaxy) -> Option<Galaxy> {
+        if self.is_plane() {
+            return Some(galaxy.clone());
+        }
+        self.unwrapped_positions(galaxy)
+            .map(|unwrapped| Galaxy::from_positions(unwrapped.into_values()))
+    }
+
+    /// Returns the normalized center of [galaxy] in doubled lattice coordinates,
+    /// or None if the galaxy wraps all the way around the surface
+    pub fn center(&self, galaxy: &Galaxy) -> Option<Position> {
+        if self.is_plane() {
+            return Some(self.grid().center(galaxy));
+        }
+        self.unwrap(galaxy)
+            .map(|unwrapped| self.normalize_center(&self.grid().center(&unwrapped)))
+    }
+
+    pub fn is_symmetric(&self, galaxy: &Galaxy, symmetry: Symmetry) -> bool {
+        if self.is_plane() {
+            return self.grid().is_symmetric(galaxy, symmetry);
+        }
+        self.unwrap(galaxy)
+            .is_some_and(|unwrapped| self.grid().is_symmetric(&unwrapped, symmetry))
+    }
+
+    pub fn is_valid(&self, galaxy: &Galaxy, symmetry: Symmetry) -> bool {
+        if self.is_plane() {
+            return self.grid().is_valid(galaxy, symmetry);
+        }
+        self.unwrap(galaxy)
+            .is_some_and(|unwrapped| self.grid().is_valid(&unwrapped, symmetry))
+    }
+
+    pub fn is_empty_or_valid(&self, galaxy: &Galaxy, symmetry: Symmetry) -> bool {
+        galaxy.is_empty() || self.is_valid(galaxy, symmetry)
+    }
+
+    /// Returns the normalized positions that [p] is mapped to by [symmetry] around the center of [galaxy]
+    pub fn mirror_positions(
+        &self,
+        galaxy: &Galaxy,
+        p: &Position,
+        symmetry: Symmetry,
+    ) -> Vec<Position> {
+        if self.is_plane() {
+            return self.grid().mirror_positions(galaxy, p, symmetry);
+        }
+        let Some(unwrapped) = self.unwrapped_positions(galaxy) else {
+            return self.grid().mirror_positions(galaxy, p, symmetry);
+        };
+        let center = Galaxy::from_positions(unwrapped.values().copied()).center();
+        let p = unwrapped
+            .get(p)
+            .copied()
+            .unwrap_or_else(|| self.nearest(&center, p));
+        symmetry
+            .images(&center, &p)
+            .iter()
+            .map(|image| self.normalize(image))
+            .collect()
+    }
+
+    /// Returns the normalized positions that [p] is mapped to by [symmetry] around [center],
+    /// using the copy of [p] that is nearest to the center
+    pub fn images(&self, symmetry: Symmetry, center: &Position, p: &Position) -> Vec<Position> {
+        self.grid()
+            .images(symmetry, center, &self.nearest(center, p))
+            .iter()
+            .map(|image| self.normalize(image))
+            .collect()
+    }
+
+    /// Returns the copy of [p] across the wrapped edges that is nearest to [center], given in half-steps
+    fn nearest(&self, center: &Position, p: &Position) -> Position {
+        let nearest = |value: i32, center: i32, period: usize| {
+            let period = period as i32;
+            value - period * (2 * value - center + period).div_euclid(2 * period)
+        };
+        let row = if self.wraps_rows() {
+            nearest(p.row, center.row, self.height)
+        } else {
+            p.row
+        };
+        let column = if self.wraps_columns() {
+            nearest(p.column, center.column, self.width)
+        } else {
+            p.column
+        };
+        Position::new(row, column)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::galaxy::Galaxy;
+    use crate::model::grid::GridType;
+    use crate::model::position::Position;
+    use crate::model::symmetry::Symmetry;
+    use crate::model::topology::{Surface, Topology};
+
+    fn galaxy(positions: &[(i32, i32)]) -> Galaxy {
+        Galaxy::from_positions(positions.iter().copied().map(Position::from))
+    }
+
+    #[test]
+    fn torus_should_wrap_adjacent_positions() {
+        let surface = Surface::new(GridType::Square, Topology::Torus, 4, 3);
+        let adjacent = surface.adjacent(&Position::new(0, 0));
+        assert!(adjacent.contains(&Position::new(0, 3)));
+        assert!(adjacent.contains(&Position::new(2, 0)));
+    }
+
+    #[test]
+    fn plane_should_not_wrap() {
+        let surface = Surface::new(GridType::Square, Topology::Plane, 4, 3);
+        assert_eq!(
+            surface.normalize(&Position::new(-1, 4)),
+            Position::new(-1, 4)
+        );
+    }
+
+    #[test]
+    fn galaxy_across_edge_should_have_center_on_edge() {
+        let surface = Surface::new(GridType::Square, Topology::HorizontalWrap, 4, 3);
+        let domino = galaxy(&[(1, 3), (1, 0)]);
+        assert_eq!(surface.center(&domino), Some(Position::new(2, 7)));
+        assert!(surface.is_valid(&domino, Symmetry::Rotational));
+        assert!(!Surface::new(GridType::Square, Topology::Plane, 4, 3)
+            .is_valid(&domino, Symmetry::Rotational));
+    }
+
+    #[test]
+    fn galaxy_around_the_surface_should_be_invalid() {
+        let surface = Surface::new(GridType::Square, Topology::HorizontalWrap, 3, 1);
+        let row = galaxy(&[(0, 0), (0, 1), (0, 2)]);
+        assert_eq!(surface.center(&row), None);
+        assert!(!surface.is_valid(&row, Symmetry::Rotational));
+    }
+
+    #[test]
+    fn mirror_position_should_wrap() {
+        let surface = Surface::new(GridType::Square, Topology::Torus, 5, 5);
+        let square = galaxy(&[(4, 4), (4, 0), (0, 4), (0, 0)]);
+        assert_eq!(surface.center(&square), Some(Position::new(9, 9)));
+        assert_eq!(
+            surface.mirror_positions(&square, &Position::new(0, 0), Symmetry::Rotational),
+            vec![Position::new(4, 4)]
+        );
+        assert_eq!(
+            surface.images(
+                Symmetry::Rotational,
+                &Position::new(9, 9),
+                &Position::new(1, 0)
+            ),
+            vec![Position::new(3, 4)]
+        );
+    }
+
+    #[test]
+    fn only_square_grids_should_wrap() {
+        let surface = Surface::new(GridType::Hexagonal, Topology::Torus, 4, 4);
+        assert!(surface.is_plane());
+        assert!(!surface.are_adjacent(&Position::new(0, 0), &Position::new(0, 3)));
+    }
+
+    #[test]
+    fn should_parse_displayed_topology() {
+        for topology in Topology::ALL {
+            assert_eq!(topology.to_string().parse(), Ok(topology));
+        }
+    }
+}
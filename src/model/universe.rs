@@ -1,72 +1,537 @@
-use crate::model::galaxy::Galaxy;
+use crate::model::board::Board;
+use crate::model::border::Border;
+use crate::model::constraints::GenerationConstraints;
+use crate::model::galaxy::{rectangles_of, Galaxy};
+use crate::model::generation_error::GenerationError;
+use crate::model::grid::{lattice_center, GridType};
+use crate::model::mask::Mask;
+use crate::model::picture::Picture;
 use crate::model::position::Position;
-use petgraph::data::Build;
-use petgraph::graphmap::UnGraphMap;
-use petgraph::visit::{Dfs, Walker};
+use crate::model::symmetry::Symmetry;
+use crate::model::topology::{Surface, Topology};
 use rand::prelude::SliceRandom;
 use rand::rngs::StdRng;
 use rand::{random, Rng, SeedableRng};
-use std::collections::BTreeSet;
+use std::cmp::min;
+use std::collections::{BTreeSet, HashMap};
 use std::fmt::{Display, Formatter};
+use std::ops::{AddAssign, SubAssign};
+use std::sync::Arc;
+
+/// How many points each cell with the wrong colour adds to the score when generating for a picture
+const PICTURE_MISMATCH_WEIGHT: i64 = 60;
+
+/// How many points each invalid galaxy adds to the score, on grids where single cells aren't valid galaxies
+const INVALID_GALAXY_WEIGHT: i64 = 1000;
+
+/// How many times generation is attempted on grids where single cells aren't valid galaxies
+const GENERATION_ATTEMPTS: usize = 20;
+
+/// How many points each step away from satisfying the constraints adds to the score
+const CONSTRAINT_WEIGHT: i64 = 1000;
+
+/// How many universes are generated before giving up on satisfying the constraints
+const CONSTRAINED_ATTEMPTS: usize = 5;
+
+/// How many cells are allocated for a flood up front, which most galaxies fit in
+const FLOOD_CAPACITY: usize = 32;
+
+/// The label of the cells outside the mask
+const OUTSIDE: u32 = u32::MAX;
 
 #[derive(Clone, Debug)]
 pub struct Universe {
-    width: usize,
-    height: usize,
-    graph: UnGraphMap<Position, ()>,
+    mask: Mask,
+    symmetry: Symmetry,
+    surface: Surface,
+    /// The label of the galaxy of every cell of the bounding rectangle, row by row.
+    /// The cells of a galaxy share a label and are connected, cells outside the mask are [OUTSIDE].
+    /// A galaxy is labelled with the index of one of its cells, so no two galaxies share a label.
+    labels: Vec<u32>,
+    /// The sums over the galaxies that the score is made of, kept up to date as the galaxies change,
+    /// except for the galaxies that are still pending
+    totals: GalaxyTotals,
+    /// What each galaxy adds to the totals, by label. Labels without a galaxy, and pending galaxies, add nothing.
+    totals_by_label: Vec<GalaxyTotals>,
+    /// The labels of the galaxies that changed since their totals were last added, possibly repeated.
+    /// A step of the generation changes galaxies several times, so they're only added before scoring,
+    /// which is skipped for the last step of each iteration of the climb.
+    pending: Vec<u32>,
+    /// The points of each straight line of borders between two rows, followed by those between two columns,
+    /// see [Universe::line_score]. Lines are None while they're pending.
+    line_scores: Vec<Option<i64>>,
+    /// The sum of the points of the lines that aren't pending
+    line_total: i64,
+    /// The lines with a border between a cell that changed galaxy and another cell
+    /// since their points were last added, like [Universe::pending]
+    pending_lines: Vec<usize>,
+    /// Never changes, so it's shared between the universes that are cloned while generating
+    adjacency: Arc<Adjacency>,
+}
+
+/// The adjacent cells inside the mask of every cell of the bounding rectangle, by index
+#[derive(Debug)]
+struct Adjacency {
+    /// Where the adjacent cells of each cell start in [Adjacency::cells], followed by the end
+    offsets: Vec<usize>,
+    cells: Vec<usize>,
+}
+
+impl Adjacency {
+    fn new(mask: &Mask, surface: &Surface) -> Adjacency {
+        let mut offsets = vec![0];
+        let mut cells = Vec::new();
+        for row in 0..mask.height() {
+            for column in 0..mask.width() {
+                let p = Position::from((row, column));
+                if mask.contains(&p) {
+                    cells.extend(
+                        surface
+                            .adjacent(&p)
+                            .iter()
+                            .filter(|q| mask.contains(q))
+                            .map(|q| q.row as usize * mask.width() + q.column as usize),
+                    );
+                }
+                offsets.push(cells.len());
+            }
+        }
+        Adjacency { offsets, cells }
+    }
+
+    fn of(&self, index: usize) -> &[usize] {
+        &self.cells[self.offsets[index]..self.offsets[index + 1]]
+    }
+}
+
+/// Sums over a set of galaxies, see [Universe::get_score]
+#[derive(Clone, Copy, Debug, Default)]
+struct GalaxyTotals {
+    count: i64,
+    /// The number of galaxies that are rectangles, only counted on square grids
+    rectangular: i64,
+    /// The sum of the squared areas of the rectangles that the galaxies are divided into
+    rectangles: i64,
+    /// The sum of the squared sizes of the galaxies
+    sizes: i64,
+}
+
+impl AddAssign for GalaxyTotals {
+    fn add_assign(&mut self, other: GalaxyTotals) {
+        self.count += other.count;
+        self.rectangular += other.rectangular;
+        self.rectangles += other.rectangles;
+        self.sizes += other.sizes;
+    }
+}
+
+impl SubAssign for GalaxyTotals {
+    fn sub_assign(&mut self, other: GalaxyTotals) {
+        self.count -= other.count;
+        self.rectangular -= other.rectangular;
+        self.rectangles -= other.rectangles;
+        self.sizes -= other.sizes;
+    }
+}
+
+/// A set of cells of the bounding rectangle, one bit per cell
+#[derive(Clone)]
+struct CellSet {
+    bits: Vec<u64>,
+}
+
+impl CellSet {
+    fn new(len: usize) -> CellSet {
+        CellSet {
+            bits: vec![0; len.div_ceil(64)],
+        }
+    }
+
+    fn contains(&self, index: usize) -> bool {
+        self.bits[index / 64] & (1 << (index % 64)) != 0
+    }
+
+    /// Adds the cell, returns whether it wasn't already in the set
+    fn insert(&mut self, index: usize) -> bool {
+        let is_new = !self.contains(index);
+        self.bits[index / 64] |= 1 << (index % 64);
+        is_new
+    }
+
+    /// Removes the cell, returns whether it was in the set
+    fn remove(&mut self, index: usize) -> bool {
+        let was_present = self.contains(index);
+        self.bits[index / 64] &= !(1 << (index % 64));
+        was_present
+    }
+}
+
+/// The cells of a galaxy by index, which are cheaper to change and check while generating
+/// than a [Galaxy]
+#[derive(Clone)]
+struct GalaxyCells {
+    cells: Vec<usize>,
+    set: CellSet,
+}
+
+impl GalaxyCells {
+    fn contains(&self, index: usize) -> bool {
+        self.set.contains(index)
+    }
+
+    /// Returns these cells along with [indices]
+    fn with(&self, indices: &[usize]) -> GalaxyCells {
+        let mut cells = Vec::with_capacity(self.cells.len() + indices.len());
+        cells.extend_from_slice(&self.cells);
+        let mut with = GalaxyCells {
+            cells,
+            set: self.set.clone(),
+        };
+        for &index in indices {
+            if with.set.insert(index) {
+                with.cells.push(index);
+            }
+        }
+        with
+    }
+
+    fn remove(&mut self, index: usize) {
+        if self.set.remove(index) {
+            self.cells.retain(|&cell| cell != index);
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
 }
 
 impl Universe {
     pub fn width(&self) -> usize {
-        self.width
+        self.mask.width()
+    }
+
+    pub fn height(&self) -> usize {
+        self.mask.height()
+    }
+
+    pub fn mask(&self) -> &Mask {
+        &self.mask
+    }
+
+    pub fn surface(&self) -> &Surface {
+        &self.surface
+    }
+
+    pub fn topology(&self) -> Topology {
+        self.surface.topology()
     }
 
+    pub fn grid_type(&self) -> GridType {
+        self.surface.grid_type()
+    }
+
+    pub fn symmetry(&self) -> Symmetry {
+        self.symmetry
+    }
+
+    /// Generates a universe of square cells, [width] and [height] must be positive
     pub fn generate(width: usize, height: usize) -> Universe {
-        let mut universe = Universe::new(width, height);
-        let iterations = width * height * 10;
-        let branches = 5;
-        let seed: u64 = random();
-        println!("Seed: {}", seed);
+        Self::generate_with_symmetry(width, height, Symmetry::default())
+    }
+
+    /// Generates a universe where every galaxy has the given kind of symmetry,
+    /// [width] and [height] must be positive
+    pub fn generate_with_symmetry(width: usize, height: usize, symmetry: Symmetry) -> Universe {
+        Self::generate_with_mask(
+            Mask::full(width, height),
+            symmetry,
+            Topology::default(),
+            GridType::default(),
+        )
+        .expect("Failed to generate a universe of square cells")
+    }
+
+    /// Checks that [mask] might be divided into galaxies of [grid] cells with [symmetry],
+    /// so that generation doesn't have to find out by failing
+    pub fn check_generation(
+        mask: &Mask,
+        symmetry: Symmetry,
+        topology: Topology,
+        grid: GridType,
+    ) -> Result<(), GenerationError> {
+        if mask.size() == 0 {
+            return Err(GenerationError::EmptyMask);
+        }
+        if !grid.supports(symmetry) {
+            return Err(GenerationError::UnsupportedSymmetry(grid, symmetry));
+        }
+        if !grid.supports_topology(topology) {
+            return Err(GenerationError::UnsupportedTopology(grid, topology));
+        }
+        let surface = Surface::new(grid, topology, mask.width(), mask.height());
+        if !surface.can_tile(mask) {
+            return Err(GenerationError::Untileable);
+        }
+        Ok(())
+    }
+
+    /// Generates a universe of [grid] cells that only covers the cells of [mask],
+    /// where galaxies can span the edges that are connected by [topology]
+    pub fn generate_with_mask(
+        mask: Mask,
+        symmetry: Symmetry,
+        topology: Topology,
+        grid: GridType,
+    ) -> Result<Universe, GenerationError> {
+        Self::generate_with_seed(mask, symmetry, topology, grid, random())
+    }
+
+    /// Generates a universe like [Universe::generate_with_mask],
+    /// where the same [seed] always generates the same universe
+    pub fn generate_with_seed(
+        mask: Mask,
+        symmetry: Symmetry,
+        topology: Topology,
+        grid: GridType,
+        seed: u64,
+    ) -> Result<Universe, GenerationError> {
+        Self::generate_with_score(mask, symmetry, topology, grid, seed, Universe::get_score)
+    }
+
+    /// Generates a universe like [Universe::generate_with_seed] that satisfies [constraints],
+    /// by penalising the universes that break them while climbing.
+    /// Each attempt uses the seed after the one of the previous attempt,
+    /// and an attempt that doesn't end up with valid galaxies counts as a failed one.
+    pub fn generate_with_constraints(
+        mask: Mask,
+        symmetry: Symmetry,
+        topology: Topology,
+        grid: GridType,
+        seed: u64,
+        constraints: &GenerationConstraints,
+    ) -> Result<Universe, GenerationError> {
+        Self::check_generation(&mask, symmetry, topology, grid)?;
+        constraints.validate(mask.size())?;
+        let mut violation = None;
+        for attempt in 0..CONSTRAINED_ATTEMPTS {
+            let generated = Self::generate_with_score(
+                mask.clone(),
+                symmetry,
+                topology,
+                grid,
+                seed.wrapping_add(attempt as u64),
+                |universe| universe.get_score() + CONSTRAINT_WEIGHT * constraints.penalty(universe),
+            );
+            let universe = match generated {
+                Ok(universe) => universe,
+                Err(GenerationError::Unsatisfied(_, _)) => {
+                    violation = None;
+                    continue;
+                }
+                Err(e) => return Err(e),
+            };
+            match constraints.check(&universe) {
+                Ok(()) => return Ok(universe),
+                Err(v) => violation = Some(v),
+            }
+        }
+        Err(GenerationError::Unsatisfied(
+            CONSTRAINED_ATTEMPTS,
+            violation,
+        ))
+    }
+
+    /// Generates a universe whose galaxies can be coloured to approximate [picture].
+    /// The climb adds the cells that end up with the wrong colour to the score, see [Universe::get_picture_mismatch],
+    /// so galaxies grow along the edges of the picture instead of across them.
+    /// The picture is scaled to the size of the universe, [width] and [height] must be positive.
+    pub fn generate_with_picture(width: usize, height: usize, picture: &Picture) -> Universe {
+        Self::generate_with_picture_and_seed(width, height, picture, random())
+    }
+
+    /// Generates a universe like [Universe::generate_with_picture],
+    /// where the same [seed] always generates the same universe
+    pub fn generate_with_picture_and_seed(
+        width: usize,
+        height: usize,
+        picture: &Picture,
+        seed: u64,
+    ) -> Universe {
+        let picture = picture.scaled(width, height);
+        let mask = Mask::full(width, height);
+        Self::generate_with_score(
+            mask,
+            Symmetry::default(),
+            Topology::default(),
+            GridType::default(),
+            seed,
+            |universe| {
+                universe.get_score()
+                    + PICTURE_MISMATCH_WEIGHT * universe.get_picture_mismatch(&picture)
+            },
+        )
+        .expect("Failed to generate a universe of square cells")
+    }
+
+    /// Generates a universe by hill climbing, keeping the branch with the lowest score in each iteration.
+    ///
+    /// The climb starts from single cell galaxies. On grids where those aren't valid,
+    /// invalid galaxies are penalised instead and merged into their neighbours after the climb,
+    /// which is retried a few times until it ends up valid.
+    fn generate_with_score(
+        mask: Mask,
+        symmetry: Symmetry,
+        topology: Topology,
+        grid: GridType,
+        seed: u64,
+        score: impl Fn(&Universe) -> i64,
+    ) -> Result<Universe, GenerationError> {
+        Self::check_generation(&mask, symmetry, topology, grid)?;
+        let universe = Universe::new_with_mask(mask, symmetry, topology, grid);
         let mut rng = StdRng::seed_from_u64(seed);
+        if universe.is_valid() {
+            let universe = universe.climb(score, &mut rng);
+            debug_assert!(universe.is_valid());
+            return Ok(universe);
+        }
+        let score = |universe: &Universe| {
+            score(universe) + INVALID_GALAXY_WEIGHT * universe.count_invalid_galaxies() as i64
+        };
+        for _attempt in 0..GENERATION_ATTEMPTS {
+            let mut climbed = universe.clone().climb(score, &mut rng);
+            climbed.repair(&mut rng);
+            if climbed.is_valid() {
+                return Ok(climbed);
+            }
+        }
+        Err(GenerationError::Unsatisfied(GENERATION_ATTEMPTS, None))
+    }
+
+    fn climb(self, score: impl Fn(&Universe) -> i64, rng: &mut impl Rng) -> Universe {
+        let iterations = self.mask.size() * 10;
+        let mut universe = self;
+        let mut universe_score = None;
+        let branches = 5;
         for _iteration in 0..iterations {
-            let mut next_universes = Vec::with_capacity(branches);
+            // Each branch takes a step from the universe before it, and the universe before
+            // the step with the lowest score is kept, the first one of them if several have it.
+            // Only the universes with a lower score than the ones before them are cloned.
+            // A step that fails leaves the universe as it was, so its score is only computed again after a step succeeds.
+            let mut best: Option<(i64, Universe)> = None;
             for _branch in 0..branches {
-                let next_universe = universe.clone();
-                let success = universe.generate_step(&mut rng);
+                let branch_score = *universe_score.get_or_insert_with(|| {
+                    universe.add_pending_totals();
+                    score(&universe)
+                });
+                let next_universe = best
+                    .as_ref()
+                    .is_none_or(|(best_score, _)| branch_score < *best_score)
+                    .then(|| universe.clone());
+                let success = universe.generate_step(rng);
                 if success {
-                    next_universes.push(next_universe);
+                    universe_score = None;
+                }
+                if let (true, Some(next_universe)) = (success, next_universe) {
+                    best = Some((branch_score, next_universe));
                 }
             }
 
-            universe = next_universes
-                .into_iter()
-                .min_by_key(|universe| universe.get_score())
-                .unwrap_or(universe);
+            if let Some((best_score, next_universe)) = best {
+                universe = next_universe;
+                universe_score = Some(best_score);
+            }
         }
-        assert!(universe.is_valid());
+        universe.add_pending_totals();
         universe
     }
+
+    /// Grows the invalid galaxies into their neighbours, as long as that doesn't leave more invalid galaxies
+    fn repair(&mut self, rng: &mut impl Rng) {
+        for _step in 0..self.mask.size() * 10 {
+            let invalid: Vec<Galaxy> = self
+                .get_galaxies()
+                .into_iter()
+                .filter(|galaxy| !self.surface.is_valid(galaxy, self.symmetry))
+                .collect();
+            let Some(galaxy) = invalid.choose(rng) else {
+                return;
+            };
+            let positions: Vec<Position> = galaxy.get_positions().copied().collect();
+            let p1 = *positions.choose(rng).expect("Galaxies should not be empty");
+            let mut next = self.clone();
+            if next.grow(&p1, rng) && next.count_invalid_galaxies() <= invalid.len() {
+                *self = next;
+            }
+        }
+    }
 }
 
 impl Universe {
     pub fn new(width: usize, height: usize) -> Universe {
-        let mut graph: UnGraphMap<Position, ()> = UnGraphMap::new();
-        for row in 0..height {
-            for column in 0..width {
-                let position = Position::from((row, column));
-                graph.add_node(position);
-            }
-        }
-        Universe {
-            width,
-            height,
-            graph,
+        Self::new_with_mask(
+            Mask::full(width, height),
+            Symmetry::default(),
+            Topology::default(),
+            GridType::default(),
+        )
+    }
+
+    /// Creates a universe of single position galaxies of [grid] cells covering the cells of [mask].
+    /// Single position galaxies are symmetric under any [Symmetry] on square and hexagonal grids,
+    /// but not on triangular grids.
+    pub fn new_with_mask(
+        mask: Mask,
+        symmetry: Symmetry,
+        topology: Topology,
+        grid: GridType,
+    ) -> Universe {
+        let surface = Surface::new(grid, topology, mask.width(), mask.height());
+        let labels: Vec<u32> = (0..mask.height())
+            .flat_map(|row| (0..mask.width()).map(move |column| (row, column)))
+            .enumerate()
+            .map(|(index, (row, column))| {
+                if mask.contains(&Position::from((row, column))) {
+                    index as u32
+                } else {
+                    OUTSIDE
+                }
+            })
+            .collect();
+        let lines = mask.height().saturating_sub(1) + mask.width().saturating_sub(1);
+        let mut universe = Universe {
+            totals_by_label: vec![GalaxyTotals::default(); labels.len()],
+            adjacency: Arc::new(Adjacency::new(&mask, &surface)),
+            pending: Vec::new(),
+            line_scores: vec![None; lines],
+            line_total: 0,
+            pending_lines: (0..lines).collect(),
+            mask,
+            symmetry,
+            surface,
+            labels,
+            totals: GalaxyTotals::default(),
+        };
+        for p in universe.mask.positions() {
+            let index = universe.index(&p).expect("Mask positions should be inside");
+            let totals = universe.galaxy_totals(&[index]);
+            universe.totals += totals;
+            universe.totals_by_label[index] = totals;
         }
+        universe.add_pending_lines();
+        universe
     }
 
     fn generate_step(&mut self, rng: &mut impl Rng) -> bool {
         // First we pick a random position in the universe
         let p1 = self.random_position(rng);
+        self.grow(&p1, rng)
+    }
+
+    /// Adds an adjacent position to the galaxy of [p1], along with any positions needed to keep it valid.
+    /// Returns whether the galaxy grew.
+    fn grow(&mut self, p1: &Position, rng: &mut impl Rng) -> bool {
+        let p1 = *p1;
 
         // Then we pick one of the adjacent positions that is not already a neighbour
         let p2_option = self.adjacent_non_neighbours(&p1).choose(rng).cloned();
@@ -75,58 +540,69 @@ impl Universe {
             return false;
         }
 
-        let g1 = self.get_galaxy(&p1);
+        let g1 = self.galaxy_cells(&p1);
         let p2 = p2_option.unwrap();
 
-        let g1_with_p2 = g1.with_position(&p2);
-        if g1_with_p2.is_symmetric() {
-            // If g1_with_p2 is symmetric, we do not need to consider p3 and g3,
+        let g1_with_p2 = g1.with(&[self
+            .index(&p2)
+            .expect("Adjacent positions should be inside")]);
+        if self.is_valid_cells(&g1_with_p2) {
+            // If g1_with_p2 is valid, we do not need to consider p3 and g3,
             // but we need to properly remove p2 from g2 before adding it to g1.
-            let g2 = self.get_galaxy(&p2);
+            let g2 = self.galaxy_cells(&p2);
             self.remove_positions_from_galaxy(&g2, &[p2]);
             self.make_neighbours(&p1, &p2);
             true
         } else {
-            // If g1_with_p2 is asymmetric, we need to add p3 to it
-            let p3_option = {
-                let mut p3_candidates = Vec::new();
+            // If g1_with_p2 is asymmetric, or doesn't contain its center, we need to add more positions to it
+            let extra_option = {
+                let mut extra_candidates: Vec<Vec<Position>> = Vec::new();
                 {
-                    let p3 = g1.mirror_position(&p2);
-                    if self.is_inside(&p3) {
-                        p3_candidates.push(p3);
+                    // The images of p2 around the center of g1 keep the center in place
+                    let mut images = self.mirrors(&g1, &p2);
+                    images.retain(|p3| p3 != &p2);
+                    images.sort();
+                    images.dedup();
+                    let image_cells: Option<Vec<usize>> =
+                        images.iter().map(|p3| self.inside_index(p3)).collect();
+                    if let Some(image_cells) = image_cells {
+                        if self.is_valid_cells(&g1_with_p2.with(&image_cells)) {
+                            extra_candidates.push(images);
+                        }
                     }
                 }
                 for p3 in self.adjacent_non_neighbours(&p2) {
-                    if g1_with_p2.with_position(&p3).is_symmetric() {
-                        p3_candidates.push(p3);
+                    let p3_cell = self
+                        .index(&p3)
+                        .expect("Adjacent positions should be inside");
+                    if self.is_valid_cells(&g1_with_p2.with(&[p3_cell])) {
+                        extra_candidates.push(vec![p3]);
                     }
                 }
-                if p3_candidates.is_empty() {
+                if extra_candidates.is_empty() {
                     None
-                }
-                else {
-                    p3_candidates.get(rng.gen_range(0..p3_candidates.len())).cloned()
+                } else {
+                    let index = rng.gen_range(0..extra_candidates.len());
+                    Some(extra_candidates.swap_remove(index))
                 }
             };
 
-            if let Some(p3) = p3_option {
-                let g2 = self.get_galaxy(&p2);
-                let g3 = self.get_galaxy(&p3);
-
-                if g2 == g3 {
-                    // If g2 and g3 is the same galaxy, we need to consider everything together while removing p2 and p3 from it
-                    self.remove_positions_from_galaxy(&g2, &[p2, p3]);
-                } else {
-                    // If g2 and g3 are different galaxies, we can treat them separately
-                    self.remove_positions_from_galaxy(&g2, &[p2]);
-                    self.remove_positions_from_galaxy(&g3, &[p3]);
+            if let Some(extra) = extra_option {
+                let positions_to_add: Vec<Position> = [p2].into_iter().chain(extra).collect();
+                let mut remaining = positions_to_add.clone();
+                while let Some(p) = remaining.first() {
+                    // Positions that are in the same galaxy need to be considered together while removing them from it
+                    let galaxy = self.galaxy_cells(p);
+                    let (in_galaxy, rest) = remaining
+                        .into_iter()
+                        .partition(|q| self.cells_contain(&galaxy, q));
+                    remaining = rest;
+                    self.remove_positions_from_galaxy(&galaxy, &in_galaxy);
                 }
-                self.make_neighbours(&p1, &p2);
-                self.make_neighbours(&p1, &p3);
+                self.join(&p1, &positions_to_add);
                 true
-            }
-            else {
-                // No candidates for p3 found to make g1 with p2 symmetric
+            } else {
+                // No candidates found to make g1 with p2 symmetric
                 false
             }
         }
@@ -134,107 +610,320 @@ impl Universe {
 
     /// Removes the given positions from the galaxy, while keeping the universe valid.
     /// After calling this method, all positions in [positions_to_remove] are singles.
-    fn remove_positions_from_galaxy(&mut self, galaxy: &Galaxy, positions_to_remove: &[Position]) {
+    /// The galaxy is only relabelled once, after finding all the positions that have to be separated from it.
+    fn remove_positions_from_galaxy(
+        &mut self,
+        galaxy: &GalaxyCells,
+        positions_to_remove: &[Position],
+    ) {
         let mut g = galaxy.clone();
+        let mut separated = Vec::new();
         for p in positions_to_remove {
-            assert!(galaxy.contains_position(&p));
-            self.remove_all_neighbours(p);
-            g.remove_position(p);
-            if !g.is_symmetric() {
-                // If g is asymmetric, we can solve that by removing the mirror of p as well
-                let p2 = galaxy.mirror_position(&p);
-                self.remove_all_neighbours(&p2);
-                g.remove_position(&p2);
-            }
-            if !g.is_empty_or_valid() {
-                // If g is invalid, it's because removing p (and maybe p2) disconnected it or removed its center.
-                // In both cases, we solve this by breaking up g completely into singles.
-                for remaining_positions in g.get_positions() {
-                    self.remove_all_neighbours(remaining_positions);
+            let index = self.index(p).filter(|&index| galaxy.contains(index));
+            g.remove(index.expect("Removed positions should be in the galaxy"));
+            separated.push(*p);
+            if !self.is_symmetric_cells(&g) {
+                // If g is asymmetric, we can solve that by removing the mirrors of p as well
+                let mirrors = self.mirrors(galaxy, p);
+                for p2 in &mirrors {
+                    if let Some(index) = self.index(p2) {
+                        g.remove(index);
+                    }
                 }
-                return;
+                separated.extend(mirrors);
+            }
+            if !g.is_empty() && !self.is_valid_cells(&g) {
+                // If g is invalid, it's because removing p (and maybe its mirrors) disconnected it or removed its center.
+                // In both cases, we solve this by breaking up g completely into singles.
+                separated.extend(g.cells.iter().map(|&i| self.position(i)));
+                break;
             }
         }
+        separated.sort();
+        separated.dedup();
+        self.separate(&separated);
     }
 
-    /// Returns a list of galaxies in this universe, in no particular order
+    /// Returns a list of galaxies in this universe, ordered by their first position
     pub fn get_galaxies(&self) -> Vec<Galaxy> {
         let mut galaxies: Vec<Galaxy> = Vec::new();
-        let mut remaining_positions: BTreeSet<Position> = self.graph.nodes().collect();
-        while let Some(position) = remaining_positions.first() {
-            let galaxy = self.get_galaxy(position);
-            for p in galaxy.get_positions() {
-                remaining_positions.remove(p);
+        let mut visited = CellSet::new(self.labels.len());
+        for p in self.mask.positions() {
+            let index = self.index(&p).expect("Mask positions should be inside");
+            if visited.contains(index) {
+                continue;
             }
-            galaxies.push(galaxy);
+            let cells = self.flood(index, &mut visited);
+            galaxies.push(Galaxy::from_positions(
+                cells.iter().map(|&i| self.position(i)),
+            ));
         }
         galaxies
     }
 
     /// Make p have no neighbours
     pub fn remove_all_neighbours(&mut self, p: &Position) {
-        for adjacent_position in self.adjacent_positions(p) {
-            self.graph.remove_edge(*p, adjacent_position);
-        }
+        self.separate(&[*p]);
+    }
+
+    /// Makes each of [positions] a galaxy of its own
+    fn separate(&mut self, positions: &[Position]) {
+        self.relabel(positions, |universe| {
+            // Labels past the cells aren't used by any galaxy
+            let first_unused = universe.labels.len() as u32;
+            for (index, p) in positions.iter().enumerate() {
+                universe.set_label(p, first_unused + index as u32);
+            }
+        });
     }
 
     /// Metric of how "cool" is the universe is. Lower is better.
     pub fn get_score(&self) -> i64 {
-        let mut score: i64 = 0;
-
-        // Add points for long, straight, horizontal borders
-        for row in 1..self.height as i32 {
-            let mut current_length: i64 = 0;
-            for col in 0..self.width as i32 {
-                let up = Position::new(row - 1, col);
-                let down = Position::new(row, col);
-                if self.are_neighbours(&up, &down) {
-                    score += current_length.pow(2);
-                    current_length = 0;
-                } else {
-                    current_length += 1;
-                }
-            }
-            score += current_length.pow(2);
+        if self.grid_type() != GridType::Square {
+            return self.get_galaxy_score();
         }
+        // Add points for long, straight borders
+        let mut score = self.current_line_total();
 
-        // Add points for long, straight, vertical borders
-        for col in 1..self.width as i32 {
-            let mut current_length: i64 = 0;
-            for row in 0..self.height as i32 {
-                let left = Position::new(row, col - 1);
-                let right = Position::new(row, col);
-                if self.are_neighbours(&left, &right) {
-                    score += current_length.pow(2);
-                    current_length = 0;
-                } else {
-                    current_length += 1;
-                }
+        let totals = self.current_totals();
+
+        // Add points for big rectangles
+        score += totals.rectangles;
+
+        // Add points for many galaxies
+        score += 3 * totals.count;
+
+        score
+    }
+
+    /// Metric of how "cool" the galaxies are regardless of the shape of the cells,
+    /// adding points for big galaxies and for many galaxies, which balance out around six cells.
+    /// Lower is better.
+    fn get_galaxy_score(&self) -> i64 {
+        let totals = self.current_totals();
+        totals.sizes + 36 * totals.count
+    }
+
+    /// Returns the points of the line of borders [line], see [Universe::line_scores].
+    /// Each straight border adds its squared length, where borders are walls between cells of different galaxies,
+    /// broken by neighbours and the edge of the board.
+    fn line_score(&self, line: usize) -> i64 {
+        let width = self.width();
+        let rows = self.height().saturating_sub(1);
+        // The cells before each border of the line, and how far the cells after them are
+        let (cells, across) = if line < rows {
+            let first = line * width;
+            ((first..first + width).step_by(1), width)
+        } else {
+            let column = line - rows;
+            ((column..self.labels.len()).step_by(width), 1)
+        };
+        let is_wall = |l1: u32, l2: u32| l1 != OUTSIDE && l2 != OUTSIDE && l1 != l2;
+        let mut score = 0;
+        let mut current_length: i64 = 0;
+        for cell in cells {
+            if is_wall(self.labels[cell], self.labels[cell + across]) {
+                current_length += 1;
+            } else {
+                score += current_length.pow(2);
+                current_length = 0;
             }
-            score += current_length.pow(2);
         }
+        score + current_length.pow(2)
+    }
 
-        let galaxies = self.get_galaxies();
+    /// Returns the lines of borders on either side of [cell], see [Universe::line_scores]
+    fn lines_of(&self, cell: usize) -> impl Iterator<Item = usize> {
+        let (row, column) = (cell / self.width(), cell % self.width());
+        let rows = self.height().saturating_sub(1);
+        let columns = self.width().saturating_sub(1);
+        let horizontal = [row.checked_sub(1), Some(row)]
+            .into_iter()
+            .flatten()
+            .filter(move |&line| line < rows);
+        let vertical = [column.checked_sub(1), Some(column)]
+            .into_iter()
+            .flatten()
+            .filter(move |&line| line < columns)
+            .map(move |line| rows + line);
+        horizontal.chain(vertical)
+    }
 
-        // Add points for big rectangles
-        for galaxy in &galaxies {
-            for rect in galaxy.rectangles() {
-                let area = rect.area() as i64;
-                score += area.pow(2);
+    /// Returns the points of all lines of borders, including the pending ones
+    fn current_line_total(&self) -> i64 {
+        self.line_total
+            + self
+                .pending_lines
+                .iter()
+                .map(|&line| self.line_score(line))
+                .sum::<i64>()
+    }
+
+    /// Adds the points of the pending lines, so that they don't have to be computed again
+    fn add_pending_lines(&mut self) {
+        for line in std::mem::take(&mut self.pending_lines) {
+            let score = self.line_score(line);
+            self.line_total += score;
+            self.line_scores[line] = Some(score);
+        }
+    }
+
+    /// Removes what the line of borders [line] adds from the total and marks it as pending
+    fn remove_line(&mut self, line: usize) {
+        if let Some(score) = self.line_scores[line].take() {
+            self.line_total -= score;
+            self.pending_lines.push(line);
+        }
+    }
+
+    /// Returns the totals including the pending galaxies
+    fn current_totals(&self) -> GalaxyTotals {
+        let mut totals = self.totals;
+        for (_, galaxy_totals) in self.pending_totals() {
+            totals += galaxy_totals;
+        }
+        totals
+    }
+
+    /// Adds the totals of the pending galaxies and lines, so that they don't have to be computed again
+    fn add_pending_totals(&mut self) {
+        for (label, totals) in self.pending_totals() {
+            self.totals += totals;
+            self.totals_by_label[label as usize] = totals;
+        }
+        self.pending.clear();
+        self.add_pending_lines();
+    }
+
+    /// Returns the label and totals of each pending galaxy that still exists
+    fn pending_totals(&self) -> Vec<(u32, GalaxyTotals)> {
+        if self.pending.is_empty() {
+            return Vec::new();
+        }
+        let mut labels = self.pending.clone();
+        labels.sort_unstable();
+        labels.dedup();
+        // The pending galaxies don't overlap, so they can share the visited cells
+        let mut visited = CellSet::new(self.labels.len());
+        let mut cells = Vec::with_capacity(FLOOD_CAPACITY);
+        labels
+            .into_iter()
+            // A galaxy is labelled with one of its cells, and its totals are only missing while it's pending
+            .filter(|&label| {
+                self.labels[label as usize] == label
+                    && self.totals_by_label[label as usize].count == 0
+            })
+            .map(|label| {
+                cells.clear();
+                self.flood_into(label as usize, &mut visited, &mut cells);
+                (label, self.galaxy_totals(&cells))
+            })
+            .collect()
+    }
+
+    /// Returns what the galaxy of [cells] adds to the totals
+    fn galaxy_totals(&self, cells: &[usize]) -> GalaxyTotals {
+        let size = cells.len() as i64;
+        let (rectangular, rectangles) = if self.grid_type() != GridType::Square {
+            (0, 0)
+        } else if self.is_rectangle(cells) {
+            (1, size * size)
+        } else {
+            let positions: Vec<Position> = cells.iter().map(|&i| self.position(i)).collect();
+            let rectangles = rectangles_of(&positions)
+                .iter()
+                .map(|rect| (rect.area() as i64).pow(2))
+                .sum();
+            (0, rectangles)
+        };
+        GalaxyTotals {
+            count: 1,
+            rectangular,
+            rectangles,
+            sizes: size * size,
+        }
+    }
+
+    /// Returns whether [cells] fill their bounding rectangle, which is common enough among
+    /// galaxies that it's worth checking before dividing them into rectangles
+    fn is_rectangle(&self, cells: &[usize]) -> bool {
+        let width = self.width();
+        let (mut min_row, mut max_row) = (usize::MAX, 0);
+        let (mut min_column, mut max_column) = (usize::MAX, 0);
+        for &cell in cells {
+            let (row, column) = (cell / width, cell % width);
+            min_row = min_row.min(row);
+            max_row = max_row.max(row);
+            min_column = min_column.min(column);
+            max_column = max_column.max(column);
+        }
+        (max_row + 1 - min_row) * (max_column + 1 - min_column) == cells.len()
+    }
+
+    /// Returns the size of every galaxy, in no particular order
+    pub fn galaxy_sizes(&self) -> Vec<usize> {
+        let mut sizes = vec![0; self.labels.len()];
+        for &label in &self.labels {
+            if label != OUTSIDE {
+                sizes[label as usize] += 1;
             }
         }
+        sizes.retain(|&size| size > 0);
+        sizes
+    }
 
-        // Add points for many galaxies
-        score += 3 * galaxies.len() as i64;
+    /// Returns the number of galaxies that are rectangles, which is 0 on grids that aren't square
+    pub fn count_rectangular_galaxies(&self) -> usize {
+        self.current_totals().rectangular as usize
+    }
 
-        score
+    /// Returns the number of galaxies that aren't valid
+    fn count_invalid_galaxies(&self) -> usize {
+        self.get_galaxies()
+            .iter()
+            .filter(|galaxy| !self.surface.is_valid(galaxy, self.symmetry))
+            .count()
     }
 
+    /// Returns the number of cells that end up with the wrong colour when each galaxy
+    /// is coloured by the majority of the pixels it covers in [picture]
+    pub fn get_picture_mismatch(&self, picture: &Picture) -> i64 {
+        // The number of black and white cells of each galaxy, by label
+        let mut counts: HashMap<u32, (i64, i64)> = HashMap::new();
+        for (index, &label) in self.labels.iter().enumerate() {
+            if label == OUTSIDE {
+                continue;
+            }
+            let count = counts.entry(label).or_default();
+            if picture.is_black(&self.position(index)) {
+                count.0 += 1;
+            } else {
+                count.1 += 1;
+            }
+        }
+        counts
+            .values()
+            .map(|&(black, white)| min(black, white))
+            .sum()
+    }
+
+    /// Joins the galaxies of adjacent positions of [galaxy]
     pub fn add_galaxy(&mut self, galaxy: &Galaxy) {
         for p1 in galaxy.get_positions() {
             for p2 in &self.adjacent_positions(p1) {
-                if galaxy.contains_position(p2) {
-                    self.graph.add_edge(*p1, *p2, ());
+                if galaxy.contains_position(p2) && self.label(p1) != self.label(p2) {
+                    let label = self.label(p1);
+                    let index = self.index(p2).expect("Adjacent positions should be inside");
+                    let cells: Vec<Position> = self
+                        .flood(index, &mut CellSet::new(self.labels.len()))
+                        .iter()
+                        .map(|&i| self.position(i))
+                        .collect();
+                    self.relabel(&cells, |universe| {
+                        for p in &cells {
+                            universe.set_label(p, label);
+                        }
+                    });
                 }
             }
         }
@@ -246,47 +935,320 @@ impl Universe {
      * Returns whether p1 and p2 were successfully made neighbours.
      */
     pub fn make_neighbours(&mut self, p1: &Position, p2: &Position) {
-        let g1 = self.get_galaxy(p1);
-        for p2_adjacent in self.adjacent_positions(p2) {
-            if g1.contains_position(&p2_adjacent) {
-                self.graph.add_edge(*p2, p2_adjacent, ());
-            } else {
-                self.graph.remove_edge(*p2, p2_adjacent);
+        self.join(p1, &[*p2]);
+    }
+
+    /// Joins [positions] into the galaxy of [p1] at once, like [Universe::make_neighbours] for each of them
+    fn join(&mut self, p1: &Position, positions: &[Position]) {
+        let label = self.label(p1);
+        self.relabel(positions, |universe| {
+            for p in positions {
+                universe.set_label(p, label);
+            }
+        });
+    }
+
+    /// Changes the labels of [cells] with [change], and then relabels every galaxy that changed,
+    /// splitting the ones that are no longer connected and marking them as pending.
+    /// Only the galaxies that lose or gain one of the cells change, the other galaxies keep their labels.
+    fn relabel(&mut self, cells: &[Position], change: impl FnOnce(&mut Self)) {
+        let cells: Vec<usize> = cells.iter().filter_map(|p| self.inside_index(p)).collect();
+        let mut old_labels: Vec<u32> = cells.iter().map(|&cell| self.labels[cell]).collect();
+        old_labels.sort_unstable();
+        old_labels.dedup();
+        for &label in &old_labels {
+            self.remove_totals(label);
+        }
+        // Only the borders of the cells can become or stop being walls, since the rest of each galaxy is relabelled as one
+        for &cell in &cells {
+            for line in self.lines_of(cell) {
+                self.remove_line(line);
             }
         }
+        change(self);
+        // What remains of the galaxies that lost cells is adjacent to those cells
+        let remains: Vec<usize> = cells
+            .iter()
+            .flat_map(|&cell| self.adjacency.of(cell))
+            .copied()
+            .filter(|&cell| old_labels.binary_search(&self.labels[cell]).is_ok())
+            .collect();
+        // Find all the galaxies before labelling any of them, since the new labels may still be in use
+        let mut visited = CellSet::new(self.labels.len());
+        // The cells of all the galaxies one after the other, and where each of them starts
+        let mut galaxies = Vec::with_capacity(FLOOD_CAPACITY);
+        let mut starts = Vec::new();
+        for &start in cells.iter().chain(&remains) {
+            if visited.contains(start) {
+                continue;
+            }
+            let first = galaxies.len();
+            starts.push(first);
+            self.flood_into(start, &mut visited, &mut galaxies);
+            // A galaxy that a cell joined is merged into it
+            if galaxies[first..].iter().any(|cell| !cells.contains(cell)) {
+                self.remove_totals(self.labels[start]);
+            }
+        }
+        let ends = starts.iter().skip(1).copied().chain([galaxies.len()]);
+        for (start, end) in starts.iter().copied().zip(ends) {
+            let galaxy = &galaxies[start..end];
+            let label = galaxy[0] as u32;
+            for &cell in galaxy {
+                self.labels[cell] = label;
+            }
+            self.pending.push(label);
+        }
+    }
+
+    /// Removes what the galaxy of [label] adds from the totals
+    fn remove_totals(&mut self, label: u32) {
+        if let Some(totals) = self.totals_by_label.get_mut(label as usize) {
+            self.totals -= std::mem::take(totals);
+        }
     }
 
+    /// Returns the index of [p] in the labels, if it's within the bounding rectangle
+    fn index(&self, p: &Position) -> Option<usize> {
+        let row = usize::try_from(p.row).ok()?;
+        let column = usize::try_from(p.column).ok()?;
+        (row < self.height() && column < self.width()).then(|| row * self.width() + column)
+    }
+
+    /// Returns the index of [p] in the labels, if it's inside the mask
+    fn inside_index(&self, p: &Position) -> Option<usize> {
+        self.index(p).filter(|&index| self.labels[index] != OUTSIDE)
+    }
+
+    fn position(&self, index: usize) -> Position {
+        Position::from((index / self.width(), index % self.width()))
+    }
+
+    fn label(&self, p: &Position) -> u32 {
+        self.index(p).map_or(OUTSIDE, |index| self.labels[index])
+    }
+
+    fn set_label(&mut self, p: &Position, label: u32) {
+        if let Some(index) = self.inside_index(p) {
+            self.labels[index] = label;
+        }
+    }
+
+    /// Returns the cell of [start] followed by the cells connected to it through cells with its label,
+    /// skipping the [visited] cells and adding the returned cells to them
+    fn flood(&self, start: usize, visited: &mut CellSet) -> Vec<usize> {
+        let mut cells = Vec::with_capacity(FLOOD_CAPACITY);
+        self.flood_into(start, visited, &mut cells);
+        cells
+    }
+
+    /// Like [Universe::flood], but appends the cells to [cells] so that their allocation can be reused
+    fn flood_into(&self, start: usize, visited: &mut CellSet, cells: &mut Vec<usize>) {
+        let label = self.labels[start];
+        if label == OUTSIDE {
+            visited.insert(start);
+            cells.push(start);
+            return;
+        }
+        self.flood_where(start, visited, |cell| self.labels[cell] == label, cells)
+    }
+
+    /// Appends the cell of [start] followed by the cells connected to it through cells that [belong] to [cells],
+    /// skipping the [visited] cells and adding the appended cells to them
+    fn flood_where(
+        &self,
+        start: usize,
+        visited: &mut CellSet,
+        belong: impl Fn(usize) -> bool,
+        cells: &mut Vec<usize>,
+    ) {
+        let mut next = cells.len();
+        cells.push(start);
+        visited.insert(start);
+        while let Some(&cell) = cells.get(next) {
+            for &adjacent in self.adjacency.of(cell) {
+                if belong(adjacent) && visited.insert(adjacent) {
+                    cells.push(adjacent);
+                }
+            }
+            next += 1;
+        }
+    }
+
+    /// Returns a random position of the universe, which must not be empty
     pub fn random_position(&self, rng: &mut impl Rng) -> Position {
-        Position::random(self.width, self.height, rng)
+        loop {
+            let p = Position::random(self.width(), self.height(), rng);
+            if self.is_inside(&p) {
+                return p;
+            }
+        }
     }
 
     pub fn adjacent_positions(&self, p: &Position) -> Vec<Position> {
-        vec![p.left(), p.up(), p.right(), p.down()]
-            .iter()
-            .copied()
-            .filter(|&adjacent_position| self.graph.contains_node(adjacent_position))
+        self.surface
+            .adjacent(p)
+            .into_iter()
+            .filter(|adjacent_position| self.is_inside(adjacent_position))
             .collect()
     }
 
     pub fn adjacent_non_neighbours(&self, p: &Position) -> Vec<Position> {
-        self.adjacent_positions(p)
+        let Some(index) = self.inside_index(p) else {
+            return Vec::new();
+        };
+        self.adjacency
+            .of(index)
             .iter()
-            .copied()
-            .filter(|adjacent_position| !self.are_neighbours(p, adjacent_position))
+            .filter(|&&adjacent| self.labels[adjacent] != self.labels[index])
+            .map(|&adjacent| self.position(adjacent))
             .collect()
     }
 
     pub fn are_neighbours(&self, p1: &Position, p2: &Position) -> bool {
-        self.graph.contains_edge(*p1, *p2)
+        p1 != p2
+            && self.is_inside(p1)
+            && self.label(p1) == self.label(p2)
+            && self.surface.are_adjacent(p1, p2)
+    }
+
+    /// Returns the borders between adjacent positions that belong to different galaxies
+    pub fn get_walls(&self) -> BTreeSet<Border> {
+        self.get_positions()
+            .flat_map(|p1| {
+                self.surface
+                    .adjacent(&p1)
+                    .into_iter()
+                    .map(move |p2| (p1, p2))
+            })
+            .filter(|(p1, p2)| p1 < p2 && self.is_inside(p2) && !self.are_neighbours(p1, p2))
+            .map(Border::from)
+            .collect()
+    }
+
+    /// Returns the borders that are walls in only one of the two universes
+    pub fn wall_differences(&self, other: &Universe) -> BTreeSet<Border> {
+        self.get_walls()
+            .symmetric_difference(&other.get_walls())
+            .copied()
+            .collect()
+    }
+
+    /// Returns a board with the walls of this universe, as if a player had solved it
+    pub fn to_board(&self) -> Board {
+        let mut board = Board::new_with_mask(self.mask.clone(), self.topology(), self.grid_type());
+        for wall in self.get_walls() {
+            board.add_wall(wall.p1(), wall.p2());
+        }
+        board
     }
 
     pub fn get_galaxy(&self, p: &Position) -> Galaxy {
-        let search = Dfs::new(&self.graph, *p);
-        Galaxy::from_positions(search.iter(&self.graph))
+        match self.inside_index(p) {
+            Some(index) => {
+                let cells = self.flood(index, &mut CellSet::new(self.labels.len()));
+                Galaxy::from_positions(cells.iter().map(|&i| self.position(i)))
+            }
+            None => Galaxy::from_positions([*p]),
+        }
+    }
+
+    /// Returns the cells of the galaxy of [p], which must be inside
+    fn galaxy_cells(&self, p: &Position) -> GalaxyCells {
+        let index = self.inside_index(p).expect("Position should be inside");
+        let mut set = CellSet::new(self.labels.len());
+        let cells = self.flood(index, &mut set);
+        GalaxyCells { cells, set }
+    }
+
+    fn to_galaxy(&self, cells: &GalaxyCells) -> Galaxy {
+        Galaxy::from_positions(cells.cells.iter().map(|&i| self.position(i)))
+    }
+
+    /// Returns whether [p] is one of [cells]
+    fn cells_contain(&self, cells: &GalaxyCells, p: &Position) -> bool {
+        self.index(p).is_some_and(|index| cells.contains(index))
+    }
+
+    /// Returns the center of [cells] on a plane surface, see [Grid::center]
+    fn plane_center(&self, cells: &GalaxyCells) -> Position {
+        let grid = self.surface.grid();
+        lattice_center(
+            cells
+                .cells
+                .iter()
+                .map(|&i| grid.to_lattice(&self.position(i))),
+        )
+    }
+
+    /// Returns whether [cells] are symmetric around [center] on a plane surface, see [Grid::is_symmetric_around]
+    fn is_plane_symmetric_around(&self, cells: &GalaxyCells, center: &Position) -> bool {
+        let grid = self.surface.grid();
+        grid.allows_center(self.symmetry, center)
+            && cells.cells.iter().all(|&i| {
+                grid.images(self.symmetry, center, &self.position(i))
+                    .iter()
+                    .all(|image| self.cells_contain(cells, image))
+            })
+    }
+
+    /// Returns whether [cells] are symmetric, like [Surface::is_symmetric] for their galaxy
+    fn is_symmetric_cells(&self, cells: &GalaxyCells) -> bool {
+        if !self.surface.is_plane() {
+            return self
+                .surface
+                .is_symmetric(&self.to_galaxy(cells), self.symmetry);
+        }
+        self.is_plane_symmetric_around(cells, &self.plane_center(cells))
+    }
+
+    /// Returns whether [cells] make a valid galaxy, like [Surface::is_valid] for their galaxy
+    fn is_valid_cells(&self, cells: &GalaxyCells) -> bool {
+        if !self.surface.is_plane() {
+            return self.surface.is_valid(&self.to_galaxy(cells), self.symmetry);
+        }
+        let Some(&start) = cells.cells.first() else {
+            return false;
+        };
+        // Connectivity is the most expensive check, so it's done last
+        let center = self.plane_center(cells);
+        // Positions on the plane are already normalized
+        let center_positions = self.surface.grid().center_positions(&center);
+        !center_positions.is_empty()
+            && center_positions
+                .iter()
+                .all(|p| self.cells_contain(cells, p))
+            && self.is_plane_symmetric_around(cells, &center)
+            && {
+                let mut connected = Vec::with_capacity(cells.cells.len());
+                self.flood_where(
+                    start,
+                    &mut CellSet::new(self.labels.len()),
+                    |cell| cells.contains(cell),
+                    &mut connected,
+                );
+                connected.len() == cells.cells.len()
+            }
+    }
+
+    /// Returns the positions that [p] is mapped to around the center of [cells],
+    /// like [Surface::mirror_positions] for their galaxy
+    fn mirrors(&self, cells: &GalaxyCells, p: &Position) -> Vec<Position> {
+        if !self.surface.is_plane() {
+            return self
+                .surface
+                .mirror_positions(&self.to_galaxy(cells), p, self.symmetry);
+        }
+        self.surface
+            .grid()
+            .images(self.symmetry, &self.plane_center(cells), p)
+            .to_vec()
     }
 
     pub fn is_valid(&self) -> bool {
-        self.get_galaxies().iter().all(|galaxy| galaxy.is_valid())
+        self.get_galaxies()
+            .iter()
+            .all(|galaxy| self.surface.is_valid(galaxy, self.symmetry))
     }
 
     pub fn is_outside(&self, p: &Position) -> bool {
@@ -294,7 +1256,7 @@ impl Universe {
     }
 
     pub fn is_inside(&self, p: &Position) -> bool {
-        self.graph.contains_node(*p)
+        self.label(p) != OUTSIDE
     }
 
     pub fn render(&self) -> String {
@@ -302,27 +1264,60 @@ impl Universe {
     }
 
     pub fn get_positions(&self) -> impl Iterator<Item = Position> + '_ {
-        (0..self.height)
-            .flat_map(move |row| (0..self.width).map(move |col| (row, col)))
-            .map(|t| Position::from(t))
+        self.mask.positions()
+    }
+}
+
+impl Universe {
+    /// Writes the universe with each cell labelled by its galaxy, for grids that can't be drawn with bars.
+    /// Odd rows of hexagonal grids are indented by half a cell.
+    fn fmt_labels(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        const LABELS: &[u8] = b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789";
+        let mut labels = HashMap::new();
+        for (index, galaxy) in self.get_galaxies().iter().enumerate() {
+            for p in galaxy.get_positions() {
+                labels.insert(*p, LABELS[index % LABELS.len()] as char);
+            }
+        }
+        for row in 0..self.height() as i32 {
+            if self.grid_type() == GridType::Hexagonal && row % 2 != 0 {
+                write!(f, " ")?;
+            }
+            for column in 0..self.width() as i32 {
+                let label = labels.get(&Position::new(row, column)).unwrap_or(&'.');
+                write!(f, "{label} ")?;
+            }
+            if row + 1 != self.height() as i32 {
+                writeln!(f)?;
+            }
+        }
+        Ok(())
     }
 }
 
 impl Display for Universe {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        for row in 0..=self.height {
-            for column in 0..=self.width {
+        if self.grid_type() != GridType::Square {
+            return self.fmt_labels(f);
+        }
+        // There's a bar between two positions if at least one of them is inside and they're not neighbours
+        // On a wrapping surface, the bars on the wrapped edges show the walls across them
+        let is_bar = |p1: &Position, p2: &Position| {
+            let p1 = self.surface.normalize(p1);
+            let p2 = self.surface.normalize(p2);
+            (self.is_inside(&p1) || self.is_inside(&p2)) && !self.are_neighbours(&p1, &p2)
+        };
+        for row in 0..=self.height() {
+            for column in 0..=self.width() {
                 let bottom_right = Position::from((row, column));
                 let bottom_left = bottom_right.left();
                 let top_left = bottom_left.up();
                 let top_right = bottom_right.up();
 
-                let bar_top = row != 0 && !self.are_neighbours(&top_left, &top_right);
-                let bar_right =
-                    column != self.width && !self.are_neighbours(&top_right, &bottom_right);
-                let bar_bottom =
-                    row != self.height && !self.are_neighbours(&bottom_left, &bottom_right);
-                let bar_left = column != 0 && !self.are_neighbours(&top_left, &bottom_left);
+                let bar_top = is_bar(&top_left, &top_right);
+                let bar_right = is_bar(&top_right, &bottom_right);
+                let bar_bottom = is_bar(&bottom_left, &bottom_right);
+                let bar_left = is_bar(&top_left, &bottom_left);
                 match (bar_top, bar_right, bar_bottom, bar_left) {
                     (false, false, false, false) => write!(f, "  ")?,
                     (false, false, false, true) => write!(f, "╴ ")?,
@@ -342,8 +1337,8 @@ impl Display for Universe {
                     (true, true, true, true) => write!(f, "┼─")?,
                 }
             }
-            if row != self.height {
-                write!(f, "\n")?;
+            if row != self.height() {
+                writeln!(f)?;
             }
         }
         Ok(())
@@ -366,15 +1361,240 @@ impl From<&[Galaxy]> for Universe {
             .unwrap_or(0) as usize;
         let mut universe = Universe::new(width, height);
         for g in galaxies {
-            for p1 in g.get_positions() {
-                for p2 in &p1.adjacent() {
-                    if g.contains_position(p2) {
-                        universe.graph.add_edge(*p1, *p2, ());
-                    }
-                }
-            }
+            universe.add_galaxy(g);
         }
 
         universe
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::model::galaxy::Galaxy;
+    use crate::model::generation_error::GenerationError;
+    use crate::model::grid::GridType;
+    use crate::model::mask::Mask;
+    use crate::model::picture::Picture;
+    use crate::model::position::Position;
+    use crate::model::symmetry::Symmetry;
+    use crate::model::topology::Topology;
+    use crate::model::universe::Universe;
+
+    #[test]
+    fn picture_mismatch_should_count_minority_cells() {
+        let galaxies = [
+            Galaxy::from_positions([
+                Position::new(0, 0),
+                Position::new(0, 1),
+                Position::new(0, 2),
+            ]),
+            Galaxy::from_positions([
+                Position::new(1, 0),
+                Position::new(1, 1),
+                Position::new(1, 2),
+            ]),
+        ];
+        let universe = Universe::from(galaxies.as_slice());
+        let picture = Picture::from_rows(&[vec![true, false, true], vec![false, false, false]]);
+        assert_eq!(universe.get_picture_mismatch(&picture), 1);
+    }
+
+    #[test]
+    fn generated_universe_should_be_valid_for_every_symmetry() {
+        for symmetry in Symmetry::ALL {
+            let universe = Universe::generate_with_symmetry(5, 5, symmetry);
+            assert!(universe.is_valid(), "{symmetry}\n{universe}");
+        }
+    }
+
+    #[test]
+    fn generated_universe_should_be_valid_for_every_topology() {
+        for topology in Topology::ALL {
+            let universe = Universe::generate_with_mask(
+                Mask::full(5, 5),
+                Symmetry::default(),
+                topology,
+                GridType::default(),
+            )
+            .unwrap();
+            assert!(universe.is_valid(), "{topology}\n{universe}");
+        }
+    }
+
+    #[test]
+    fn generated_universe_should_be_valid_for_every_grid() {
+        for grid in GridType::ALL {
+            let universe = Universe::generate_with_mask(
+                Mask::full(6, 6),
+                Symmetry::default(),
+                Topology::default(),
+                grid,
+            )
+            .unwrap();
+            assert!(universe.is_valid(), "{grid}\n{universe}");
+            assert_eq!(universe.grid_type(), grid);
+        }
+    }
+
+    #[test]
+    fn boards_without_symmetric_galaxies_should_be_rejected() {
+        let generate = |mask: Mask, symmetry, grid| {
+            Universe::generate_with_mask(mask, symmetry, Topology::default(), grid).err()
+        };
+        assert_eq!(
+            generate(Mask::full(0, 0), Symmetry::default(), GridType::Square),
+            Some(GenerationError::EmptyMask)
+        );
+        assert_eq!(
+            generate(Mask::full(4, 4), Symmetry::FourFold, GridType::Hexagonal),
+            Some(GenerationError::UnsupportedSymmetry(
+                GridType::Hexagonal,
+                Symmetry::FourFold
+            ))
+        );
+        assert_eq!(
+            Universe::generate_with_mask(
+                Mask::full(4, 4),
+                Symmetry::default(),
+                Topology::Torus,
+                GridType::Hexagonal
+            )
+            .err(),
+            Some(GenerationError::UnsupportedTopology(
+                GridType::Hexagonal,
+                Topology::Torus
+            ))
+        );
+        for mask in [Mask::full(1, 1), Mask::circle(3), Mask::heart(5)] {
+            assert_eq!(
+                generate(mask, Symmetry::default(), GridType::Triangular),
+                Some(GenerationError::Untileable)
+            );
+        }
+    }
+
+    #[test]
+    fn generated_universe_should_only_cover_mask() {
+        let mask = Mask::circle(6).without(&Position::new(2, 2));
+        let universe = Universe::generate_with_mask(
+            mask.clone(),
+            Symmetry::default(),
+            Topology::default(),
+            GridType::default(),
+        )
+        .unwrap();
+        assert!(universe.is_valid(), "{universe}");
+        assert!(universe.get_positions().eq(mask.positions()));
+        let covered: usize = universe.get_galaxies().iter().map(|g| g.size()).sum();
+        assert_eq!(covered, mask.size());
+    }
+
+    #[test]
+    fn generated_universe_should_approximate_picture() {
+        let picture = Picture::from_rows(&[
+            vec![false, false, false, false],
+            vec![false, true, true, false],
+            vec![false, true, true, false],
+            vec![false, false, false, false],
+        ]);
+        for seed in 0..10 {
+            let universe = Universe::generate_with_picture_and_seed(4, 4, &picture, seed);
+            assert!(universe.is_valid());
+            assert!(universe.get_picture_mismatch(&picture) <= 2);
+        }
+    }
+
+    #[test]
+    fn generated_universe_should_follow_picture_while_climbing() {
+        // A ring, whose edges the galaxies of an ordinary universe cut across
+        let rows: Vec<Vec<bool>> = (0..10)
+            .map(|row| {
+                (0..10)
+                    .map(|column| {
+                        let distance = (row * 2 - 9_i32).pow(2) + (column * 2 - 9_i32).pow(2);
+                        (25..=64).contains(&distance)
+                    })
+                    .collect()
+            })
+            .collect();
+        let picture = Picture::from_rows(&rows);
+        for seed in 0..3 {
+            let universe = Universe::generate_with_picture_and_seed(10, 10, &picture, seed);
+            let ordinary = Universe::generate_with_seed(
+                Mask::full(10, 10),
+                Symmetry::default(),
+                Topology::default(),
+                GridType::default(),
+                seed,
+            )
+            .unwrap();
+            assert!(universe.is_valid());
+            assert!(universe.get_picture_mismatch(&picture) <= 3, "{universe}");
+            assert!(
+                universe.get_picture_mismatch(&picture) < ordinary.get_picture_mismatch(&picture)
+            );
+        }
+    }
+
+    #[test]
+    fn same_seed_should_generate_same_universe() {
+        let generate = |seed| {
+            Universe::generate_with_seed(
+                Mask::full(6, 6),
+                Symmetry::default(),
+                Topology::default(),
+                GridType::default(),
+                seed,
+            )
+            .unwrap()
+        };
+        assert_eq!(generate(7).get_walls(), generate(7).get_walls());
+    }
+
+    #[test]
+    fn incremental_score_should_match_rebuilt_universe() {
+        let universe = Universe::generate_with_seed(
+            Mask::full(8, 8),
+            Symmetry::default(),
+            Topology::default(),
+            GridType::default(),
+            3,
+        )
+        .unwrap();
+        let galaxies = universe.get_galaxies();
+        let rebuilt = Universe::from(galaxies.as_slice());
+        assert_eq!(universe.get_score(), rebuilt.get_score());
+        let lines = universe.line_scores.len();
+        assert_eq!(
+            universe.current_line_total(),
+            (0..lines)
+                .map(|line| universe.line_score(line))
+                .sum::<i64>()
+        );
+        assert_eq!(
+            universe.count_rectangular_galaxies(),
+            galaxies
+                .iter()
+                .filter(|galaxy| galaxy.rectangles().len() == 1)
+                .count()
+        );
+        assert_eq!(rebuilt.get_galaxies().len(), galaxies.len());
+    }
+
+    #[test]
+    fn removing_neighbours_should_split_galaxy() {
+        let galaxy = Galaxy::from_positions([
+            Position::new(0, 0),
+            Position::new(0, 1),
+            Position::new(0, 2),
+        ]);
+        let mut universe = Universe::from([galaxy].as_slice());
+        assert_eq!(universe.get_galaxies().len(), 1);
+        universe.remove_all_neighbours(&Position::new(0, 1));
+        assert_eq!(universe.get_galaxies().len(), 3);
+        assert!(!universe.are_neighbours(&Position::new(0, 0), &Position::new(0, 1)));
+        universe.make_neighbours(&Position::new(0, 0), &Position::new(0, 1));
+        assert_eq!(universe.get_galaxies().len(), 2);
+        assert!(universe.are_neighbours(&Position::new(0, 0), &Position::new(0, 1)));
+    }
+}
@@ -0,0 +1,608 @@
+use crate::model::board::Board;
+use crate::model::border::Border;
+use crate::model::galaxy::Galaxy;
+use crate::model::grid::Grid;
+use crate::model::mask::Mask;
+use crate::model::objective::{CenterColor, Objective};
+use crate::model::position::Position;
+use crate::model::topology::Surface;
+use crate::model::universe::Universe;
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write;
+
+/// How thick the walls are compared to the distance between adjacent cells
+const WALL_CELL_RATIO: f64 = 0.1;
+
+/// How thick the lines between the cells of a galaxy are compared to the walls
+const GRID_LINE_RATIO: f64 = 0.2;
+
+/// Maps the drawing units of a [Grid] onto a square of the given size, with room for the walls on the edge
+#[derive(Clone, Copy)]
+pub struct Layout {
+    pub grid: &'static dyn Grid,
+    /// The length of one drawing unit
+    pub scale: f64,
+    /// The distance between the centers of adjacent cells
+    pub cell_distance: f64,
+    pub wall_size: f64,
+    pub offset: (f64, f64),
+}
+
+impl Layout {
+    /// Fits a board of [width] by [height] cells on [surface] into a square with sides of [size]
+    pub fn new(surface: &Surface, width: usize, height: usize, size: f64) -> Layout {
+        let grid = surface.grid();
+        let origin = Position::new(0, 0);
+        let cell_distance = distance(
+            centroid(&grid.polygon(&origin)),
+            centroid(&grid.polygon(&grid.adjacent(&origin)[0])),
+        );
+        let (extent_x, extent_y) = grid.extent(width, height);
+        let extent = extent_x.max(extent_y);
+        // Walls are a fraction of the cells they separate, and the walls on the edge stick out by half a wall
+        let wall_units = WALL_CELL_RATIO / (1.0 + WALL_CELL_RATIO) * cell_distance;
+        let scale = size / (extent + wall_units);
+        let wall_size = wall_units * scale;
+        let offset = (
+            wall_size / 2.0 + (extent - extent_x) * scale / 2.0,
+            wall_size / 2.0 + (extent - extent_y) * scale / 2.0,
+        );
+        Layout {
+            grid,
+            scale,
+            cell_distance: cell_distance * scale,
+            wall_size,
+            offset,
+        }
+    }
+
+    /// Returns the coordinates of a point in drawing units
+    pub fn point(&self, (x, y): (f64, f64)) -> (f64, f64) {
+        (
+            self.offset.0 + x * self.scale,
+            self.offset.1 + y * self.scale,
+        )
+    }
+
+    pub fn polygon(&self, p: &Position) -> Vec<(f64, f64)> {
+        self.grid
+            .polygon(p)
+            .into_iter()
+            .map(|point| self.point(point))
+            .collect()
+    }
+
+    pub fn center_point(&self, center: &Position) -> (f64, f64) {
+        self.point(self.grid.center_point(center))
+    }
+
+    /// Returns the radius of the circle of a center
+    pub fn center_radius(&self) -> f64 {
+        let cell_size = self.cell_distance - self.wall_size;
+        cell_size / 2.5 - self.wall_size
+    }
+
+    /// Returns the ends of the side shared by [p1] and [p2], which are adjacent but may lie outside the board
+    pub fn shared_side(&self, p1: &Position, p2: &Position) -> ((f64, f64), (f64, f64)) {
+        let polygon = self.polygon(p2);
+        let shared: Vec<(f64, f64)> = self
+            .polygon(p1)
+            .into_iter()
+            .filter(|&a| polygon.iter().any(|&b| distance(a, b) < 1e-6))
+            .collect();
+        (shared[0], shared[1])
+    }
+
+    /// Returns the line cap that fills in the corners where walls meet,
+    /// square ones for the right angles of square cells
+    pub fn linecap(&self, p: &Position) -> &'static str {
+        if self.grid.corners(p).len() == 4 {
+            "square"
+        } else {
+            "round"
+        }
+    }
+}
+
+/// How a standalone SVG document is drawn
+#[derive(Debug, Clone)]
+pub struct SvgOptions {
+    /// The distance between the centers of adjacent cells
+    pub cell_size: f64,
+    pub background: String,
+    pub wall_color: String,
+    /// The colour of the thin lines between cells that aren't separated by a wall
+    pub grid_color: String,
+    pub center_color: String,
+    /// The colour of the centers whose galaxies are shaded in picture puzzles
+    pub black_center_color: String,
+    /// Whether galaxies are filled with the colours of [SvgOptions::palette],
+    /// where adjacent galaxies get different colours
+    pub colored_galaxies: bool,
+    pub palette: Vec<String>,
+}
+
+impl Default for SvgOptions {
+    fn default() -> Self {
+        SvgOptions {
+            cell_size: 40.0,
+            background: "#ffffff".to_string(),
+            wall_color: "#202020".to_string(),
+            grid_color: "#c8c8c8".to_string(),
+            center_color: "#ffffff".to_string(),
+            black_center_color: "#202020".to_string(),
+            colored_galaxies: false,
+            palette: [
+                "#f4c7c3", "#fce8b2", "#b7e1cd", "#c6dafc", "#e1bee7", "#ffe0b2",
+            ]
+            .map(String::from)
+            .to_vec(),
+        }
+    }
+}
+
+/// A shape of a [Drawing], in the coordinates of the drawing
+#[derive(Debug, Clone, PartialEq)]
+pub enum Shape {
+    Polygon {
+        points: Vec<(f64, f64)>,
+        fill: String,
+    },
+    Line {
+        start: (f64, f64),
+        end: (f64, f64),
+        stroke: String,
+        width: f64,
+        /// How the ends are drawn, `butt`, `round` or `square` as in SVG
+        linecap: &'static str,
+    },
+    Circle {
+        center: (f64, f64),
+        radius: f64,
+        fill: String,
+        stroke: String,
+        width: f64,
+    },
+    /// Text that is centered on [Shape::Text::position]
+    Text {
+        position: (f64, f64),
+        text: String,
+        size: f64,
+        fill: String,
+    },
+}
+
+impl Shape {
+    /// Returns this shape scaled by [scale] and then moved by [offset]
+    fn transformed(&self, offset: (f64, f64), scale: f64) -> Shape {
+        let point = |(x, y): (f64, f64)| (offset.0 + x * scale, offset.1 + y * scale);
+        match self.clone() {
+            Shape::Polygon { points, fill } => Shape::Polygon {
+                points: points.into_iter().map(point).collect(),
+                fill,
+            },
+            Shape::Line {
+                start,
+                end,
+                stroke,
+                width,
+                linecap,
+            } => Shape::Line {
+                start: point(start),
+                end: point(end),
+                stroke,
+                width: width * scale,
+                linecap,
+            },
+            Shape::Circle {
+                center,
+                radius,
+                fill,
+                stroke,
+                width,
+            } => Shape::Circle {
+                center: point(center),
+                radius: radius * scale,
+                fill,
+                stroke,
+                width: width * scale,
+            },
+            Shape::Text {
+                position,
+                text,
+                size,
+                fill,
+            } => Shape::Text {
+                position: point(position),
+                text,
+                size: size * scale,
+                fill,
+            },
+        }
+    }
+
+    fn to_svg(&self) -> String {
+        match self {
+            Shape::Polygon { points, fill } => {
+                format!(
+                    r#"<polygon points="{}" fill="{fill}"/>"#,
+                    format_points(points)
+                )
+            }
+            Shape::Line {
+                start: (x1, y1),
+                end: (x2, y2),
+                stroke,
+                width,
+                linecap,
+            } => {
+                let linecap = match *linecap {
+                    "butt" => String::new(),
+                    linecap => format!(r#" stroke-linecap="{linecap}""#),
+                };
+                format!(
+                    r#"<line x1="{x1}" y1="{y1}" x2="{x2}" y2="{y2}" stroke="{stroke}" stroke-width="{width}"{linecap}/>"#
+                )
+            }
+            Shape::Circle {
+                center: (cx, cy),
+                radius,
+                fill,
+                stroke,
+                width,
+            } => format!(
+                r#"<circle cx="{cx}" cy="{cy}" r="{radius}" fill="{fill}" stroke="{stroke}" stroke-width="{width}"/>"#
+            ),
+            Shape::Text {
+                position: (x, y),
+                text,
+                size,
+                fill,
+            } => format!(
+                r#"<text x="{x}" y="{y}" fill="{fill}" font-family="sans-serif" font-size="{size}" text-anchor="middle" dominant-baseline="central">{}</text>"#,
+                escape(text)
+            ),
+        }
+    }
+}
+
+/// Shapes on a canvas of [Drawing::width] by [Drawing::height], drawn in order
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Drawing {
+    pub width: f64,
+    pub height: f64,
+    pub shapes: Vec<Shape>,
+}
+
+impl Drawing {
+    pub fn new(width: f64, height: f64) -> Drawing {
+        Drawing {
+            width,
+            height,
+            shapes: Vec::new(),
+        }
+    }
+
+    pub fn push(&mut self, shape: Shape) {
+        self.shapes.push(shape);
+    }
+
+    /// Adds the shapes of [other], scaled by [scale] and then moved by [offset]
+    pub fn place(&mut self, other: &Drawing, offset: (f64, f64), scale: f64) {
+        self.shapes.extend(
+            other
+                .shapes
+                .iter()
+                .map(|shape| shape.transformed(offset, scale)),
+        );
+    }
+
+    /// Returns a standalone SVG document of the drawing
+    pub fn to_svg(&self) -> String {
+        let (width, height) = (self.width, self.height);
+        let mut svg = format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" viewBox="0 0 {width} {height}">
+"#
+        );
+        for shape in &self.shapes {
+            let _ = writeln!(svg, "{}", shape.to_svg());
+        }
+        svg.push_str("</svg>\n");
+        svg
+    }
+}
+
+/// Draws the blank puzzle of [objective] on the cells of [mask]
+pub fn objective_drawing(mask: &Mask, objective: &Objective, options: &SvgOptions) -> Drawing {
+    let surface = Surface::new(
+        objective.grid,
+        objective.topology,
+        mask.width(),
+        mask.height(),
+    );
+    let mut sketch = Sketch::new(mask, surface, options);
+    sketch.cells(&[]);
+    sketch.walls(|border| objective.is_given_wall(border));
+    sketch.centers(objective);
+    sketch.drawing
+}
+
+/// Draws a puzzle in progress, where the completed galaxies are coloured if the options say so
+pub fn board_drawing(board: &Board, objective: &Objective, options: &SvgOptions) -> Drawing {
+    let mut sketch = Sketch::new(board.get_mask(), *board.get_surface(), options);
+    let galaxies: Vec<Galaxy> = board
+        .get_completed_galaxies(objective)
+        .into_iter()
+        .map(|(_, galaxy)| galaxy)
+        .collect();
+    sketch.cells(&galaxies);
+    sketch.walls(|border| board.is_wall(border.p1(), border.p2()));
+    sketch.centers(objective);
+    sketch.drawing
+}
+
+/// Draws the solution [universe], with a center in every galaxy
+pub fn universe_drawing(universe: &Universe, options: &SvgOptions) -> Drawing {
+    let mut sketch = Sketch::new(universe.mask(), *universe.surface(), options);
+    let galaxies = universe.get_galaxies();
+    sketch.cells(&galaxies);
+    sketch.walls(|border| !universe.are_neighbours(&border.p1(), &border.p2()));
+    for galaxy in &galaxies {
+        if let Some(center) = universe.surface().center(galaxy) {
+            sketch.center(&center, CenterColor::White, None);
+        }
+    }
+    sketch.drawing
+}
+
+/// Returns an SVG document of the blank puzzle, see [objective_drawing]
+pub fn objective_svg(mask: &Mask, objective: &Objective, options: &SvgOptions) -> String {
+    objective_drawing(mask, objective, options).to_svg()
+}
+
+/// Returns an SVG document of the puzzle in progress, see [board_drawing]
+pub fn board_svg(board: &Board, objective: &Objective, options: &SvgOptions) -> String {
+    board_drawing(board, objective, options).to_svg()
+}
+
+/// Returns an SVG document of the solution, see [universe_drawing]
+pub fn universe_svg(universe: &Universe, options: &SvgOptions) -> String {
+    universe_drawing(universe, options).to_svg()
+}
+
+/// A drawing of a board being drawn, layer by layer
+struct Sketch<'a> {
+    mask: &'a Mask,
+    surface: Surface,
+    layout: Layout,
+    options: &'a SvgOptions,
+    drawing: Drawing,
+}
+
+impl<'a> Sketch<'a> {
+    fn new(mask: &'a Mask, surface: Surface, options: &'a SvgOptions) -> Sketch<'a> {
+        let unit = Layout::new(&surface, mask.width(), mask.height(), 1.0);
+        // Scale the drawing so that adjacent cells are the requested size apart
+        let size = options.cell_size / unit.cell_distance;
+        Sketch {
+            mask,
+            surface,
+            layout: Layout::new(&surface, mask.width(), mask.height(), size),
+            options,
+            drawing: Drawing::new(size, size),
+        }
+    }
+
+    /// Draws the cells, where the cells of [galaxies] are coloured if the options say so
+    fn cells(&mut self, galaxies: &[Galaxy]) {
+        let colors = if self.options.colored_galaxies {
+            self.galaxy_colors(galaxies)
+        } else {
+            HashMap::new()
+        };
+        for p in self.mask.positions() {
+            let fill = colors.get(&p).unwrap_or(&self.options.background);
+            self.drawing.push(Shape::Polygon {
+                points: self.layout.polygon(&p),
+                fill: fill.clone(),
+            });
+        }
+    }
+
+    /// Colours the cells of each galaxy, such that adjacent galaxies get different colours when possible
+    fn galaxy_colors(&self, galaxies: &[Galaxy]) -> HashMap<Position, String> {
+        let palette = &self.options.palette;
+        let mut color_by_position: HashMap<Position, usize> = HashMap::new();
+        for galaxy in galaxies {
+            let neighbour_colors: HashSet<usize> = galaxy
+                .get_positions()
+                .flat_map(|p| self.surface.adjacent(p))
+                .filter_map(|q| color_by_position.get(&q).copied())
+                .collect();
+            let color = (0..palette.len())
+                .find(|color| !neighbour_colors.contains(color))
+                .unwrap_or(0);
+            for p in galaxy.get_positions() {
+                color_by_position.insert(*p, color);
+            }
+        }
+        color_by_position
+            .into_iter()
+            .filter_map(|(p, color)| Some((p, palette.get(color)?.clone())))
+            .collect()
+    }
+
+    /// Draws the edge of the board, thin lines between cells and the borders that [is_wall] says are walls.
+    /// Walls across a wrapped edge are drawn on both edges.
+    fn walls(&mut self, is_wall: impl Fn(&Border) -> bool) {
+        let grid = self.surface.grid();
+        let wall_size = self.layout.wall_size;
+        let mut lines = Vec::new();
+        let mut walls = Vec::new();
+        for p1 in self.mask.positions() {
+            let linecap = self.layout.linecap(&p1);
+            for p2 in grid.adjacent(&p1) {
+                let wrapped_p2 = self.surface.normalize(&p2);
+                let (start, end) = self.layout.shared_side(&p1, &p2);
+                let wall = Shape::Line {
+                    start,
+                    end,
+                    stroke: self.options.wall_color.clone(),
+                    width: wall_size,
+                    linecap,
+                };
+                if !self.mask.contains(&wrapped_p2) {
+                    walls.push(wall);
+                } else if p2 != wrapped_p2 || p1 < p2 {
+                    if is_wall(&Border::new(p1, wrapped_p2)) {
+                        walls.push(wall);
+                    } else {
+                        lines.push(Shape::Line {
+                            start,
+                            end,
+                            stroke: self.options.grid_color.clone(),
+                            width: wall_size * GRID_LINE_RATIO,
+                            linecap: "butt",
+                        });
+                    }
+                }
+            }
+        }
+        self.drawing.shapes.extend(lines);
+        self.drawing.shapes.extend(walls);
+    }
+
+    fn centers(&mut self, objective: &Objective) {
+        for gc in &objective.centers {
+            self.center(&gc.position, gc.color, gc.size);
+        }
+    }
+
+    fn center(&mut self, center: &Position, color: CenterColor, size: Option<usize>) {
+        let position = self.layout.center_point(center);
+        let radius = self.layout.center_radius();
+        let (fill, text_color) = match color {
+            CenterColor::White => (&self.options.center_color, &self.options.wall_color),
+            CenterColor::Black => (&self.options.black_center_color, &self.options.center_color),
+        };
+        self.drawing.push(Shape::Circle {
+            center: position,
+            radius,
+            fill: fill.clone(),
+            stroke: self.options.wall_color.clone(),
+            width: self.layout.wall_size * GRID_LINE_RATIO,
+        });
+        if let Some(size) = size {
+            self.drawing.push(Shape::Text {
+                position,
+                text: size.to_string(),
+                size: radius * 1.2,
+                fill: text_color.clone(),
+            });
+        }
+    }
+}
+
+/// Escapes the characters that have a meaning in XML
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+pub fn distance((x1, y1): (f64, f64), (x2, y2): (f64, f64)) -> f64 {
+    (x1 - x2).hypot(y1 - y2)
+}
+
+pub fn centroid(points: &[(f64, f64)]) -> (f64, f64) {
+    let n = points.len() as f64;
+    let x = points.iter().map(|(x, _)| x).sum::<f64>() / n;
+    let y = points.iter().map(|(_, y)| y).sum::<f64>() / n;
+    (x, y)
+}
+
+/// Formats points as the `points` attribute of a polygon
+pub fn format_points(points: &[(f64, f64)]) -> String {
+    points
+        .iter()
+        .map(|(x, y)| format!("{x},{y}"))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::board::Board;
+    use crate::model::galaxy::Galaxy;
+    use crate::model::objective::Objective;
+    use crate::model::position::Position;
+    use crate::model::universe::Universe;
+    use crate::svg::{board_svg, objective_svg, universe_svg, SvgOptions};
+
+    fn two_galaxies() -> Universe {
+        let galaxies = [
+            Galaxy::from_positions([Position::new(0, 0), Position::new(0, 1)]),
+            Galaxy::from_positions([Position::new(1, 0), Position::new(1, 1)]),
+        ];
+        Universe::from(galaxies.as_slice())
+    }
+
+    #[test]
+    fn puzzle_should_have_a_circle_per_center() {
+        let universe = two_galaxies();
+        let objective = Objective::generate(&universe);
+        let svg = objective_svg(universe.mask(), &objective, &SvgOptions::default());
+        assert!(svg.starts_with("<?xml"));
+        assert!(svg.trim_end().ends_with("</svg>"));
+        assert_eq!(svg.matches("<circle").count(), 2);
+        assert_eq!(svg.matches("<polygon").count(), 4);
+    }
+
+    #[test]
+    fn solution_should_draw_the_wall_between_galaxies() {
+        let options = SvgOptions::default();
+        let universe = two_galaxies();
+        let objective = Objective::generate(&universe);
+        let blank = objective_svg(universe.mask(), &objective, &options);
+        let solution = universe_svg(&universe, &options);
+        // The edge has 8 sides, and the solution has 2 more walls
+        assert_eq!(blank.matches("stroke-linecap").count(), 8);
+        assert_eq!(solution.matches("stroke-linecap").count(), 10);
+    }
+
+    #[test]
+    fn colored_galaxies_should_get_different_colors() {
+        let options = SvgOptions {
+            colored_galaxies: true,
+            ..SvgOptions::default()
+        };
+        let universe = two_galaxies();
+        let svg = universe_svg(&universe, &options);
+        assert_eq!(svg.matches(&options.palette[0]).count(), 2);
+        assert_eq!(svg.matches(&options.palette[1]).count(), 2);
+
+        // Nothing is completed on an empty board
+        let board = Board::new(2, 2);
+        let svg = board_svg(&board, &Objective::generate(&universe), &options);
+        assert!(!svg.contains(&options.palette[0]));
+    }
+
+    #[test]
+    fn cell_size_should_set_the_document_size() {
+        let options = SvgOptions {
+            cell_size: 10.0,
+            ..SvgOptions::default()
+        };
+        let svg = universe_svg(&two_galaxies(), &options);
+        let width: f64 = svg
+            .split("width=\"")
+            .nth(1)
+            .and_then(|rest| rest.split('"').next())
+            .unwrap()
+            .parse()
+            .unwrap();
+        assert!((20.0..22.0).contains(&width), "{width}");
+    }
+}